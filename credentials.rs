@@ -1,4 +0,0 @@
-pub const CLIENTID: &str = "";
-pub const TOKEN: &str = "";
-pub const CHANID: &str = "";
-pub const TESTCH: i64 = 12826;
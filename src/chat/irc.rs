@@ -0,0 +1,415 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+//! Twitch IRC message tags and commands
+//!
+//! https://dev.twitch.tv/docs/irc/tags
+
+use std::{
+    collections::HashMap,
+    ops::RangeInclusive,
+};
+
+use crate::{
+    response::{
+        ApiError,
+        TwitchResult,
+    },
+    util::normalize_login,
+};
+
+/// Maximum length of a chat message in characters
+pub const MAX_MESSAGE_LEN: usize = 500;
+
+/// Builds a `PRIVMSG` replying to the message with id `parent_msg_id`
+///
+/// The id is the `id` tag of the message replied to. `channel` has to be a
+/// valid login, with or without the leading `#`. The returned line includes
+/// the trailing `\r\n`.
+pub fn reply(
+    channel: &str,
+    parent_msg_id: &str,
+    text: &str,
+) -> TwitchResult<String>
+{
+    check_message(text)?;
+    if parent_msg_id.is_empty()
+        || parent_msg_id.contains(&[' ', ';', '\r', '\n'][..])
+    {
+        return Err(ApiError::invalid_input("invalid parent message id"));
+    }
+    Ok(format!(
+        "@reply-parent-msg-id={} PRIVMSG {} :{}\r\n",
+        parent_msg_id,
+        channel_name(channel)?,
+        text
+    ))
+}
+
+/// Builds the `PRIVMSG` sent by `/me <text>`
+///
+/// `channel` has to be a valid login, with or without the leading `#`. The
+/// returned line includes the trailing `\r\n`.
+pub fn action(
+    channel: &str,
+    text: &str,
+) -> TwitchResult<String>
+{
+    check_message(text)?;
+    Ok(format!(
+        "PRIVMSG {} :\x01ACTION {}\x01\r\n",
+        channel_name(channel)?,
+        text
+    ))
+}
+
+fn check_message(text: &str) -> TwitchResult<()> {
+    if text.chars().count() > MAX_MESSAGE_LEN {
+        return Err(ApiError::invalid_input(
+            "chat messages can't be longer than 500 characters",
+        ));
+    }
+    if text.contains(&['\r', '\n'][..]) {
+        return Err(ApiError::invalid_input(
+            "chat messages can't contain line breaks",
+        ));
+    }
+    Ok(())
+}
+
+/// Channels are joined as `#login`, anything but a login could smuggle in
+/// further commands
+fn channel_name(channel: &str) -> TwitchResult<String> {
+    let login = normalize_login(channel.trim_start_matches('#'))?;
+    Ok(format!("#{}", login))
+}
+
+///////////////////////////////////////
+// Tags
+///////////////////////////////////////
+/// The tags Twitch prepends to IRC messages when the `twitch.tv/tags`
+/// capability is requested
+///
+/// Tags without a dedicated field are kept in `other`, so tags added by
+/// Twitch later on are still accessible.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Tags {
+    pub badge_info: Vec<Badge>,
+    pub badges: Vec<Badge>,
+    /// Hex RGB color, e.g. `#0D4200`, `None` if the user never set one
+    pub color: Option<String>,
+    pub display_name: Option<String>,
+    pub emotes: Vec<Emote>,
+    pub user_id: Option<String>,
+    pub room_id: Option<String>,
+    pub subscriber: bool,
+    pub moderator: bool,
+    /// Amount of bits cheered with the message
+    pub bits: Option<u32>,
+    /// Whether the user has Turbo, `None` if the message has no `turbo` tag
+    ///
+    /// Chat tags are the only place Twitch exposes this, Helix users don't
+    /// carry it.
+    pub turbo: Option<bool>,
+    /// Whether the user has Prime, from the `premium` badge, `None` if the
+    /// message has no `badges` tag
+    pub has_prime: Option<bool>,
+    /// Id of the channel the message was sent in during a shared chat
+    /// session, `None` outside of shared chat
+    pub source_room_id: Option<String>,
+    /// Id of the message in the channel it was sent in, `None` outside of
+    /// shared chat
+    pub source_id: Option<String>,
+    pub other: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Badge {
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Emote {
+    pub id: String,
+    /// Character positions of the emote in the message, inclusive
+    pub ranges: Vec<RangeInclusive<usize>>,
+}
+
+impl Tags {
+    /// Parses the tags part of an IRC message
+    ///
+    /// Accepts the tags with or without the leading `@`, as well as a whole
+    /// raw message, of which only the tags are read.
+    pub fn parse(raw: &str) -> Tags {
+        let raw = raw.strip_prefix('@').unwrap_or(raw);
+        let raw = raw.split(' ').next().unwrap_or_default();
+
+        let mut tags = Tags::default();
+        for tag in raw.split(';').filter(|t| !t.is_empty()) {
+            let mut kv = tag.splitn(2, '=');
+            let key = kv.next().unwrap_or_default();
+            let value = unescape(kv.next().unwrap_or_default());
+            let non_empty = || Some(value.clone()).filter(|v| !v.is_empty());
+
+            match key {
+                "badge-info" => tags.badge_info = parse_badges(&value),
+                "badges" => {
+                    tags.badges = parse_badges(&value);
+                    tags.has_prime =
+                        Some(tags.badges.iter().any(|b| b.name == "premium"));
+                }
+                "color" => tags.color = non_empty(),
+                "display-name" => tags.display_name = non_empty(),
+                "emotes" => tags.emotes = parse_emotes(&value),
+                "user-id" => tags.user_id = non_empty(),
+                "room-id" => tags.room_id = non_empty(),
+                "subscriber" => tags.subscriber = value == "1",
+                "mod" => tags.moderator = value == "1",
+                "bits" => tags.bits = value.parse().ok(),
+                "turbo" => tags.turbo = Some(value == "1"),
+                "source-room-id" => tags.source_room_id = non_empty(),
+                "source-id" => tags.source_id = non_empty(),
+                _ => {
+                    tags.other.insert(key.to_owned(), value);
+                }
+            }
+        }
+        tags
+    }
+
+    /// Whether the message was sent in another channel of the shared chat
+    /// session the channel takes part in
+    pub fn is_from_shared_chat(&self) -> bool {
+        match (&self.source_room_id, &self.room_id) {
+            (Some(source), Some(room)) => source != room,
+            _ => false,
+        }
+    }
+
+    /// Looks up a tag by its name, for tags without a dedicated field
+    pub fn get(
+        &self,
+        key: &str,
+    ) -> Option<&str>
+    {
+        self.other.get(key).map(String::as_str)
+    }
+}
+
+/// Reverts the escaping of tag values, e.g. `\s` to a space
+fn unescape(value: &str) -> String {
+    let mut res = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            res.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some(':') => res.push(';'),
+            Some('s') => res.push(' '),
+            Some('r') => res.push('\r'),
+            Some('n') => res.push('\n'),
+            Some(c) => res.push(c),
+            None => (),
+        }
+    }
+    res
+}
+
+/// Parses `broadcaster/1,subscriber/12`
+fn parse_badges(value: &str) -> Vec<Badge> {
+    value
+        .split(',')
+        .filter_map(|badge| {
+            let mut parts = badge.splitn(2, '/');
+            let name = parts.next().filter(|n| !n.is_empty())?;
+            Some(Badge {
+                name: name.to_owned(),
+                version: parts.next().unwrap_or_default().to_owned(),
+            })
+        })
+        .collect()
+}
+
+/// Parses `25:0-4,12-16/1902:6-10`, skipping malformed entries
+fn parse_emotes(value: &str) -> Vec<Emote> {
+    value
+        .split('/')
+        .filter_map(|emote| {
+            let mut parts = emote.splitn(2, ':');
+            let id = parts.next().filter(|n| !n.is_empty())?;
+            let ranges = parts
+                .next()?
+                .split(',')
+                .filter_map(|range| {
+                    let mut bounds = range.splitn(2, '-');
+                    let start = bounds.next()?.parse().ok()?;
+                    let end = bounds.next()?.parse().ok()?;
+                    Some(start..=end)
+                })
+                .collect();
+            Some(Emote {
+                id: id.to_owned(),
+                ranges,
+            })
+        })
+        .collect()
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Badge,
+        Emote,
+        Tags,
+    };
+    use crate::response::ApiError;
+
+    #[test]
+    fn reply() {
+        assert_eq!(
+            super::reply(
+                "#Ronni",
+                "b34ccfc7-4977-403a-8a94-33c6bac34fb8",
+                "Hi there"
+            )
+            .unwrap(),
+            "@reply-parent-msg-id=b34ccfc7-4977-403a-8a94-33c6bac34fb8 \
+             PRIVMSG #ronni :Hi there\r\n"
+        );
+        assert!(super::reply("ronni", "", "Hi").is_err());
+    }
+
+    #[test]
+    fn injection() {
+        for channel in &["ronni\r\nPART #ronni", "ronni PART", "#ro\nni"] {
+            match super::action(channel, "waves") {
+                Err(ApiError::InvalidInput(_)) => (),
+                r => panic!("{:?}", r),
+            }
+        }
+        match super::reply("ronni", "abc\r\nPART #ronni", "Hi") {
+            Err(ApiError::InvalidInput(_)) => (),
+            r => panic!("{:?}", r),
+        }
+        match super::reply("ronni", "abc\n", "Hi") {
+            Err(ApiError::InvalidInput(_)) => (),
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn action() {
+        assert_eq!(
+            super::action("ronni", "waves").unwrap(),
+            "PRIVMSG #ronni :\x01ACTION waves\x01\r\n"
+        );
+    }
+
+    #[test]
+    fn message_length() {
+        assert!(super::action("ronni", &"a".repeat(500)).is_ok());
+        match super::action("ronni", &"a".repeat(501)) {
+            Err(ApiError::InvalidInput(_)) => (),
+            r => panic!("{:?}", r),
+        }
+        match super::action("ronni", "hi\r\nPART #ronni") {
+            Err(ApiError::InvalidInput(_)) => (),
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn privmsg() {
+        let tags = Tags::parse(
+            "@badge-info=subscriber/8;badges=broadcaster/1,subscriber/6;\
+             bits=100;color=#0D4200;display-name=ronni;\
+             emotes=25:0-4,12-16/1902:6-10;id=b34ccfc7-4977-403a-8a94-33c6bac34fb8;\
+             mod=0;room-id=1337;subscriber=1;tmi-sent-ts=1507246572675;\
+             turbo=1;user-id=1337;user-type=global_mod \
+             :ronni!ronni@ronni.tmi.twitch.tv PRIVMSG #ronni :Kappa Keepo Kappa",
+        );
+
+        assert_eq!(tags.badge_info, vec![Badge {
+            name: "subscriber".to_owned(),
+            version: "8".to_owned(),
+        }]);
+        assert_eq!(tags.badges.len(), 2);
+        assert_eq!(tags.badges[0].name, "broadcaster");
+        assert_eq!(tags.bits, Some(100));
+        assert_eq!(tags.color.as_deref(), Some("#0D4200"));
+        assert_eq!(tags.display_name.as_deref(), Some("ronni"));
+        assert_eq!(tags.emotes, vec![
+            Emote {
+                id: "25".to_owned(),
+                ranges: vec![0..=4, 12..=16],
+            },
+            Emote {
+                id: "1902".to_owned(),
+                ranges: vec![6..=10],
+            },
+        ]);
+        assert!(!tags.moderator);
+        assert!(tags.subscriber);
+        assert_eq!(tags.room_id.as_deref(), Some("1337"));
+        assert_eq!(tags.user_id.as_deref(), Some("1337"));
+        assert_eq!(tags.get("user-type"), Some("global_mod"));
+        assert_eq!(tags.get("tmi-sent-ts"), Some("1507246572675"));
+        assert_eq!(tags.turbo, Some(true));
+        assert_eq!(tags.has_prime, Some(false));
+    }
+
+    #[test]
+    fn shared_chat() {
+        let tags = Tags::parse(
+            "@badges=;id=8e5a9d1c-3f6b-4c59-9b1e-5d0b1b2d3c4e;room-id=1337;\
+             source-id=1cb9a2e4-4a1e-4c2d-8f3e-2b1c0d9e8f7a;\
+             source-room-id=4242;user-id=9001",
+        );
+        assert_eq!(tags.source_room_id.as_deref(), Some("4242"));
+        assert_eq!(
+            tags.source_id.as_deref(),
+            Some("1cb9a2e4-4a1e-4c2d-8f3e-2b1c0d9e8f7a")
+        );
+        assert!(tags.is_from_shared_chat());
+
+        let own = Tags::parse("room-id=1337;source-room-id=1337");
+        assert!(!own.is_from_shared_chat());
+
+        let single = Tags::parse("room-id=1337;user-id=9001");
+        assert_eq!(single.source_room_id, None);
+        assert!(!single.is_from_shared_chat());
+    }
+
+    #[test]
+    fn prime() {
+        let tags = Tags::parse("badges=premium/1;turbo=0");
+        assert_eq!(tags.has_prime, Some(true));
+        assert_eq!(tags.turbo, Some(false));
+
+        let tags = Tags::parse("display-name=ronni");
+        assert_eq!(tags.has_prime, None);
+        assert_eq!(tags.turbo, None);
+    }
+
+    #[test]
+    fn empty_and_escaped() {
+        let tags = Tags::parse(
+            "badges=;color=;display-name=;emotes=;mod=1;\
+             system-msg=5\\sraiders\\sfrom\\sa\\:b\\\\c",
+        );
+
+        assert!(tags.badges.is_empty());
+        assert_eq!(tags.has_prime, Some(false));
+        assert_eq!(tags.color, None);
+        assert_eq!(tags.display_name, None);
+        assert!(tags.emotes.is_empty());
+        assert!(tags.moderator);
+        assert_eq!(tags.get("system-msg"), Some("5 raiders from a;b\\c"));
+    }
+}
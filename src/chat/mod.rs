@@ -0,0 +1,7 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+//! Helpers for Twitch chat
+//!
+//! https://dev.twitch.tv/docs/irc
+
+pub mod irc;
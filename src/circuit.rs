@@ -0,0 +1,258 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+//! Failing fast while Twitch is down instead of piling up more requests
+
+use std::{
+    io,
+    sync::Mutex,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use crate::response::{
+    ApiError,
+    TwitchResult,
+};
+
+///////////////////////////////////////
+// CircuitBreaker
+///////////////////////////////////////
+/// When `TwitchClient` stops sending Helix requests during an outage
+///
+/// After `failure_threshold` consecutive server errors (`5xx`) or timeouts
+/// the circuit opens, and every request fails with `ApiError::CircuitOpen`
+/// without being sent. Once `cool_down` passed the circuit is half-open:
+/// the next request is sent to test whether Twitch recovered, and closes
+/// the circuit if it didn't fail that way or opens it for another
+/// `cool_down` if it did. Other requests fail fast while the test runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitBreaker {
+    /// Consecutive failures opening the circuit
+    pub failure_threshold: u32,
+    /// How long the circuit stays open before testing Twitch again
+    pub cool_down: Duration,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> CircuitBreaker {
+        CircuitBreaker {
+            failure_threshold: 5,
+            cool_down: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Whether a circuit breaker lets requests through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests are sent, counting the consecutive failures
+    Closed { failures: u32 },
+    /// Requests fail fast for `retry_in`
+    Open { retry_in: Duration },
+    /// A request testing whether Twitch recovered is running
+    HalfOpen,
+}
+
+impl ApiError {
+    /// Whether `err` counts towards opening the circuit, network errors
+    /// other than timeouts are more likely on our end than Twitch's
+    pub fn trips_circuit(&self) -> bool {
+        match self {
+            ApiError::ReqwestErr(e) => match e.status() {
+                Some(status) => status.is_server_error(),
+                None => e.is_timeout(),
+            },
+            ApiError::IoError(e) => e.kind() == io::ErrorKind::TimedOut,
+            ApiError::TwitchError(e) => (500..600).contains(&e.status),
+            _ => false,
+        }
+    }
+}
+
+/// A `CircuitBreaker` and the state of its circuit
+#[derive(Debug)]
+pub(crate) struct Circuit {
+    breaker: CircuitBreaker,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum State {
+    Closed(u32),
+    Open(Instant),
+    HalfOpen,
+}
+
+impl Circuit {
+    pub(crate) fn new(breaker: CircuitBreaker) -> Circuit {
+        Circuit {
+            breaker,
+            state: Mutex::new(State::Closed(0)),
+        }
+    }
+
+    pub(crate) fn state(&self) -> CircuitState {
+        match *self.state.lock().unwrap() {
+            State::Closed(failures) => CircuitState::Closed { failures },
+            State::Open(until) => CircuitState::Open {
+                retry_in: until.saturating_duration_since(Instant::now()),
+            },
+            State::HalfOpen => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Fails with `ApiError::CircuitOpen` unless a request may be sent
+    pub(crate) fn acquire(&self) -> TwitchResult<()> {
+        let mut state = self.state.lock().unwrap();
+        match *state {
+            State::Closed(_) => Ok(()),
+            State::Open(until) => {
+                let now = Instant::now();
+                if now < until {
+                    return Err(ApiError::CircuitOpen(until - now));
+                }
+                *state = State::HalfOpen;
+                Ok(())
+            }
+            State::HalfOpen => {
+                Err(ApiError::CircuitOpen(Duration::from_secs(0)))
+            }
+        }
+    }
+
+    /// Records the outcome of a request `acquire` let through
+    pub(crate) fn record<T>(
+        &self,
+        r: &TwitchResult<T>,
+    )
+    {
+        let mut state = self.state.lock().unwrap();
+        let failed = matches!(r, Err(e) if e.trips_circuit());
+        *state = match (*state, failed) {
+            (_, false) => State::Closed(0),
+            (State::Closed(failures), true)
+                if failures + 1 < self.breaker.failure_threshold =>
+            {
+                State::Closed(failures + 1)
+            }
+            // Requests sent before the circuit opened don't extend it
+            (State::Open(until), true) => State::Open(until),
+            (_, true) => State::Open(Instant::now() + self.breaker.cool_down),
+        };
+    }
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        thread,
+        time::Duration,
+    };
+
+    use super::{
+        Circuit,
+        CircuitBreaker,
+        CircuitState,
+    };
+    use crate::{
+        helix::Endpoint,
+        mock::{
+            self,
+            twitch_error,
+        },
+        new,
+        response::ApiError,
+    };
+
+    #[test]
+    fn failures() {
+        assert!(twitch_error(503).trips_circuit());
+        assert!(twitch_error(500).trips_circuit());
+        assert!(!twitch_error(429).trips_circuit());
+        assert!(!twitch_error(404).trips_circuit());
+        let timeout = io::Error::from(io::ErrorKind::TimedOut);
+        assert!(ApiError::from(timeout).trips_circuit());
+        assert!(!ApiError::invalid_input("").trips_circuit());
+    }
+
+    #[test]
+    fn consecutive_failures_only() {
+        let circuit = Circuit::new(CircuitBreaker {
+            failure_threshold: 2,
+            cool_down: Duration::from_secs(60),
+        });
+        circuit.record::<()>(&Err(twitch_error(503)));
+        assert_eq!(circuit.state(), CircuitState::Closed { failures: 1 });
+        // Twitch answering at all means it's up
+        circuit.record::<()>(&Err(twitch_error(400)));
+        assert_eq!(circuit.state(), CircuitState::Closed { failures: 0 });
+
+        circuit.record::<()>(&Err(twitch_error(503)));
+        circuit.record::<()>(&Err(twitch_error(502)));
+        match circuit.state() {
+            CircuitState::Open { retry_in } => {
+                assert!(retry_in > Duration::from_secs(59))
+            }
+            s => panic!("{:?}", s),
+        }
+        match circuit.acquire() {
+            Err(ApiError::CircuitOpen(_)) => (),
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn repeated_503s_trip_the_breaker() {
+        const ENDPOINT: Endpoint = Endpoint::new("/streams");
+        let server = mock::serve(vec![
+            mock::response(503, &[], ""),
+            mock::response(503, &[], ""),
+            mock::response(503, &[], ""),
+            mock::response(503, &[], ""),
+            mock::response(200, &[], r#"{"data": []}"#),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+        let c = c.with_circuit_breaker(CircuitBreaker {
+            failure_threshold: 3,
+            cool_down: Duration::from_millis(200),
+        });
+        let get = || c.helix_get::<serde_json::Value, _>(&ENDPOINT, &());
+
+        for _ in 0..3 {
+            match get() {
+                Err(ApiError::TwitchError(e)) => assert_eq!(e.status, 503),
+                r => panic!("{:?}", r),
+            }
+        }
+        match get() {
+            Err(ApiError::CircuitOpen(retry_in)) => {
+                assert!(retry_in <= Duration::from_millis(200))
+            }
+            r => panic!("{:?}", r),
+        }
+
+        // The test request fails, so the circuit opens again
+        thread::sleep(Duration::from_millis(250));
+        assert!(get().is_err());
+        match get() {
+            Err(ApiError::CircuitOpen(_)) => (),
+            r => panic!("{:?}", r),
+        }
+
+        thread::sleep(Duration::from_millis(250));
+        get().unwrap();
+        assert_eq!(
+            c.circuit_state(),
+            Some(CircuitState::Closed { failures: 0 })
+        );
+        assert_eq!(server.requests().len(), 5);
+    }
+}
@@ -0,0 +1,103 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+//! URLs for embedding the Twitch player in an iframe
+//!
+//! Twitch only serves embeds to the domains listed in the `parent`
+//! parameters, so every domain (and subdomain) the iframe is shown on has to
+//! be passed in `parents`, e.g. `&["example.com", "www.example.com"]`.
+//!
+//! https://dev.twitch.tv/docs/embed
+
+use reqwest::Url;
+
+use crate::response::{
+    ApiError,
+    TwitchResult,
+};
+
+/// Builds the URL of a player showing the live stream of `channel`
+pub fn channel_url(
+    channel: &str,
+    parents: &[&str],
+) -> TwitchResult<String>
+{
+    build("https://player.twitch.tv/", ("channel", channel), parents)
+}
+
+/// Builds the URL of a player showing the clip with the given slug
+pub fn clip_url(
+    slug: &str,
+    parents: &[&str],
+) -> TwitchResult<String>
+{
+    build("https://clips.twitch.tv/embed", ("clip", slug), parents)
+}
+
+/// Builds the URL of a player showing the video with the given id
+pub fn vod_url(
+    video_id: &str,
+    parents: &[&str],
+) -> TwitchResult<String>
+{
+    build("https://player.twitch.tv/", ("video", video_id), parents)
+}
+
+fn build(
+    base: &str,
+    target: (&str, &str),
+    parents: &[&str],
+) -> TwitchResult<String>
+{
+    if parents.is_empty() {
+        return Err(ApiError::invalid_input(
+            "embeds need at least one parent domain",
+        ));
+    }
+    let params = std::iter::once(target)
+        .chain(parents.iter().map(|parent| ("parent", *parent)));
+    let url = Url::parse_with_params(base, params)
+        .expect("embed base URLs are valid");
+    Ok(url.into())
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn channel_url() {
+        assert_eq!(
+            super::channel_url("twitchdev", &["example.com"]).unwrap(),
+            "https://player.twitch.tv/?channel=twitchdev&parent=example.com"
+        );
+        assert!(super::channel_url("twitchdev", &[]).is_err());
+    }
+
+    #[test]
+    fn multiple_parents() {
+        assert_eq!(
+            super::clip_url("AwkwardHelplessSalamanderSwiftRage", &[
+                "example.com",
+                "www.example.com",
+            ])
+            .unwrap(),
+            "https://clips.twitch.tv/embed?clip=AwkwardHelplessSalamanderSwiftRage\
+             &parent=example.com&parent=www.example.com"
+        );
+        assert_eq!(
+            super::vod_url("v123456789", &["a.example", "b.example"]).unwrap(),
+            "https://player.twitch.tv/?video=v123456789\
+             &parent=a.example&parent=b.example"
+        );
+    }
+
+    #[test]
+    fn encoding() {
+        assert_eq!(
+            super::channel_url("a&b", &["example.com/x y"]).unwrap(),
+            "https://player.twitch.tv/?channel=a%26b&parent=example.com%2Fx+y"
+        );
+    }
+}
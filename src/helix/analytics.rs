@@ -0,0 +1,270 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+//! Analytics reports of extensions and games
+//!
+//! Twitch doesn't return the reports themselves but signed URLs of CSV
+//! files, which expire after five minutes. Download them with
+//! `TwitchClient::download_report`.
+//!
+//! The reports are only updated once a day and Twitch limits how often they
+//! can be requested independently of the regular rate limit bucket, so
+//! cache them instead of fetching them on every use.
+
+use std::io;
+
+use chrono::prelude::*;
+use serde::Deserialize;
+
+use super::{
+    scope::HelixScope,
+    Endpoint,
+    HelixPage,
+    HelixResponse,
+};
+
+use crate::{
+    response::{
+        ApiError,
+        TwitchResult,
+    },
+    TwitchClient,
+};
+
+pub const EXTENSIONS: Endpoint = Endpoint::new("/analytics/extensions")
+    .with_scopes(&[HelixScope::AnalyticsReadExtensions]);
+
+/// Gets the analytics reports of the extensions of the authenticated user
+///
+/// `opts.id` selects a single extension, otherwise the reports of all
+/// extensions are paginated.
+///
+/// #### Authentication: `analytics:read:extensions`
+pub fn extension(
+    c: &TwitchClient,
+    opts: &AnalyticsOpts,
+) -> TwitchResult<HelixPage<AnalyticsReport>>
+{
+    let r = c.helix_get::<HelixResponse<AnalyticsReport>, _>(
+        &EXTENSIONS,
+        &opts.params("extension_id"),
+    )?;
+    Ok(HelixPage::from(r))
+}
+
+pub const GAMES: Endpoint = Endpoint::new("/analytics/games")
+    .with_scopes(&[HelixScope::AnalyticsReadGames]);
+
+/// Gets the analytics reports of the games of the authenticated user
+///
+/// `opts.id` selects a single game, otherwise the reports of all games are
+/// paginated.
+///
+/// #### Authentication: `analytics:read:games`
+pub fn game(
+    c: &TwitchClient,
+    opts: &AnalyticsOpts,
+) -> TwitchResult<HelixPage<AnalyticsReport>>
+{
+    let r = c.helix_get::<HelixResponse<AnalyticsReport>, _>(
+        &GAMES,
+        &opts.params("game_id"),
+    )?;
+    Ok(HelixPage::from(r))
+}
+
+impl TwitchClient {
+    /// Downloads the CSV file behind `AnalyticsReport::url`
+    ///
+    /// The URL is signed and points to a CDN, so unlike every other request
+    /// of the client it's sent without the `Client-Id` and `Authorization`
+    /// headers, which would break the signature. Fails with an
+    /// `ApiError::ReqwestErr` for a non-success status, e.g. once the URL
+    /// expired, and with an `io::ErrorKind::InvalidData` error if the file
+    /// isn't valid UTF-8.
+    pub fn download_report(
+        &self,
+        url: &str,
+    ) -> TwitchResult<String>
+    {
+        let r = self.client.get(url).send()?.error_for_status()?;
+        let body = self.read_body(r)?;
+        String::from_utf8(body).map_err(|e| {
+            ApiError::from(io::Error::new(io::ErrorKind::InvalidData, e))
+        })
+    }
+}
+
+///////////////////////////////////////
+// AnalyticsOpts
+///////////////////////////////////////
+/// Filters of the analytics endpoints
+///
+/// Set both `started_at` and `ended_at` or neither, in which case the
+/// report covers the last 90 days. Twitch ignores the time of day and only
+/// has data from January 31, 2018 on.
+#[derive(Debug, Default, Clone)]
+pub struct AnalyticsOpts {
+    /// The extension or game id
+    pub id: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub ended_at: Option<DateTime<Utc>>,
+    /// Maximum number of reports per page, ignored if `id` is set
+    pub first: Option<u32>,
+    /// Cursor of the page to continue after, ignored if `id` is set
+    pub after: Option<String>,
+}
+
+impl AnalyticsOpts {
+    fn params(
+        &self,
+        id_param: &'static str,
+    ) -> Vec<(&'static str, String)>
+    {
+        let date =
+            |d: &DateTime<Utc>| d.format("%Y-%m-%dT00:00:00Z").to_string();
+        let mut params = Vec::new();
+        if let Some(ref id) = self.id {
+            params.push((id_param, id.clone()));
+        }
+        params.push(("type", "overview_v2".to_owned()));
+        if let Some(ref started_at) = self.started_at {
+            params.push(("started_at", date(started_at)));
+        }
+        if let Some(ref ended_at) = self.ended_at {
+            params.push(("ended_at", date(ended_at)));
+        }
+        if let Some(first) = self.first {
+            params.push(("first", first.to_string()));
+        }
+        if let Some(ref after) = self.after {
+            params.push(("after", after.clone()));
+        }
+        params
+    }
+}
+
+///////////////////////////////////////
+// AnalyticsReport
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone)]
+pub struct AnalyticsReport {
+    /// The extension or game id
+    #[serde(alias = "extension_id", alias = "game_id")]
+    pub id: String,
+    /// Signed URL of the CSV file, valid for five minutes
+    #[serde(rename = "URL")]
+    pub url: String,
+    /// Always `overview_v2`
+    #[serde(rename = "type")]
+    pub _type: String,
+    pub date_range: DateRange,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateRange {
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use chrono::prelude::*;
+
+    use super::{
+        AnalyticsOpts,
+        AnalyticsReport,
+    };
+    use crate::{
+        helix::HelixResponse,
+        mock,
+        new,
+        response::ApiError,
+    };
+
+    #[test]
+    fn download_report() {
+        let csv = "Date,Extension Name,Installs\n2018-03-01,My Extension,3\n";
+        let server = mock::serve(vec![
+            mock::response(200, &[("Content-Type", "text/csv")], csv),
+            mock::response(
+                403,
+                &[],
+                "<Error><Code>AccessDenied</Code></Error>",
+            ),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.set_oauth_token("<token>");
+
+        let url = format!(
+            "{}/dynamic/overview.csv?X-Amz-Expires=300&X-Amz-Signature=abc",
+            server.url
+        );
+        assert_eq!(c.download_report(&url).unwrap(), csv);
+        match c.download_report(&url) {
+            Err(ApiError::ReqwestErr(_)) => (),
+            r => panic!("{:?}", r),
+        }
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with(
+            "GET /dynamic/overview.csv?X-Amz-Expires=300&X-Amz-Signature=abc "
+        ));
+        let headers = requests[0].to_ascii_lowercase();
+        assert!(!headers.contains("client-id"));
+        assert!(!headers.contains("authorization"));
+    }
+
+    #[test]
+    fn params() {
+        let opts = AnalyticsOpts {
+            id: Some("493057".to_owned()),
+            started_at: Some(
+                Utc.with_ymd_and_hms(2018, 1, 1, 12, 30, 0).unwrap(),
+            ),
+            ended_at: Some(Utc.with_ymd_and_hms(2018, 3, 1, 0, 0, 0).unwrap()),
+            ..AnalyticsOpts::default()
+        };
+        assert_eq!(opts.params("game_id"), vec![
+            ("game_id", "493057".to_owned()),
+            ("type", "overview_v2".to_owned()),
+            ("started_at", "2018-01-01T00:00:00Z".to_owned()),
+            ("ended_at", "2018-03-01T00:00:00Z".to_owned()),
+        ]);
+    }
+
+    #[test]
+    fn reports() {
+        let r: HelixResponse<AnalyticsReport> = serde_json::from_str(
+            r#"{
+              "data": [{
+                "extension_id": "efgh",
+                "URL": "https://twitch-piper-reports.s3-us-west-2.amazonaws.com/dynamic/LoL%20ADC...",
+                "type": "overview_v2",
+                "date_range": {
+                  "started_at": "2018-03-01T00:00:00Z",
+                  "ended_at": "2018-06-01T00:00:00Z"
+                }
+              }, {
+                "game_id": "493057",
+                "URL": "https://twitch-piper-reports.s3-us-west-2.amazonaws.com/games/66170/overview/15183360...",
+                "type": "overview_v2",
+                "date_range": {
+                  "started_at": "2018-01-01T00:00:00Z",
+                  "ended_at": "2018-03-01T00:00:00Z"
+                }
+              }],
+              "pagination": {}
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(r.data[0].id, "efgh");
+        assert_eq!(r.data[1].id, "493057");
+        assert!(r.data[1].url.starts_with("https://"));
+        assert_eq!(r.data[1].date_range.ended_at.month(), 3);
+    }
+}
@@ -0,0 +1,139 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+use std::collections::HashMap;
+
+use chrono::prelude::*;
+use serde::Deserialize;
+
+use super::{
+    Endpoint,
+    HelixResponse,
+};
+
+use crate::{
+    response::TwitchResult,
+    TwitchClient,
+};
+
+pub const CHEERMOTES: Endpoint = Endpoint::new("/bits/cheermotes");
+
+/// Gets the cheermotes users can cheer with, including the custom ones of
+/// `broadcaster_id` if given
+///
+/// #### Authentication: App or user access token
+pub fn cheermotes(
+    c: &TwitchClient,
+    broadcaster_id: Option<&str>,
+) -> TwitchResult<Vec<Cheermote>>
+{
+    let params: Vec<_> = broadcaster_id
+        .iter()
+        .map(|id| ("broadcaster_id", *id))
+        .collect();
+    let r = c.helix_get::<HelixResponse<Cheermote>, _>(&CHEERMOTES, &params)?;
+    Ok(r.data)
+}
+
+///////////////////////////////////////
+// GetCheermotes
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone)]
+pub struct Cheermote {
+    /// The word to cheer with, e.g. `Cheer` in `Cheer100`
+    pub prefix: String,
+    pub tiers: Vec<Tier>,
+    /// e.g. `global_first_party` or `channel_custom`
+    #[serde(rename = "type")]
+    pub _type: String,
+    pub order: i32,
+    pub last_updated: DateTime<Utc>,
+    pub is_charitable: bool,
+}
+
+impl Cheermote {
+    /// The tier a cheer of `bits` is shown with, the one with the highest
+    /// `min_bits` not above `bits`
+    pub fn tier_for_bits(
+        &self,
+        bits: u32,
+    ) -> Option<&Tier>
+    {
+        self.tiers
+            .iter()
+            .filter(|t| t.min_bits <= bits)
+            .max_by_key(|t| t.min_bits)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Tier {
+    pub min_bits: u32,
+    /// One of `1`, `100`, `500`, `1000`, `5000`, `10000` and `100000`
+    pub id: String,
+    /// Hex color like `#9c3ee8`
+    pub color: String,
+    /// URLs of the images by theme (`dark` or `light`), format (`animated`
+    /// or `static`) and scale (`1` to `4`)
+    pub images: HashMap<String, HashMap<String, HashMap<String, String>>>,
+    pub can_cheer: bool,
+    pub show_in_bits_card: bool,
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::Cheermote;
+    use crate::helix::HelixResponse;
+
+    fn cheermote() -> Cheermote {
+        let tier = |min_bits: u32| {
+            format!(
+                r##"{{
+                  "min_bits": {0},
+                  "id": "{0}",
+                  "color": "#979797",
+                  "images": {{"dark": {{"static": {{"1": "https://d3aqoihi2n8ty8.cloudfront.net/actions/cheer/dark/static/{0}/1.png"}}}}}},
+                  "can_cheer": true,
+                  "show_in_bits_card": true
+                }}"##,
+                min_bits
+            )
+        };
+        let mut r: HelixResponse<Cheermote> = serde_json::from_str(&format!(
+            r#"{{
+              "data": [{{
+                "prefix": "Cheer",
+                "tiers": [{}, {}, {}],
+                "type": "global_first_party",
+                "order": 1,
+                "last_updated": "2018-05-22T00:06:04Z",
+                "is_charitable": false
+              }}]
+            }}"#,
+            tier(100),
+            tier(1),
+            tier(1000),
+        ))
+        .unwrap();
+        r.data.remove(0)
+    }
+
+    #[test]
+    fn tier_for_bits() {
+        let c = cheermote();
+        let tier = |bits| c.tier_for_bits(bits).map(|t| t.min_bits);
+
+        assert_eq!(tier(0), None);
+        assert_eq!(tier(1), Some(1));
+        assert_eq!(tier(99), Some(1));
+        assert_eq!(tier(100), Some(100));
+        assert_eq!(tier(999), Some(100));
+        assert_eq!(tier(1000), Some(1000));
+        assert_eq!(tier(u32::MAX), Some(1000));
+        assert!(c.tier_for_bits(1).unwrap().images["dark"]["static"]["1"]
+            .ends_with("/static/1/1.png"));
+    }
+}
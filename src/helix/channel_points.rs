@@ -0,0 +1,245 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+use chrono::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use super::{
+    scope::HelixScope,
+    Endpoint,
+    HelixPage,
+    HelixResponse,
+    PageOpts,
+};
+
+use crate::{
+    response::TwitchResult,
+    TwitchClient,
+};
+
+pub const REDEMPTIONS: Endpoint =
+    Endpoint::new("/channel_points/custom_rewards/redemptions").with_scopes(&[
+        HelixScope::ChannelReadRedemptions,
+        HelixScope::ChannelManageRedemptions,
+    ]);
+
+/// Gets the redemptions of a specified custom reward with the given
+/// `status`, oldest first
+///
+/// Only rewards created by the client of the access token can be queried.
+/// Pages hold up to 50 redemptions.
+///
+/// #### Authentication: `channel:read:redemptions` or
+/// `channel:manage:redemptions`
+pub fn redemptions(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    reward_id: &str,
+    status: RedemptionStatus,
+    opts: &PageOpts,
+) -> TwitchResult<HelixPage<Redemption>>
+{
+    let mut params = vec![
+        ("broadcaster_id", broadcaster_id.to_owned()),
+        ("reward_id", reward_id.to_owned()),
+        ("status", status.as_str().to_owned()),
+    ];
+    params.extend(opts.forward_params()?);
+    let r =
+        c.helix_get::<HelixResponse<Redemption>, _>(&REDEMPTIONS, &params)?;
+    Ok(HelixPage::from(r))
+}
+
+/// Sums up the cost of all unfulfilled redemptions of a specified custom
+/// reward, i.e. the points still waiting in the queue
+///
+/// Pages through all of them, paced by the rate limit.
+///
+/// #### Authentication: `channel:read:redemptions` or
+/// `channel:manage:redemptions`
+pub fn pending_cost(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    reward_id: &str,
+) -> TwitchResult<u64>
+{
+    c.aggregate_pages(
+        &REDEMPTIONS,
+        &[
+            ("broadcaster_id", broadcaster_id),
+            ("reward_id", reward_id),
+            ("status", RedemptionStatus::Unfulfilled.as_str()),
+            ("first", "50"),
+        ],
+        0,
+        |sum, r: &Redemption| sum + r.reward.cost,
+    )
+}
+
+///////////////////////////////////////
+// GetCustomRewardRedemption
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone)]
+pub struct Redemption {
+    pub broadcaster_id: String,
+    pub broadcaster_login: String,
+    pub broadcaster_name: String,
+    pub id: String,
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+    /// What the user entered, empty if the reward doesn't ask for input
+    pub user_input: String,
+    pub status: RedemptionStatus,
+    pub redeemed_at: DateTime<Utc>,
+    pub reward: RedeemedReward,
+}
+
+/// The reward as it was when it got redeemed
+#[derive(Deserialize, Debug, Clone)]
+pub struct RedeemedReward {
+    pub id: String,
+    pub title: String,
+    pub prompt: String,
+    pub cost: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RedemptionStatus {
+    /// Waiting in the queue
+    Unfulfilled,
+    Fulfilled,
+    /// Rejected, the points were refunded
+    Canceled,
+}
+
+impl RedemptionStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RedemptionStatus::Unfulfilled => "UNFULFILLED",
+            RedemptionStatus::Fulfilled => "FULFILLED",
+            RedemptionStatus::Canceled => "CANCELED",
+        }
+    }
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::RedemptionStatus;
+    use crate::{
+        helix::PageOpts,
+        mock,
+        new,
+    };
+
+    fn redemption(
+        id: &str,
+        cost: u64,
+    ) -> String
+    {
+        format!(
+            r#"{{
+              "broadcaster_id": "274637212",
+              "broadcaster_login": "torpedo09",
+              "broadcaster_name": "torpedo09",
+              "id": "{}",
+              "user_id": "274637212",
+              "user_login": "torpedo09",
+              "user_name": "torpedo09",
+              "user_input": "",
+              "status": "UNFULFILLED",
+              "redeemed_at": "2020-07-01T18:37:32Z",
+              "reward": {{
+                "id": "92af127c-7326-4483-a52b-b0da0be61c01",
+                "title": "game analysis",
+                "prompt": "",
+                "cost": {}
+              }}
+            }}"#,
+            id, cost
+        )
+    }
+
+    #[test]
+    fn redemptions() {
+        let server = mock::serve(vec![mock::response(
+            200,
+            &[],
+            &format!(
+                r#"{{"data": [{}], "pagination": {{}}}}"#,
+                redemption("17fa2df1-ad76-4804-bfa5-a40ef63efe63", 50000)
+            ),
+        )]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let page = super::redemptions(
+            &c,
+            "274637212",
+            "92af127c-7326-4483-a52b-b0da0be61c01",
+            RedemptionStatus::Unfulfilled,
+            &PageOpts::default(),
+        )
+        .unwrap();
+        assert_eq!(page.data[0].status, RedemptionStatus::Unfulfilled);
+        assert_eq!(page.data[0].reward.cost, 50000);
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with(
+            "GET /channel_points/custom_rewards/redemptions?broadcaster_id=274637212&reward_id=92af127c-7326-4483-a52b-b0da0be61c01&status=UNFULFILLED "
+        ));
+    }
+
+    #[test]
+    fn pending_cost() {
+        let server = mock::serve(vec![
+            mock::response(
+                200,
+                &[],
+                &format!(
+                    r#"{{"data": [{}, {}], "pagination": {{"cursor": "page2"}}}}"#,
+                    redemption("1", 100),
+                    redemption("2", 250)
+                ),
+            ),
+            mock::response(
+                200,
+                &[],
+                &format!(
+                    r#"{{"data": [{}], "pagination": {{}}}}"#,
+                    redemption("3", 50)
+                ),
+            ),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        assert_eq!(
+            super::pending_cost(&c, "274637212", "reward").unwrap(),
+            400
+        );
+
+        let requests = server.requests();
+        assert!(requests[1].contains("&first=50&after=page2 "));
+    }
+
+    #[test]
+    fn pending_cost_empty_queue() {
+        let server = mock::serve(vec![mock::response(
+            200,
+            &[],
+            r#"{"data": [], "pagination": {}}"#,
+        )]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        assert_eq!(super::pending_cost(&c, "274637212", "reward").unwrap(), 0);
+    }
+}
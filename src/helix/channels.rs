@@ -0,0 +1,473 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+use std::{
+    collections::HashSet,
+    thread,
+};
+
+use chrono::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::json;
+
+use super::{
+    scope::HelixScope,
+    Endpoint,
+    HelixPage,
+    HelixResponse,
+    PageOpts,
+};
+
+use crate::{
+    response::{
+        ApiError,
+        Rejection,
+        TwitchResult,
+    },
+    TwitchClient,
+};
+
+pub const GET_INFO: Endpoint = Endpoint::new("/channels");
+
+/// Gets the channel information of a specified broadcaster
+///
+/// #### Authentication: App or user access token
+pub fn get_info(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+) -> TwitchResult<ChannelInfo>
+{
+    let r = c.helix_get::<HelixResponse<ChannelInfo>, _>(&GET_INFO, &[(
+        "broadcaster_id",
+        broadcaster_id,
+    )])?;
+    r.single()
+}
+
+pub const ADD_VIP: Endpoint = Endpoint::new("/channels/vips")
+    .with_scopes(&[HelixScope::ChannelManageVips]);
+
+/// Makes a user a VIP in a specified broadcaster's channel
+///
+/// Fails with `Rejection::VipSlotsFull` if the broadcaster has no VIP slots
+/// left and with `Rejection::AlreadyVip` if the user already is a VIP.
+///
+/// #### Authentication: `channel:manage:vips`
+pub fn add_vip(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    user_id: &str,
+) -> TwitchResult<()>
+{
+    let r = c.helix_post::<_, (), _>(
+        &ADD_VIP,
+        &[("broadcaster_id", broadcaster_id), ("user_id", user_id)],
+        &json!({}),
+    );
+    match r {
+        Err(ApiError::TwitchError(ref e)) if e.status == 422 => {
+            Err(ApiError::Rejected(Rejection::VipSlotsFull))
+        }
+        Err(ApiError::TwitchError(ref e)) if e.status == 409 => {
+            Err(ApiError::Rejected(Rejection::AlreadyVip))
+        }
+        r => r,
+    }
+}
+
+pub const REMOVE_VIP: Endpoint = Endpoint::new("/channels/vips")
+    .with_scopes(&[HelixScope::ChannelManageVips]);
+
+/// Removes the VIP status of a user in a specified broadcaster's channel
+///
+/// #### Authentication: `channel:manage:vips`
+pub fn remove_vip(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    user_id: &str,
+) -> TwitchResult<()>
+{
+    c.helix_delete::<(), _>(&REMOVE_VIP, &[
+        ("broadcaster_id", broadcaster_id),
+        ("user_id", user_id),
+    ])
+}
+
+pub const START_COMMERCIAL: Endpoint = Endpoint::new("/channels/commercial")
+    .with_scopes(&[HelixScope::ChannelEditCommercial]);
+
+/// Runs a commercial on a specified broadcaster's live channel
+///
+/// Twitch may run a shorter commercial than requested, see
+/// `CommercialResult::length`. Fails with
+/// `Rejection::CommercialNotEligible` if the broadcaster is neither a
+/// partner nor an affiliate and with `Rejection::CommercialRunning` if a
+/// commercial is running or the last one ended too recently.
+///
+/// #### Authentication: `channel:edit:commercial`
+pub fn start_commercial(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    length: CommercialLength,
+) -> TwitchResult<CommercialResult>
+{
+    let r = c.helix_post::<_, HelixResponse<CommercialResult>, _>(
+        &START_COMMERCIAL,
+        &(),
+        &NewCommercial {
+            broadcaster_id,
+            length: length.as_secs(),
+        },
+    );
+    match r {
+        Ok(r) => r.single(),
+        Err(ApiError::TwitchError(ref e))
+            if e.status == 400
+                && (e.message.contains("partner")
+                    || e.message.contains("affiliate")) =>
+        {
+            Err(ApiError::Rejected(Rejection::CommercialNotEligible))
+        }
+        Err(ApiError::TwitchError(ref e))
+            if (e.status == 400 || e.status == 429)
+                && e.message.to_lowercase().contains("commercial") =>
+        {
+            Err(ApiError::Rejected(Rejection::CommercialRunning))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+pub const FOLLOWERS: Endpoint = Endpoint::new("/channels/followers")
+    .with_scopes(&[HelixScope::ModeratorReadFollowers]);
+
+/// Gets the users following a specified broadcaster, newest first
+///
+/// Pass `user_id` to only check whether that user follows the broadcaster.
+/// Without the scope, e.g. with an app access token, only `total` is set.
+///
+/// #### Authentication: `moderator:read:followers` for the followers, any
+/// token for their number
+pub fn followers(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    user_id: Option<&str>,
+    opts: &PageOpts,
+) -> TwitchResult<HelixPage<Follower>>
+{
+    let mut params = vec![("broadcaster_id", broadcaster_id.to_owned())];
+    if let Some(user_id) = user_id {
+        params.push(("user_id", user_id.to_owned()));
+    }
+    params.extend(opts.forward_params()?);
+    let r = c.helix_get::<HelixResponse<Follower>, _>(&FOLLOWERS, &params)?;
+    Ok(HelixPage::from(r))
+}
+
+/// How many channels `combined_followers` pages through at once
+const COMBINED_FOLLOWERS_PARALLELISM: usize = 4;
+
+/// Gets the ids of the users following any of `broadcaster_ids`, e.g. to
+/// count the unique followers across several channels
+///
+/// Pages through the followers of up to four channels at once, paced by the
+/// rate limit. Needs one request per 100 followers of every channel.
+///
+/// #### Authentication: `moderator:read:followers`
+pub fn combined_followers(
+    c: &TwitchClient,
+    broadcaster_ids: &[&str],
+) -> TwitchResult<HashSet<String>>
+{
+    let collect = |broadcaster_id: &str| {
+        c.aggregate_pages(
+            &FOLLOWERS,
+            &[("broadcaster_id", broadcaster_id), ("first", "100")],
+            HashSet::new(),
+            |mut ids, f: &Follower| {
+                ids.insert(f.user_id.clone());
+                ids
+            },
+        )
+    };
+
+    let mut all = HashSet::new();
+    for chunk in broadcaster_ids.chunks(COMBINED_FOLLOWERS_PARALLELISM) {
+        let results: Vec<TwitchResult<HashSet<String>>> = thread::scope(|s| {
+            let handles: Vec<_> = chunk
+                .iter()
+                .map(|id| s.spawn(move || collect(id)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().expect("follower lookup panicked"))
+                .collect()
+        });
+        for ids in results {
+            all.extend(ids?);
+        }
+    }
+    Ok(all)
+}
+
+///////////////////////////////////////
+// GetChannelInformation
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone)]
+pub struct ChannelInfo {
+    pub broadcaster_id: String,
+    pub broadcaster_login: String,
+    pub broadcaster_name: String,
+    pub broadcaster_language: String,
+    /// Game last played, empty if the channel never played a game
+    pub game_id: String,
+    pub game_name: String,
+    pub title: String,
+    /// Stream delay in seconds
+    pub delay: i32,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+///////////////////////////////////////
+// GetChannelFollowers
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone)]
+pub struct Follower {
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+    pub followed_at: DateTime<Utc>,
+}
+
+///////////////////////////////////////
+// StartCommercial
+///////////////////////////////////////
+/// The commercial lengths Twitch accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommercialLength {
+    Secs30,
+    Secs60,
+    Secs90,
+    Secs120,
+    Secs150,
+    Secs180,
+}
+
+impl CommercialLength {
+    pub fn as_secs(self) -> u32 {
+        match self {
+            CommercialLength::Secs30 => 30,
+            CommercialLength::Secs60 => 60,
+            CommercialLength::Secs90 => 90,
+            CommercialLength::Secs120 => 120,
+            CommercialLength::Secs150 => 150,
+            CommercialLength::Secs180 => 180,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CommercialResult {
+    /// Seconds the commercial actually runs, may be less than requested
+    pub length: u32,
+    /// Why the commercial is shorter than requested, empty otherwise
+    pub message: String,
+    /// Seconds until the next commercial can run
+    pub retry_after: u32,
+}
+
+#[derive(Serialize, Debug)]
+struct NewCommercial<'a> {
+    broadcaster_id: &'a str,
+    length: u32,
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ChannelInfo,
+        CommercialLength,
+    };
+    use crate::{
+        helix::HelixResponse,
+        mock,
+        new,
+        response::{
+            ApiError,
+            Rejection,
+        },
+    };
+
+    #[test]
+    fn channel_info() {
+        let r: HelixResponse<ChannelInfo> = serde_json::from_str(
+            r#"{
+              "data": [{
+                "broadcaster_id": "141981764",
+                "broadcaster_login": "twitchdev",
+                "broadcaster_name": "TwitchDev",
+                "broadcaster_language": "en",
+                "game_id": "509670",
+                "game_name": "Science & Technology",
+                "title": "TwitchDev Monthly Update // May 6, 2021",
+                "delay": 0,
+                "tags": ["DevsInTheKnow"]
+              }]
+            }"#,
+        )
+        .unwrap();
+
+        let info = r.single().unwrap();
+        assert_eq!(info.game_name, "Science & Technology");
+        assert_eq!(info.tags, vec!["DevsInTheKnow"]);
+    }
+
+    #[test]
+    fn vips() {
+        let server = mock::serve(vec![
+            mock::response(204, &[], ""),
+            mock::response(
+                422,
+                &[],
+                r#"{"error":"Unprocessable Entity","status":422,"message":"The broadcaster doesn't have available VIP slots"}"#,
+            ),
+            mock::response(
+                409,
+                &[],
+                r#"{"error":"Conflict","status":409,"message":"The user is already a VIP"}"#,
+            ),
+            mock::response(204, &[], ""),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        super::add_vip(&c, "123", "456").unwrap();
+        for rejection in &[Rejection::VipSlotsFull, Rejection::AlreadyVip] {
+            match super::add_vip(&c, "123", "456") {
+                Err(ApiError::Rejected(r)) => assert_eq!(r, *rejection),
+                r => panic!("{:?}", r),
+            }
+        }
+        super::remove_vip(&c, "123", "456").unwrap();
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with(
+            "POST /channels/vips?broadcaster_id=123&user_id=456 "
+        ));
+        assert!(requests[3].starts_with(
+            "DELETE /channels/vips?broadcaster_id=123&user_id=456 "
+        ));
+    }
+
+    #[test]
+    fn start_commercial() {
+        let server = mock::serve(vec![
+            mock::response(
+                200,
+                &[],
+                r#"{"data": [{
+                  "length": 60,
+                  "message": "Commercial length was reduced to 60 seconds",
+                  "retry_after": 480
+                }]}"#,
+            ),
+            mock::response(
+                400,
+                &[],
+                r#"{"error":"Bad Request","status":400,"message":"To start a commercial, the broadcaster must be a partner or an affiliate"}"#,
+            ),
+            mock::response(
+                429,
+                &[],
+                r#"{"error":"Too Many Requests","status":429,"message":"A commercial is already running"}"#,
+            ),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let r =
+            super::start_commercial(&c, "41245072", CommercialLength::Secs120)
+                .unwrap();
+        assert_eq!(r.length, 60);
+        assert_eq!(r.retry_after, 480);
+
+        for rejection in &[
+            Rejection::CommercialNotEligible,
+            Rejection::CommercialRunning,
+        ] {
+            match super::start_commercial(
+                &c,
+                "41245072",
+                CommercialLength::Secs30,
+            ) {
+                Err(ApiError::Rejected(r)) => assert_eq!(r, *rejection),
+                r => panic!("{:?}", r),
+            }
+        }
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with("POST /channels/commercial "));
+        assert!(requests[0]
+            .ends_with(r#"{"broadcaster_id":"41245072","length":120}"#));
+    }
+
+    fn followers(
+        ids: &[&str],
+        cursor: &str,
+    ) -> String
+    {
+        let data: Vec<String> = ids
+            .iter()
+            .map(|id| {
+                format!(
+                    r#"{{"user_id":"{0}","user_login":"u{0}","user_name":"U{0}","followed_at":"2022-05-24T22:22:08Z"}}"#,
+                    id
+                )
+            })
+            .collect();
+        mock::response(
+            200,
+            &[],
+            &format!(
+                r#"{{"data":[{}],"pagination":{{"cursor":"{}"}},"total":{}}}"#,
+                data.join(","),
+                cursor,
+                ids.len()
+            ),
+        )
+    }
+
+    #[test]
+    fn combined_followers() {
+        // The first matching prefix is served, so the second page comes first
+        let server = mock::serve_routes(vec![
+            (
+                "/channels/followers?broadcaster_id=111&first=100&after=next",
+                followers(&["3"], ""),
+            ),
+            (
+                "/channels/followers?broadcaster_id=111&",
+                followers(&["1", "2"], "next"),
+            ),
+            (
+                "/channels/followers?broadcaster_id=222&",
+                followers(&["2", "3", "4"], ""),
+            ),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let ids = super::combined_followers(&c, &["111", "222"]).unwrap();
+        let mut ids: Vec<_> = ids.into_iter().collect();
+        ids.sort();
+        assert_eq!(ids, vec!["1", "2", "3", "4"]);
+        assert_eq!(server.requests().len(), 3);
+    }
+}
@@ -0,0 +1,248 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+use std::fmt;
+
+use serde::Deserialize;
+
+use super::{
+    scope::HelixScope,
+    Endpoint,
+    HelixPage,
+    HelixResponse,
+    PageOpts,
+};
+
+use crate::{
+    response::TwitchResult,
+    TwitchClient,
+};
+
+pub const CAMPAIGN: Endpoint = Endpoint::new("/charity/campaigns")
+    .with_scopes(&[HelixScope::ChannelReadCharity]);
+
+/// Gets the charity campaign a specified broadcaster is running
+///
+/// Returns `None` if the broadcaster has no active campaign.
+///
+/// #### Authentication: `channel:read:charity`
+pub fn campaign(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+) -> TwitchResult<Option<CharityCampaign>>
+{
+    let r =
+        c.helix_get::<HelixResponse<CharityCampaign>, _>(&CAMPAIGN, &[(
+            "broadcaster_id",
+            broadcaster_id,
+        )])?;
+    Ok(r.data.into_iter().next())
+}
+
+pub const DONATIONS: Endpoint = Endpoint::new("/charity/donations")
+    .with_scopes(&[HelixScope::ChannelReadCharity]);
+
+/// Gets the donations to the active charity campaign of a specified
+/// broadcaster
+///
+/// Returns no donations if the broadcaster has no active campaign.
+///
+/// #### Authentication: `channel:read:charity`
+pub fn donations(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    opts: &PageOpts,
+) -> TwitchResult<HelixPage<CharityDonation>>
+{
+    let mut params = vec![("broadcaster_id", broadcaster_id.to_owned())];
+    params.extend(opts.forward_params()?);
+    let r =
+        c.helix_get::<HelixResponse<CharityDonation>, _>(&DONATIONS, &params)?;
+    Ok(HelixPage::from(r))
+}
+
+///////////////////////////////////////
+// CharityAmount
+///////////////////////////////////////
+/// A monetary amount in the minor unit of its currency
+///
+/// `value` 1250 with `decimal_places` 2 is 12.50. Use the `Display`
+/// implementation to show the amount with the right number of decimals, it
+/// shows the raw `value` if `decimal_places` is too large to scale by.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CharityAmount {
+    pub value: i64,
+    pub decimal_places: u32,
+    /// ISO-4217 currency code
+    pub currency: String,
+}
+
+impl CharityAmount {
+    /// The amount in the major unit of its currency
+    ///
+    /// Only use this for calculations like progress bars, floating point
+    /// values aren't exact.
+    pub fn as_f64(&self) -> f64 {
+        self.value as f64 / 10f64.powi(self.decimal_places as i32)
+    }
+}
+
+impl fmt::Display for CharityAmount {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result
+    {
+        let sign = if self.value < 0 { "-" } else { "" };
+        let value = self.value.unsigned_abs();
+        if self.decimal_places == 0 {
+            return write!(f, "{}{} {}", sign, value, self.currency);
+        }
+        let scale = match 10u64.checked_pow(self.decimal_places) {
+            Some(scale) => scale,
+            // More decimals than any currency has, don't guess
+            None => {
+                return write!(f, "{} {}", self.value, self.currency);
+            }
+        };
+        write!(
+            f,
+            "{}{}.{:0width$} {}",
+            sign,
+            value / scale,
+            value % scale,
+            self.currency,
+            width = self.decimal_places as usize
+        )
+    }
+}
+
+///////////////////////////////////////
+// GetCharityCampaign
+///////////////////////////////////////
+#[derive(Deserialize, Debug)]
+pub struct CharityCampaign {
+    pub id: String,
+    pub broadcaster_id: String,
+    pub broadcaster_login: String,
+    pub broadcaster_name: String,
+    pub charity_name: String,
+    pub charity_description: String,
+    pub charity_logo: String,
+    pub charity_website: String,
+    pub current_amount: CharityAmount,
+    /// `None` if the broadcaster didn't set a fundraising goal
+    pub target_amount: Option<CharityAmount>,
+}
+
+///////////////////////////////////////
+// GetCharityCampaignDonations
+///////////////////////////////////////
+#[derive(Deserialize, Debug)]
+pub struct CharityDonation {
+    pub id: String,
+    pub campaign_id: String,
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+    pub amount: CharityAmount,
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CharityAmount,
+        CharityCampaign,
+        CharityDonation,
+    };
+    use crate::helix::HelixResponse;
+
+    fn amount(
+        value: i64,
+        decimal_places: u32,
+    ) -> CharityAmount
+    {
+        CharityAmount {
+            value,
+            decimal_places,
+            currency: "USD".to_owned(),
+        }
+    }
+
+    #[test]
+    fn amount_display() {
+        assert_eq!(amount(86000, 2).to_string(), "860.00 USD");
+        assert_eq!(amount(1205, 2).to_string(), "12.05 USD");
+        assert_eq!(amount(5, 3).to_string(), "0.005 USD");
+        assert_eq!(amount(500, 0).to_string(), "500 USD");
+        assert_eq!(amount(-150, 2).to_string(), "-1.50 USD");
+        assert_eq!(amount(-150, 20).to_string(), "-150 USD");
+        assert_eq!(amount(150, u32::MAX).to_string(), "150 USD");
+        assert!((amount(1250, 2).as_f64() - 12.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn campaign() {
+        let r: HelixResponse<CharityCampaign> = serde_json::from_str(
+            r#"{
+              "data": [{
+                "id": "123-abc-456-def",
+                "broadcaster_id": "123456",
+                "broadcaster_name": "SunnySideUp",
+                "broadcaster_login": "sunnysideup",
+                "charity_name": "Example name",
+                "charity_description": "Example description",
+                "charity_logo": "https://abc.cloudfront.net/ppgf/1000/100.png",
+                "charity_website": "https://www.example.com",
+                "current_amount": {
+                  "value": 86000,
+                  "decimal_places": 2,
+                  "currency": "USD"
+                },
+                "target_amount": {
+                  "value": 1500000,
+                  "decimal_places": 2,
+                  "currency": "USD"
+                }
+              }]
+            }"#,
+        )
+        .unwrap();
+
+        let campaign = r.single().unwrap();
+        assert_eq!(campaign.current_amount, amount(86000, 2));
+        assert_eq!(campaign.target_amount.unwrap().to_string(), "15000.00 USD");
+
+        let r: HelixResponse<CharityCampaign> =
+            serde_json::from_str(r#"{"data": []}"#).unwrap();
+        assert!(r.data.into_iter().next().is_none());
+    }
+
+    #[test]
+    fn donations() {
+        let r: HelixResponse<CharityDonation> = serde_json::from_str(
+            r#"{
+              "data": [{
+                "id": "a1b2c3-aabb-4455-d1e2f3",
+                "campaign_id": "123-abc-456-def",
+                "user_id": "5678",
+                "user_login": "cool_user",
+                "user_name": "Cool_User",
+                "amount": {
+                  "value": 500,
+                  "decimal_places": 2,
+                  "currency": "USD"
+                }
+              }],
+              "pagination": {"cursor": "eyJiIjpudWxsLCJhIjp7Ik9mZnNldCI6NX19"}
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(r.cursor(), Some("eyJiIjpudWxsLCJhIjp7Ik9mZnNldCI6NX19"));
+        assert_eq!(r.data[0].amount.to_string(), "5.00 USD");
+    }
+}
@@ -0,0 +1,1018 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serialize,
+};
+use serde_json::json;
+use std::time::Duration;
+#[cfg(feature = "global-cache")]
+use std::{
+    sync::{
+        Arc,
+        Mutex,
+    },
+    time::Instant,
+};
+
+use super::{
+    scope::HelixScope,
+    Endpoint,
+    HelixPage,
+    HelixResponse,
+    PageOpts,
+};
+
+use crate::{
+    response::{
+        ApiError,
+        Rejection,
+        TwitchResult,
+    },
+    TwitchClient,
+};
+
+pub const GET_CHATTERS: Endpoint = Endpoint::new("/chat/chatters")
+    .with_scopes(&[HelixScope::ModeratorReadChatters]);
+
+/// Gets the users connected to the chat of a specified broadcaster
+///
+/// `moderator_id` has to match the user of the access token and be the
+/// broadcaster or one of their moderators. Pages hold up to 1000 chatters,
+/// `HelixPage::total` is the number of chatters across all pages.
+///
+/// #### Authentication: `moderator:read:chatters`
+pub fn get_chatters(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    moderator_id: &str,
+    opts: &PageOpts,
+) -> TwitchResult<HelixPage<Chatter>>
+{
+    let mut params = vec![
+        ("broadcaster_id", broadcaster_id.to_owned()),
+        ("moderator_id", moderator_id.to_owned()),
+    ];
+    params.extend(opts.forward_params()?);
+    let r = c.helix_get::<HelixResponse<Chatter>, _>(&GET_CHATTERS, &params)?;
+    Ok(HelixPage::from(r))
+}
+
+pub const GET_USER_COLOR: Endpoint = Endpoint::new("/chat/color");
+
+/// Gets the colors up to 100 specified users use for their name in chat
+///
+/// `color` is empty for users who never picked one.
+///
+/// #### Authentication: App or user access token
+pub fn get_user_color(
+    c: &TwitchClient,
+    user_ids: &[&str],
+) -> TwitchResult<Vec<UserColor>>
+{
+    if user_ids.len() > 100 {
+        return Err(ApiError::invalid_input(
+            "at most 100 user colors can be looked up at once",
+        ));
+    }
+    let params: Vec<_> = user_ids.iter().map(|id| ("user_id", *id)).collect();
+    let r =
+        c.helix_get::<HelixResponse<UserColor>, _>(&GET_USER_COLOR, &params)?;
+    Ok(r.data)
+}
+
+/// The colors every user can pick for their name in chat
+pub const NAMED_COLORS: [&str; 15] = [
+    "blue",
+    "blue_violet",
+    "cadet_blue",
+    "chocolate",
+    "coral",
+    "dodger_blue",
+    "firebrick",
+    "golden_rod",
+    "green",
+    "hot_pink",
+    "orange_red",
+    "red",
+    "sea_green",
+    "spring_green",
+    "yellow_green",
+];
+
+pub const UPDATE_USER_COLOR: Endpoint = Endpoint::new("/chat/color")
+    .with_scopes(&[HelixScope::UserManageChatColor]);
+
+/// Sets the color a specified user uses for their name in chat
+///
+/// `color` is either one of `NAMED_COLORS` or a hex color like `#9146FF`.
+/// Only Turbo and Prime users can pick a hex color, for everyone else this
+/// fails with `Rejection::HexColorRequiresTurbo`.
+///
+/// #### Authentication: `user:manage:chat_color`
+pub fn update_user_color(
+    c: &TwitchClient,
+    user_id: &str,
+    color: &str,
+) -> TwitchResult<()>
+{
+    let is_hex = is_hex_color(color);
+    if !is_hex && !NAMED_COLORS.contains(&color) {
+        return Err(ApiError::invalid_input(
+            "color must be a named color or of the form #RRGGBB",
+        ));
+    }
+    let r = c.helix_put::<_, (), _>(
+        &UPDATE_USER_COLOR,
+        &[("user_id", user_id), ("color", color)],
+        &json!({}),
+    );
+    match r {
+        Err(ApiError::TwitchError(ref e))
+            if is_hex
+                && e.status == 400
+                && (e.message.contains("Turbo")
+                    || e.message.contains("Prime")) =>
+        {
+            Err(ApiError::Rejected(Rejection::HexColorRequiresTurbo))
+        }
+        r => r,
+    }
+}
+
+pub const SEND_MESSAGE: Endpoint =
+    Endpoint::new("/chat/messages").with_scopes(&[HelixScope::UserWriteChat]);
+
+/// Sends a message to the chat of a specified broadcaster, without IRC
+///
+/// `sender_id` has to match the user of the access token. `reply_to` is the
+/// id of the message to reply to. Messages have to be between 1 and 500
+/// characters long.
+///
+/// A message Twitch dropped, e.g. because AutoMod held it, isn't an error:
+/// `is_sent` is `false` then and `drop_reason` says why.
+///
+/// #### Authentication: `user:write:chat`
+pub fn send_message(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    sender_id: &str,
+    message: &str,
+    reply_to: Option<&str>,
+) -> TwitchResult<SendResult>
+{
+    let len = message.chars().count();
+    if !(1..=500).contains(&len) {
+        return Err(ApiError::invalid_input(
+            "chat messages must be between 1 and 500 characters long",
+        ));
+    }
+    let r = c.helix_post::<_, HelixResponse<SendResult>, _>(
+        &SEND_MESSAGE,
+        &(),
+        &NewMessage {
+            broadcaster_id,
+            sender_id,
+            message,
+            reply_parent_message_id: reply_to,
+        },
+    )?;
+    r.single()
+}
+
+fn is_hex_color(color: &str) -> bool {
+    match color.strip_prefix('#') {
+        Some(hex) => {
+            hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit())
+        }
+        None => false,
+    }
+}
+
+pub const GLOBAL_BADGES: Endpoint = Endpoint::new("/chat/badges/global");
+
+/// Gets the chat badges every channel shows, e.g. for staff or Prime
+///
+/// #### Authentication: App or user access token
+pub fn global_badges(c: &TwitchClient) -> TwitchResult<Vec<BadgeSet>> {
+    let r = c.helix_get::<HelixResponse<BadgeSet>, _>(&GLOBAL_BADGES, &())?;
+    Ok(r.data)
+}
+
+pub const CHANNEL_BADGES: Endpoint = Endpoint::new("/chat/badges");
+
+/// Gets the custom chat badges of a specified broadcaster, e.g. their
+/// subscriber and bits badges
+///
+/// #### Authentication: App or user access token
+pub fn channel_badges(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+) -> TwitchResult<Vec<BadgeSet>>
+{
+    let r = c.helix_get::<HelixResponse<BadgeSet>, _>(&CHANNEL_BADGES, &[(
+        "broadcaster_id",
+        broadcaster_id,
+    )])?;
+    Ok(r.data)
+}
+
+pub const CHANNEL_EMOTES: Endpoint = Endpoint::new("/chat/emotes");
+
+/// Gets the custom emotes of a specified broadcaster, with how each of them
+/// is unlocked
+///
+/// #### Authentication: App or user access token
+pub fn channel_emotes(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+) -> TwitchResult<Vec<ChannelEmote>>
+{
+    let r = c.helix_get::<HelixResponse<ChannelEmote>, _>(
+        &CHANNEL_EMOTES,
+        &[("broadcaster_id", broadcaster_id)],
+    )?;
+    Ok(r.data)
+}
+
+pub const GLOBAL_EMOTES: Endpoint = Endpoint::new("/chat/emotes/global");
+
+/// Gets the emotes everyone can use in every channel
+///
+/// #### Authentication: App or user access token
+pub fn global_emotes(c: &TwitchClient) -> TwitchResult<Vec<GlobalEmote>> {
+    let r =
+        c.helix_get::<HelixResponse<GlobalEmote>, _>(&GLOBAL_EMOTES, &())?;
+    Ok(r.data)
+}
+
+pub const GET_SETTINGS: Endpoint = Endpoint::new("/chat/settings");
+
+/// Gets the chat settings of a specified broadcaster
+///
+/// The `non_moderator_chat_delay` fields are only set if `moderator_id` is
+/// given, which has to be the user of the token and a moderator of the
+/// broadcaster or the broadcaster themselves.
+///
+/// #### Authentication: App or user access token, `moderator:read:chat_settings`
+/// or `moderator:manage:chat_settings` for the moderator fields
+pub fn get_settings(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    moderator_id: Option<&str>,
+) -> TwitchResult<ChatSettings>
+{
+    let mut params = vec![("broadcaster_id", broadcaster_id)];
+    if let Some(moderator_id) = moderator_id {
+        params.push(("moderator_id", moderator_id));
+    }
+    let r =
+        c.helix_get::<HelixResponse<ChatSettings>, _>(&GET_SETTINGS, &params)?;
+    r.single()
+}
+
+///////////////////////////////////////
+// Global cache
+///////////////////////////////////////
+/// How long `cached_global_badges` and `cached_global_emotes` keep a
+/// response
+#[cfg(feature = "global-cache")]
+pub const GLOBAL_CACHE_TTL: Duration = Duration::from_secs(12 * 60 * 60);
+
+#[cfg(feature = "global-cache")]
+static GLOBAL_BADGE_CACHE: GlobalCache<BadgeSet> = GlobalCache::new();
+#[cfg(feature = "global-cache")]
+static GLOBAL_EMOTE_CACHE: GlobalCache<GlobalEmote> = GlobalCache::new();
+
+/// Like `global_badges`, but shares the response between all clients of
+/// the process for `GLOBAL_CACHE_TTL`
+///
+/// Only the first caller fetches the badges, concurrent callers wait for
+/// its response. Errors aren't cached.
+///
+/// #### Authentication: App or user access token
+#[cfg(feature = "global-cache")]
+pub fn cached_global_badges(
+    c: &TwitchClient
+) -> TwitchResult<Arc<Vec<BadgeSet>>>
+{
+    GLOBAL_BADGE_CACHE.get(|| global_badges(c))
+}
+
+/// Like `global_emotes`, but shares the response between all clients of
+/// the process for `GLOBAL_CACHE_TTL`
+///
+/// #### Authentication: App or user access token
+#[cfg(feature = "global-cache")]
+pub fn cached_global_emotes(
+    c: &TwitchClient
+) -> TwitchResult<Arc<Vec<GlobalEmote>>>
+{
+    GLOBAL_EMOTE_CACHE.get(|| global_emotes(c))
+}
+
+/// Drops the cached global badges and emotes, the next call of
+/// `cached_global_badges` or `cached_global_emotes` fetches them again
+#[cfg(feature = "global-cache")]
+pub fn refresh_global_cache() {
+    GLOBAL_BADGE_CACHE.clear();
+    GLOBAL_EMOTE_CACHE.clear();
+}
+
+#[cfg(feature = "global-cache")]
+struct GlobalCache<T> {
+    entry: Mutex<Option<(Instant, Arc<Vec<T>>)>>,
+}
+
+#[cfg(feature = "global-cache")]
+impl<T> GlobalCache<T> {
+    const fn new() -> Self {
+        GlobalCache {
+            entry: Mutex::new(None),
+        }
+    }
+
+    /// The lock is held while fetching, so a cold cache is filled once
+    fn get(
+        &self,
+        fetch: impl FnOnce() -> TwitchResult<Vec<T>>,
+    ) -> TwitchResult<Arc<Vec<T>>>
+    {
+        let mut entry = self.entry.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((fetched_at, ref data)) = *entry {
+            if fetched_at.elapsed() < GLOBAL_CACHE_TTL {
+                return Ok(Arc::clone(data));
+            }
+        }
+        let data = Arc::new(fetch()?);
+        *entry = Some((Instant::now(), Arc::clone(&data)));
+        Ok(data)
+    }
+
+    fn clear(&self) {
+        *self.entry.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+///////////////////////////////////////
+// GetChatters
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Chatter {
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+}
+
+///////////////////////////////////////
+// GetUserChatColor
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct UserColor {
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+    /// Hex color like `#9146FF`, empty if the user never picked a color
+    pub color: String,
+}
+
+///////////////////////////////////////
+// GetGlobalChatBadges
+///////////////////////////////////////
+/// A badge and its versions, e.g. `subscriber` with one version per month
+/// count
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BadgeSet {
+    pub set_id: String,
+    pub versions: Vec<BadgeVersion>,
+}
+
+/// One version of a badge, matched against the `badges` tag of a chat
+/// message by `set_id` and `id`
+///
+/// `title` and `description` are the tooltip chat shows for the badge,
+/// clicking it runs `click_action`, for `visit_url` it opens `click_url`.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BadgeVersion {
+    pub id: String,
+    pub image_url_1x: String,
+    pub image_url_2x: String,
+    pub image_url_4x: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub click_action: Option<String>,
+    #[serde(default)]
+    pub click_url: Option<String>,
+}
+
+///////////////////////////////////////
+// GetChannelEmotes
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChannelEmote {
+    pub id: String,
+    pub name: String,
+    pub images: EmoteImages,
+    /// Subscription tier unlocking the emote, `1000`, `2000` or `3000`
+    ///
+    /// `None` for emotes which aren't unlocked by a subscription.
+    #[serde(deserialize_with = "empty_as_none")]
+    pub tier: Option<String>,
+    pub emote_type: EmoteType,
+    pub emote_set_id: String,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EmoteImages {
+    pub url_1x: String,
+    pub url_2x: String,
+    pub url_4x: String,
+}
+
+///////////////////////////////////////
+// GetGlobalEmotes
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GlobalEmote {
+    pub id: String,
+    pub name: String,
+    pub images: EmoteImages,
+}
+
+/// How an emote is unlocked
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum EmoteType {
+    /// By subscribing to the broadcaster, at the emote's `tier`
+    Subscriptions,
+    /// By cheering bits in the broadcaster's chat
+    Bitstier,
+    /// By following the broadcaster
+    Follower,
+    #[serde(other)]
+    Other,
+}
+
+impl EmoteType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EmoteType::Subscriptions => "subscriptions",
+            EmoteType::Bitstier => "bitstier",
+            EmoteType::Follower => "follower",
+            EmoteType::Other => "other",
+        }
+    }
+}
+
+/// Twitch sends an empty string instead of `null` for emotes without a tier
+fn empty_as_none<'de, D>(d: D) -> Result<Option<String>, D::Error>
+where D: Deserializer<'de> {
+    Ok(Option::<String>::deserialize(d)?.filter(|s| !s.is_empty()))
+}
+
+///////////////////////////////////////
+// GetChatSettings
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChatSettings {
+    pub broadcaster_id: String,
+    /// Only emotes may be sent
+    pub emote_mode: bool,
+    /// Only followers may chat
+    pub follower_mode: bool,
+    /// How long users have to follow before they may chat, zero for any
+    /// follower
+    #[serde(deserialize_with = "optional_minutes")]
+    pub follower_mode_duration: Option<Duration>,
+    pub slow_mode: bool,
+    /// How long users have to wait between messages
+    #[serde(deserialize_with = "optional_seconds")]
+    pub slow_mode_wait_time: Option<Duration>,
+    /// Only subscribers may chat
+    pub subscriber_mode: bool,
+    /// Messages have to differ from those sent before
+    pub unique_chat_mode: bool,
+    /// Messages are shown to non-moderators with a delay, so moderators can
+    /// remove them first
+    ///
+    /// `None` unless requested with a moderator id.
+    #[serde(default)]
+    pub non_moderator_chat_delay: Option<bool>,
+    #[serde(default, deserialize_with = "optional_seconds")]
+    pub non_moderator_chat_delay_duration: Option<Duration>,
+}
+
+fn optional_seconds<'de, D>(d: D) -> Result<Option<Duration>, D::Error>
+where D: Deserializer<'de> {
+    Ok(Option::<u64>::deserialize(d)?.map(Duration::from_secs))
+}
+
+fn optional_minutes<'de, D>(d: D) -> Result<Option<Duration>, D::Error>
+where D: Deserializer<'de> {
+    Ok(Option::<u64>::deserialize(d)?.map(|m| Duration::from_secs(m * 60)))
+}
+
+///////////////////////////////////////
+// SendChatMessage
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SendResult {
+    /// Empty if the message was dropped
+    pub message_id: String,
+    pub is_sent: bool,
+    /// Why the message was dropped, `None` if it was sent
+    pub drop_reason: Option<DropReason>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DropReason {
+    pub code: String,
+    pub message: String,
+}
+
+#[derive(Serialize, Debug)]
+struct NewMessage<'a> {
+    broadcaster_id: &'a str,
+    sender_id: &'a str,
+    message: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reply_parent_message_id: Option<&'a str>,
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::EmoteType;
+    use crate::{
+        helix::PageOpts,
+        mock,
+        new,
+        response::{
+            ApiError,
+            Rejection,
+        },
+    };
+
+    #[test]
+    fn get_chatters() {
+        let server = mock::serve(vec![mock::response(
+            200,
+            &[],
+            r#"{
+              "data": [{
+                "user_id": "128393656",
+                "user_login": "smittysmithers",
+                "user_name": "smittysmithers"
+              }],
+              "pagination": {"cursor": "eyJiIjpudWxsLCJhIjp7Ik9mZnNldCI6NX19"},
+              "total": 8
+            }"#,
+        )]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let opts = PageOpts {
+            first: Some(1),
+            ..PageOpts::default()
+        };
+        let page = super::get_chatters(&c, "123456", "654321", &opts).unwrap();
+        assert_eq!(page.data[0].user_login, "smittysmithers");
+        assert_eq!(page.total, Some(8));
+        assert!(page.cursor.is_some());
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with(
+            "GET /chat/chatters?broadcaster_id=123456&moderator_id=654321&first=1 "
+        ));
+    }
+
+    #[test]
+    fn update_user_color() {
+        let server = mock::serve(vec![
+            mock::response(204, &[], ""),
+            mock::response(
+                400,
+                &[],
+                r#"{"error":"Bad Request","status":400,"message":"The user is not Turbo or Prime"}"#,
+            ),
+            mock::response(
+                400,
+                &[],
+                r#"{"error":"Bad Request","status":400,"message":"Invalid user_id"}"#,
+            ),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        super::update_user_color(&c, "123", "blue_violet").unwrap();
+        match super::update_user_color(&c, "123", "#9146FF") {
+            Err(ApiError::Rejected(Rejection::HexColorRequiresTurbo)) => (),
+            r => panic!("{:?}", r),
+        }
+        match super::update_user_color(&c, "abc", "#9146FF") {
+            Err(ApiError::TwitchError(e)) => assert_eq!(e.status, 400),
+            r => panic!("{:?}", r),
+        }
+
+        let requests = server.requests();
+        assert!(requests[0]
+            .starts_with("PUT /chat/color?user_id=123&color=blue_violet "));
+        assert!(requests[1]
+            .starts_with("PUT /chat/color?user_id=123&color=%239146FF "));
+    }
+
+    #[test]
+    fn invalid_user_color() {
+        let c = new(String::from("<clientid>"));
+
+        for color in &["purple", "#9146F", "#9146FG", "9146FF", "#9146FF0"] {
+            match super::update_user_color(&c, "123", color) {
+                Err(ApiError::InvalidInput(_)) => (),
+                r => panic!("{}: {:?}", color, r),
+            }
+        }
+    }
+
+    #[test]
+    fn send_message() {
+        let server = mock::serve(vec![
+            mock::response(
+                200,
+                &[],
+                r#"{"data":[{
+                  "message_id": "abc-123-def",
+                  "is_sent": true
+                }]}"#,
+            ),
+            mock::response(
+                200,
+                &[],
+                r#"{"data":[{
+                  "message_id": "",
+                  "is_sent": false,
+                  "drop_reason": {
+                    "code": "msg_rejected",
+                    "message": "Your message is being checked by mods"
+                  }
+                }]}"#,
+            ),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let sent =
+            super::send_message(&c, "123", "456", "Hello", None).unwrap();
+        assert!(sent.is_sent);
+        assert_eq!(sent.message_id, "abc-123-def");
+        assert_eq!(sent.drop_reason, None);
+
+        let held =
+            super::send_message(&c, "123", "456", "Hi", Some("abc-123-def"))
+                .unwrap();
+        assert!(!held.is_sent);
+        assert_eq!(held.drop_reason.unwrap().code, "msg_rejected");
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with("POST /chat/messages "));
+        assert!(requests[0].ends_with(
+            r#"{"broadcaster_id":"123","sender_id":"456","message":"Hello"}"#
+        ));
+        assert!(requests[1]
+            .ends_with(r#""reply_parent_message_id":"abc-123-def"}"#));
+    }
+
+    #[test]
+    fn invalid_message() {
+        let c = new(String::from("<clientid>"));
+
+        for message in &[String::new(), "a".repeat(501)] {
+            match super::send_message(&c, "123", "456", message, None) {
+                Err(ApiError::InvalidInput(_)) => (),
+                r => panic!("{:?}", r),
+            }
+        }
+    }
+
+    #[test]
+    fn badges() {
+        let server = mock::serve(vec![mock::response(
+            200,
+            &[],
+            r#"{
+              "data": [{
+                "set_id": "vip",
+                "versions": [{
+                  "id": "1",
+                  "image_url_1x": "https://static-cdn.jtvnw.net/badges/v1/b817aba4-fad8-49e2-b88a-7cc744dfa6ec/1",
+                  "image_url_2x": "https://static-cdn.jtvnw.net/badges/v1/b817aba4-fad8-49e2-b88a-7cc744dfa6ec/2",
+                  "image_url_4x": "https://static-cdn.jtvnw.net/badges/v1/b817aba4-fad8-49e2-b88a-7cc744dfa6ec/3",
+                  "title": "VIP",
+                  "description": "VIP",
+                  "click_action": "visit_url",
+                  "click_url": "https://help.twitch.tv/customer/en/portal/articles/659115-twitch-chat-badges-guide"
+                }]
+              }, {
+                "set_id": "legacy",
+                "versions": [{
+                  "id": "1",
+                  "image_url_1x": "https://static-cdn.jtvnw.net/badges/v1/1/1",
+                  "image_url_2x": "https://static-cdn.jtvnw.net/badges/v1/1/2",
+                  "image_url_4x": "https://static-cdn.jtvnw.net/badges/v1/1/3",
+                  "click_action": null
+                }]
+              }]
+            }"#,
+        )]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let sets = super::global_badges(&c).unwrap();
+        let vip = &sets[0].versions[0];
+        assert_eq!(sets[0].set_id, "vip");
+        assert_eq!(vip.title.as_deref(), Some("VIP"));
+        assert_eq!(vip.click_action.as_deref(), Some("visit_url"));
+        assert!(vip.click_url.as_ref().unwrap().starts_with("https://help."));
+
+        let legacy = &sets[1].versions[0];
+        assert_eq!(legacy.title, None);
+        assert_eq!(legacy.description, None);
+        assert_eq!(legacy.click_action, None);
+        assert_eq!(legacy.click_url, None);
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with("GET /chat/badges/global "));
+    }
+
+    #[test]
+    fn channel_emotes() {
+        let server = mock::serve(vec![mock::response(
+            200,
+            &[],
+            r#"{
+              "data": [{
+                "id": "304456832",
+                "name": "twitchdevPitchfork",
+                "images": {
+                  "url_1x": "https://static-cdn.jtvnw.net/emoticons/v2/304456832/static/light/1.0",
+                  "url_2x": "https://static-cdn.jtvnw.net/emoticons/v2/304456832/static/light/2.0",
+                  "url_4x": "https://static-cdn.jtvnw.net/emoticons/v2/304456832/static/light/3.0"
+                },
+                "tier": "1000",
+                "emote_type": "subscriptions",
+                "emote_set_id": "301590448",
+                "format": ["static"],
+                "scale": ["1.0", "2.0", "3.0"],
+                "theme_mode": ["light", "dark"]
+              }, {
+                "id": "emotesv2_4c3b4ed516de493bbcd2df2f5d450f49",
+                "name": "twitchdevHyperPitchfork",
+                "images": {
+                  "url_1x": "https://static-cdn.jtvnw.net/emoticons/v2/emotesv2_4c3b4ed516de493bbcd2df2f5d450f49/static/light/1.0",
+                  "url_2x": "https://static-cdn.jtvnw.net/emoticons/v2/emotesv2_4c3b4ed516de493bbcd2df2f5d450f49/static/light/2.0",
+                  "url_4x": "https://static-cdn.jtvnw.net/emoticons/v2/emotesv2_4c3b4ed516de493bbcd2df2f5d450f49/static/light/3.0"
+                },
+                "tier": "3000",
+                "emote_type": "subscriptions",
+                "emote_set_id": "318939165",
+                "format": ["static"],
+                "scale": ["1.0", "2.0", "3.0"],
+                "theme_mode": ["light", "dark"]
+              }, {
+                "id": "302227822",
+                "name": "twitchdevBits",
+                "images": {
+                  "url_1x": "https://static-cdn.jtvnw.net/emoticons/v2/302227822/static/light/1.0",
+                  "url_2x": "https://static-cdn.jtvnw.net/emoticons/v2/302227822/static/light/2.0",
+                  "url_4x": "https://static-cdn.jtvnw.net/emoticons/v2/302227822/static/light/3.0"
+                },
+                "tier": "",
+                "emote_type": "bitstier",
+                "emote_set_id": "302227823",
+                "format": ["static"],
+                "scale": ["1.0", "2.0", "3.0"],
+                "theme_mode": ["light", "dark"]
+              }, {
+                "id": "emotesv2_8a5b9d2e1f2c4a7b9e3d6c0f1a2b3c4d",
+                "name": "twitchdevWave",
+                "images": {
+                  "url_1x": "https://static-cdn.jtvnw.net/emoticons/v2/emotesv2_8a5b9d2e1f2c4a7b9e3d6c0f1a2b3c4d/static/light/1.0",
+                  "url_2x": "https://static-cdn.jtvnw.net/emoticons/v2/emotesv2_8a5b9d2e1f2c4a7b9e3d6c0f1a2b3c4d/static/light/2.0",
+                  "url_4x": "https://static-cdn.jtvnw.net/emoticons/v2/emotesv2_8a5b9d2e1f2c4a7b9e3d6c0f1a2b3c4d/static/light/3.0"
+                },
+                "tier": "",
+                "emote_type": "follower",
+                "emote_set_id": "emotesv2_3f2d87b8d4a24f8e9d6f7c5b4a3e2d1c",
+                "format": ["static"],
+                "scale": ["1.0", "2.0", "3.0"],
+                "theme_mode": ["light", "dark"]
+              }],
+              "template": "https://static-cdn.jtvnw.net/emoticons/v2/{{id}}/{{format}}/{{theme_mode}}/{{scale}}"
+            }"#,
+        )]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let emotes = super::channel_emotes(&c, "141981764").unwrap();
+        let kinds: Vec<_> = emotes
+            .iter()
+            .map(|e| (e.emote_type, e.tier.as_deref()))
+            .collect();
+        assert_eq!(kinds, vec![
+            (EmoteType::Subscriptions, Some("1000")),
+            (EmoteType::Subscriptions, Some("3000")),
+            (EmoteType::Bitstier, None),
+            (EmoteType::Follower, None),
+        ]);
+        assert!(emotes[0].images.url_4x.ends_with("/3.0"));
+
+        let requests = server.requests();
+        assert!(requests[0]
+            .starts_with("GET /chat/emotes?broadcaster_id=141981764 "));
+    }
+
+    #[test]
+    fn global_emotes() {
+        let server = mock::serve(vec![mock::response(
+            200,
+            &[],
+            r#"{
+              "data": [{
+                "id": "196892",
+                "name": "TwitchUnity",
+                "images": {
+                  "url_1x": "https://static-cdn.jtvnw.net/emoticons/v2/196892/static/light/1.0",
+                  "url_2x": "https://static-cdn.jtvnw.net/emoticons/v2/196892/static/light/2.0",
+                  "url_4x": "https://static-cdn.jtvnw.net/emoticons/v2/196892/static/light/3.0"
+                },
+                "format": ["static"],
+                "scale": ["1.0", "2.0", "3.0"],
+                "theme_mode": ["light", "dark"]
+              }],
+              "template": "https://static-cdn.jtvnw.net/emoticons/v2/{{id}}/{{format}}/{{theme_mode}}/{{scale}}"
+            }"#,
+        )]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let emotes = super::global_emotes(&c).unwrap();
+        assert_eq!(emotes[0].id, "196892");
+        assert_eq!(emotes[0].name, "TwitchUnity");
+        assert!(emotes[0].images.url_4x.ends_with("/3.0"));
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with("GET /chat/emotes/global "));
+    }
+
+    // The only test touching the process-global cache, so parallel tests
+    // can't fill it with their responses
+    #[cfg(feature = "global-cache")]
+    #[test]
+    fn global_cache() {
+        let server = mock::serve_routes(vec![
+            (
+                "/chat/badges/global",
+                mock::response(
+                    200,
+                    &[],
+                    r#"{"data": [{"set_id": "vip", "versions": []}]}"#,
+                ),
+            ),
+            (
+                "/chat/emotes/global",
+                mock::response(
+                    200,
+                    &[],
+                    r#"{
+                      "data": [{
+                        "id": "196892",
+                        "name": "TwitchUnity",
+                        "images": {
+                          "url_1x": "https://static-cdn.jtvnw.net/emoticons/v2/196892/static/light/1.0",
+                          "url_2x": "https://static-cdn.jtvnw.net/emoticons/v2/196892/static/light/2.0",
+                          "url_4x": "https://static-cdn.jtvnw.net/emoticons/v2/196892/static/light/3.0"
+                        },
+                        "format": ["static"],
+                        "scale": ["1.0", "2.0", "3.0"],
+                        "theme_mode": ["light", "dark"]
+                      }],
+                      "template": "https://static-cdn.jtvnw.net/emoticons/v2/{{id}}/{{format}}/{{theme_mode}}/{{scale}}"
+                    }"#,
+                ),
+            ),
+            (
+                "/chat/badges/global",
+                mock::response(
+                    200,
+                    &[],
+                    r#"{"data": [{"set_id": "staff", "versions": []}]}"#,
+                ),
+            ),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+        let mut other = new(String::from("<other clientid>"));
+        other.helix_url = server.url.clone();
+
+        super::refresh_global_cache();
+        let badges = super::cached_global_badges(&c).unwrap();
+        assert_eq!(badges[0].set_id, "vip");
+        let again = super::cached_global_badges(&other).unwrap();
+        assert!(std::sync::Arc::ptr_eq(&badges, &again));
+
+        let emotes = super::cached_global_emotes(&other).unwrap();
+        assert_eq!(emotes[0].name, "TwitchUnity");
+        super::cached_global_emotes(&c).unwrap();
+
+        super::refresh_global_cache();
+        let badges = super::cached_global_badges(&c).unwrap();
+        assert_eq!(badges[0].set_id, "staff");
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        assert!(requests[0].starts_with("GET /chat/badges/global "));
+        assert!(requests[1].starts_with("GET /chat/emotes/global "));
+        assert!(requests[2].starts_with("GET /chat/badges/global "));
+    }
+
+    #[test]
+    fn get_settings() {
+        let server = mock::serve(vec![
+            mock::response(
+                200,
+                &[],
+                r#"{
+                  "data": [{
+                    "broadcaster_id": "713936733",
+                    "slow_mode": true,
+                    "slow_mode_wait_time": 30,
+                    "follower_mode": true,
+                    "follower_mode_duration": 0,
+                    "subscriber_mode": false,
+                    "emote_mode": false,
+                    "unique_chat_mode": false
+                  }]
+                }"#,
+            ),
+            mock::response(
+                200,
+                &[],
+                r#"{
+                  "data": [{
+                    "broadcaster_id": "713936733",
+                    "slow_mode": false,
+                    "slow_mode_wait_time": null,
+                    "follower_mode": true,
+                    "follower_mode_duration": 10,
+                    "subscriber_mode": false,
+                    "emote_mode": true,
+                    "unique_chat_mode": false,
+                    "moderator_id": "713936733",
+                    "non_moderator_chat_delay": true,
+                    "non_moderator_chat_delay_duration": 4
+                  }]
+                }"#,
+            ),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let settings = super::get_settings(&c, "713936733", None).unwrap();
+        assert_eq!(settings.slow_mode_wait_time, Some(Duration::from_secs(30)));
+        assert_eq!(
+            settings.follower_mode_duration,
+            Some(Duration::from_secs(0))
+        );
+        assert_eq!(settings.non_moderator_chat_delay, None);
+        assert_eq!(settings.non_moderator_chat_delay_duration, None);
+
+        let settings =
+            super::get_settings(&c, "713936733", Some("713936733")).unwrap();
+        assert!(settings.emote_mode);
+        assert_eq!(settings.slow_mode_wait_time, None);
+        assert_eq!(
+            settings.follower_mode_duration,
+            Some(Duration::from_secs(600))
+        );
+        assert_eq!(settings.non_moderator_chat_delay, Some(true));
+        assert_eq!(
+            settings.non_moderator_chat_delay_duration,
+            Some(Duration::from_secs(4))
+        );
+
+        let requests = server.requests();
+        assert!(requests[0]
+            .starts_with("GET /chat/settings?broadcaster_id=713936733 "));
+        assert!(requests[1].starts_with(
+            "GET /chat/settings?broadcaster_id=713936733&moderator_id=713936733 "
+        ));
+    }
+}
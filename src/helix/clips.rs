@@ -0,0 +1,427 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+use chrono::prelude::*;
+use serde::Deserialize;
+
+use super::{
+    Direction,
+    Endpoint,
+    HelixPage,
+    HelixResponse,
+    PageOpts,
+};
+
+use crate::{
+    response::{
+        ApiError,
+        TwitchResult,
+    },
+    TwitchClient,
+};
+
+pub const GET: Endpoint = Endpoint::new("/clips");
+
+/// Gets the clips with the specified ids, at most 100 at once
+///
+/// #### Authentication: App or user access token
+pub fn get(
+    c: &TwitchClient,
+    ids: &[&str],
+) -> TwitchResult<Vec<HelixClip>>
+{
+    let opts = ClipOpts {
+        ids: ids.iter().map(|id| id.to_string()).collect(),
+        ..ClipOpts::default()
+    };
+    Ok(list(c, &opts)?.data)
+}
+
+/// Gets the clips of a specified broadcaster, most viewed first
+///
+/// Supports paging `Direction::Backward` with the cursor of any page.
+///
+/// #### Authentication: App or user access token
+pub fn by_broadcaster(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    opts: &PageOpts,
+) -> TwitchResult<HelixPage<HelixClip>>
+{
+    list(c, &ClipOpts {
+        broadcaster_id: Some(broadcaster_id.to_owned()),
+        first: opts.first,
+        after: opts.after.clone(),
+        direction: opts.direction,
+        ..ClipOpts::default()
+    })
+}
+
+/// Gets the clips matching `opts`, most viewed first
+///
+/// Exactly one of `opts.ids`, `opts.broadcaster_id` and `opts.game_id` has
+/// to be set, the other filters and paging only apply to the latter two.
+///
+/// #### Authentication: App or user access token
+pub fn list(
+    c: &TwitchClient,
+    opts: &ClipOpts,
+) -> TwitchResult<HelixPage<HelixClip>>
+{
+    let r =
+        c.helix_get::<HelixResponse<HelixClip>, _>(&GET, &opts.params()?)?;
+    Ok(HelixPage::from(r))
+}
+
+/// Derives the URL of the MP4 file of `clip` from its thumbnail
+///
+/// Thumbnails of clips hosted on `clips-media-assets` are named like the
+/// video with a `-preview-WIDTHxHEIGHT.jpg` suffix. This convention is
+/// undocumented and may change at any time, `None` is returned if the
+/// thumbnail doesn't follow it.
+pub fn download_url(clip: &HelixClip) -> Option<String> {
+    let (base, size) = clip
+        .thumbnail_url
+        .strip_suffix(".jpg")?
+        .rsplit_once("-preview-")?;
+    let (width, height) = size.split_once('x')?;
+    let is_number =
+        |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    if !is_number(width) || !is_number(height) {
+        return None;
+    }
+    Some(format!("{}.mp4", base))
+}
+
+/// The VOD `clip` was taken from and the offset into it in seconds
+///
+/// Returns `None` if the VOD isn't available, e.g. because it expired or
+/// the broadcaster deleted it.
+pub fn vod_offset(clip: &HelixClip) -> Option<(String, u32)> {
+    match clip.vod_offset {
+        Some(offset) if !clip.video_id.is_empty() => {
+            Some((clip.video_id.clone(), offset))
+        }
+        _ => None,
+    }
+}
+
+///////////////////////////////////////
+// ClipOpts
+///////////////////////////////////////
+/// Filters of `list`, e.g.
+/// `ClipOpts::default().game_id("509670").started_at(week_ago).first(50)`
+#[derive(Debug, Default, Clone)]
+pub struct ClipOpts {
+    /// Up to 100 clip ids
+    pub ids: Vec<String>,
+    pub broadcaster_id: Option<String>,
+    pub game_id: Option<String>,
+    /// Only clips created at or after this time
+    pub started_at: Option<DateTime<Utc>>,
+    /// Only clips created before this time, needs `started_at`
+    pub ended_at: Option<DateTime<Utc>>,
+    /// Only featured clips, or only those which aren't
+    pub is_featured: Option<bool>,
+    /// Maximum number of clips per page, at most 100
+    pub first: Option<u32>,
+    /// Cursor of the page to continue after, or before when paging
+    /// `Direction::Backward`
+    pub after: Option<String>,
+    pub direction: Direction,
+}
+
+impl ClipOpts {
+    pub fn id(
+        mut self,
+        id: &str,
+    ) -> ClipOpts
+    {
+        self.ids.push(id.to_owned());
+        self
+    }
+
+    pub fn broadcaster_id(
+        mut self,
+        broadcaster_id: &str,
+    ) -> ClipOpts
+    {
+        self.broadcaster_id = Some(broadcaster_id.to_owned());
+        self
+    }
+
+    pub fn game_id(
+        mut self,
+        game_id: &str,
+    ) -> ClipOpts
+    {
+        self.game_id = Some(game_id.to_owned());
+        self
+    }
+
+    pub fn started_at(
+        mut self,
+        started_at: DateTime<Utc>,
+    ) -> ClipOpts
+    {
+        self.started_at = Some(started_at);
+        self
+    }
+
+    pub fn ended_at(
+        mut self,
+        ended_at: DateTime<Utc>,
+    ) -> ClipOpts
+    {
+        self.ended_at = Some(ended_at);
+        self
+    }
+
+    pub fn is_featured(
+        mut self,
+        is_featured: bool,
+    ) -> ClipOpts
+    {
+        self.is_featured = Some(is_featured);
+        self
+    }
+
+    pub fn first(
+        mut self,
+        first: u32,
+    ) -> ClipOpts
+    {
+        self.first = Some(first);
+        self
+    }
+
+    pub fn after(
+        mut self,
+        cursor: &str,
+    ) -> ClipOpts
+    {
+        self.after = Some(cursor.to_owned());
+        self.direction = Direction::Forward;
+        self
+    }
+
+    pub fn before(
+        mut self,
+        cursor: &str,
+    ) -> ClipOpts
+    {
+        self.after = Some(cursor.to_owned());
+        self.direction = Direction::Backward;
+        self
+    }
+
+    fn params(&self) -> TwitchResult<Vec<(&'static str, String)>> {
+        let selectors = usize::from(!self.ids.is_empty())
+            + usize::from(self.broadcaster_id.is_some())
+            + usize::from(self.game_id.is_some());
+        if selectors != 1 {
+            return Err(ApiError::invalid_input(
+                "clips are selected by exactly one of ids, broadcaster_id \
+                 and game_id",
+            ));
+        }
+        if self.ids.len() > 100 {
+            return Err(ApiError::invalid_input(
+                "at most 100 clips can be looked up at once",
+            ));
+        }
+
+        let date =
+            |d: &DateTime<Utc>| d.to_rfc3339_opts(SecondsFormat::Secs, true);
+        let mut params: Vec<_> =
+            self.ids.iter().map(|id| ("id", id.clone())).collect();
+        if let Some(ref broadcaster_id) = self.broadcaster_id {
+            params.push(("broadcaster_id", broadcaster_id.clone()));
+        }
+        if let Some(ref game_id) = self.game_id {
+            params.push(("game_id", game_id.clone()));
+        }
+        if let Some(ref started_at) = self.started_at {
+            params.push(("started_at", date(started_at)));
+        }
+        if let Some(ref ended_at) = self.ended_at {
+            params.push(("ended_at", date(ended_at)));
+        }
+        if let Some(is_featured) = self.is_featured {
+            params.push(("is_featured", is_featured.to_string()));
+        }
+        if self.first.is_some_and(|first| first == 0 || first > 100) {
+            return Err(ApiError::invalid_input(
+                "first has to be between 1 and 100",
+            ));
+        }
+        params.extend(
+            PageOpts {
+                first: self.first,
+                after: self.after.clone(),
+                direction: self.direction,
+            }
+            .params(),
+        );
+        Ok(params)
+    }
+}
+
+///////////////////////////////////////
+// GetClips
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone)]
+pub struct HelixClip {
+    pub id: String,
+    pub url: String,
+    pub embed_url: String,
+    pub broadcaster_id: String,
+    pub broadcaster_name: String,
+    pub creator_id: String,
+    pub creator_name: String,
+    /// Empty if the VOD the clip was taken from isn't available
+    pub video_id: String,
+    pub game_id: String,
+    pub language: String,
+    pub title: String,
+    pub view_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub thumbnail_url: String,
+    /// Length in seconds
+    pub duration: f64,
+    /// Seconds into the VOD the clip starts at, `None` if the VOD isn't
+    /// available, see `vod_offset`
+    #[serde(default)]
+    pub vod_offset: Option<u32>,
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use chrono::prelude::*;
+
+    use super::{
+        ClipOpts,
+        HelixClip,
+    };
+    use crate::{
+        helix::HelixResponse,
+        mock,
+        new,
+        response::ApiError,
+    };
+
+    fn clip(thumbnail_url: &str) -> HelixClip {
+        clip_with(thumbnail_url, "205586603", "null")
+    }
+
+    fn clip_with(
+        thumbnail_url: &str,
+        video_id: &str,
+        vod_offset: &str,
+    ) -> HelixClip
+    {
+        let r: HelixResponse<HelixClip> = serde_json::from_str(&format!(
+            r#"{{"data": [{{
+              "id": "AwkwardHelplessSalamanderSwiftRage",
+              "url": "https://clips.twitch.tv/AwkwardHelplessSalamanderSwiftRage",
+              "embed_url": "https://clips.twitch.tv/embed?clip=AwkwardHelplessSalamanderSwiftRage",
+              "broadcaster_id": "67955580",
+              "broadcaster_name": "ChewieMelodies",
+              "creator_id": "53834192",
+              "creator_name": "BlackNova03",
+              "video_id": "{}",
+              "game_id": "488191",
+              "language": "en",
+              "title": "babymetal",
+              "view_count": 10,
+              "created_at": "2017-11-30T22:34:18Z",
+              "thumbnail_url": "{}",
+              "duration": 60.0,
+              "vod_offset": {}
+            }}]}}"#,
+            video_id, thumbnail_url, vod_offset
+        ))
+        .unwrap();
+        r.single().unwrap()
+    }
+
+    #[test]
+    fn download_url() {
+        let c = clip(
+            "https://clips-media-assets.twitch.tv/157589949-preview-480x272.jpg",
+        );
+        assert_eq!(c.duration, 60.0);
+        assert_eq!(
+            super::download_url(&c).unwrap(),
+            "https://clips-media-assets.twitch.tv/157589949.mp4"
+        );
+
+        let c = clip(
+            "https://clips-media-assets2.twitch.tv/AT-cm%7C1148159155-preview-480x272.jpg",
+        );
+        assert_eq!(
+            super::download_url(&c).unwrap(),
+            "https://clips-media-assets2.twitch.tv/AT-cm%7C1148159155.mp4"
+        );
+    }
+
+    #[test]
+    fn download_url_unknown_pattern() {
+        for thumbnail in &[
+            "https://static-cdn.jtvnw.net/twitch-clips-thumbnails-prod/AwkwardHelplessSalamanderSwiftRage/preview.jpg",
+            "https://clips-media-assets.twitch.tv/157589949-preview-480x272.png",
+            "https://clips-media-assets.twitch.tv/157589949-preview-{width}x{height}.jpg",
+            "",
+        ] {
+            assert_eq!(super::download_url(&clip(thumbnail)), None, "{}", thumbnail);
+        }
+    }
+
+    #[test]
+    fn vod_offset() {
+        let c = clip_with("", "205586603", "480");
+        assert_eq!(super::vod_offset(&c), Some(("205586603".to_owned(), 480)));
+
+        let c = clip_with("", "", "null");
+        assert_eq!(c.vod_offset, None);
+        assert_eq!(super::vod_offset(&c), None);
+    }
+
+    #[test]
+    fn clip_opts() {
+        let server = mock::serve(vec![mock::response(
+            200,
+            &[],
+            r#"{"data": [], "pagination": {}}"#,
+        )]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let opts = ClipOpts::default()
+            .game_id("509670")
+            .started_at(Utc.with_ymd_and_hms(2021, 3, 1, 0, 0, 0).unwrap())
+            .is_featured(true)
+            .first(50);
+        assert!(super::list(&c, &opts).unwrap().data.is_empty());
+
+        for opts in &[
+            ClipOpts::default(),
+            ClipOpts::default().broadcaster_id("1337").game_id("509670"),
+            ClipOpts::default().broadcaster_id("1337").first(0),
+            ClipOpts::default().broadcaster_id("1337").first(101),
+        ] {
+            match super::list(&c, opts) {
+                Err(ApiError::InvalidInput(_)) => (),
+                r => panic!("{:?}", r),
+            }
+        }
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with(
+            "GET /clips?game_id=509670&started_at=2021-03-01T00%3A00%3A00Z&is_featured=true&first=50 "
+        ));
+    }
+}
@@ -0,0 +1,245 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+//! Conversions from Kraken types to their Helix counterparts
+//!
+//! These let apps move to Helix one endpoint at a time while keeping the
+//! Helix types internally. Kraken lacks some Helix fields, those are left
+//! empty as documented on each conversion.
+
+use crate::kraken;
+
+use super::{
+    channels::ChannelInfo,
+    games::HelixGame,
+    streams::HelixStream,
+    users::HelixUser,
+};
+
+/// Kraken only names the game, so `game_id` is empty, and it has no tags.
+/// `title` is the channel's status, `viewers` becomes `viewer_count` and
+/// `thumbnail_url` is the `template` preview, if there is one.
+impl From<kraken::streams::Stream> for HelixStream {
+    fn from(s: kraken::streams::Stream) -> HelixStream {
+        HelixStream {
+            id: s.id,
+            user_id: s.channel.id,
+            user_login: s.channel.name,
+            user_name: s.channel.display_name,
+            game_id: String::new(),
+            game_name: s.game,
+            _type: s.stream_type,
+            title: s.channel.status,
+            viewer_count: s.viewers,
+            started_at: s.created_at,
+            language: s.channel.broadcaster_language,
+            thumbnail_url: s
+                .preview
+                .get("template")
+                .cloned()
+                .unwrap_or_default(),
+            tags: Vec::new(),
+            is_mature: s.channel.mature.unwrap_or(false),
+        }
+    }
+}
+
+/// Kraken doesn't report the broadcaster type or the offline image, so
+/// `broadcaster_type` and `offline_image_url` are empty.
+impl From<kraken::users::User> for HelixUser {
+    fn from(u: kraken::users::User) -> HelixUser {
+        HelixUser {
+            id: u.id,
+            login: u.name,
+            display_name: u.display_name,
+            _type: u._type,
+            broadcaster_type: String::new(),
+            description: u.bio,
+            profile_image_url: u.logo,
+            offline_image_url: String::new(),
+            email: u.email,
+            created_at: u.created_at,
+        }
+    }
+}
+
+/// Kraken only names the game, so `game_id` is empty, and it has neither
+/// the stream delay nor tags, so `delay` is `0` and `tags` is empty.
+/// `title` is the channel's status.
+impl From<kraken::channels::Channel> for ChannelInfo {
+    fn from(c: kraken::channels::Channel) -> ChannelInfo {
+        ChannelInfo {
+            broadcaster_id: c.id,
+            broadcaster_login: c.name,
+            broadcaster_name: c.display_name,
+            broadcaster_language: c.broadcaster_language,
+            game_id: String::new(),
+            game_name: c.game,
+            title: c.status,
+            delay: 0,
+            tags: Vec::new(),
+        }
+    }
+}
+
+/// `box_art_url` is the `template` box art, if there is one. `igdb_id` is
+/// empty, Kraken only knows the Giant Bomb id.
+impl From<kraken::games::Game> for HelixGame {
+    fn from(g: kraken::games::Game) -> HelixGame {
+        HelixGame {
+            id: g.id,
+            name: g.name,
+            box_art_url: g._box.get("template").cloned().unwrap_or_default(),
+            igdb_id: String::new(),
+        }
+    }
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        helix::{
+            channels::ChannelInfo,
+            games::HelixGame,
+            streams::{
+                HelixStream,
+                StreamType,
+            },
+            users::HelixUser,
+        },
+        kraken,
+    };
+
+    const CHANNEL: &str = r#"{
+      "_id": 23161357,
+      "broadcaster_language": "en",
+      "created_at": "2011-06-03T17:49:19Z",
+      "display_name": "LIRIK",
+      "email": null,
+      "followers": 4461420,
+      "game": "Hunt: Showdown",
+      "language": "en",
+      "logo": "https://static-cdn.jtvnw.net/jtv_user_pictures/lirik-profile_image-300x300.png",
+      "mature": true,
+      "name": "lirik",
+      "partner": true,
+      "profile_banner": null,
+      "profile_banner_background_color": null,
+      "status": "Hunt with the boys",
+      "stream_key": null,
+      "updated_at": "2020-04-20T16:31:38Z",
+      "url": "https://www.twitch.tv/lirik",
+      "video_banner": null,
+      "views": 351322072
+    }"#;
+
+    #[test]
+    fn stream() {
+        let s: kraken::streams::Stream = serde_json::from_str(&format!(
+            r#"{{
+              "_id": 38208405792,
+              "game": "Hunt: Showdown",
+              "viewers": 27012,
+              "video_height": 1080,
+              "average_fps": 60,
+              "delay": 0,
+              "created_at": "2020-04-20T15:02:41Z",
+              "is_playlist": false,
+              "stream_type": "live",
+              "preview": {{
+                "template": "https://static-cdn.jtvnw.net/previews-ttv/live_user_lirik-{{width}}x{{height}}.jpg"
+              }},
+              "channel": {}
+            }}"#,
+            CHANNEL
+        ))
+        .unwrap();
+
+        let s = HelixStream::from(s);
+        assert_eq!(s.id, "38208405792");
+        assert_eq!(s.user_id, "23161357");
+        assert_eq!(s.user_login, "lirik");
+        assert_eq!(s.user_name, "LIRIK");
+        assert_eq!(s.game_id, "");
+        assert_eq!(s.game_name, "Hunt: Showdown");
+        assert_eq!(s._type, StreamType::Live);
+        assert_eq!(s.title, "Hunt with the boys");
+        assert_eq!(s.viewer_count, 27012);
+        assert_eq!(s.language, "en");
+        assert!(s.thumbnail_url.ends_with("lirik-{width}x{height}.jpg"));
+        assert!(s.is_mature);
+    }
+
+    #[test]
+    fn channel() {
+        let c: kraken::channels::Channel =
+            serde_json::from_str(CHANNEL).unwrap();
+
+        let c = ChannelInfo::from(c);
+        assert_eq!(c.broadcaster_id, "23161357");
+        assert_eq!(c.broadcaster_login, "lirik");
+        assert_eq!(c.game_name, "Hunt: Showdown");
+        assert_eq!(c.title, "Hunt with the boys");
+        assert_eq!(c.delay, 0);
+    }
+
+    #[test]
+    fn user() {
+        let u: kraken::users::User = serde_json::from_str(
+            r#"{
+              "_id": 44322889,
+              "bio": "Just a gamer playing games and chatting. :)",
+              "created_at": "2013-06-03T19:12:02Z",
+              "display_name": "dallas",
+              "email": "email-address@provider.com",
+              "email_verified": true,
+              "logo": "https://static-cdn.jtvnw.net/jtv_user_pictures/dallas-profile_image-1a2c906ee2c35f12-300x300.png",
+              "name": "dallas",
+              "notifications": {"email": false, "push": true},
+              "type": "staff",
+              "updated_at": "2016-12-14T01:01:44Z"
+            }"#,
+        )
+        .unwrap();
+
+        let u = HelixUser::from(u);
+        assert_eq!(u.id, "44322889");
+        assert_eq!(u.login, "dallas");
+        assert_eq!(u._type, "staff");
+        assert_eq!(
+            u.description,
+            "Just a gamer playing games and chatting. :)"
+        );
+        assert!(u.profile_image_url.ends_with("300x300.png"));
+        assert_eq!(u.email.as_deref(), Some("email-address@provider.com"));
+    }
+
+    #[test]
+    fn game() {
+        let g: kraken::games::Game = serde_json::from_str(
+            r#"{
+              "_id": 488552,
+              "box": {
+                "large": "https://static-cdn.jtvnw.net/ttv-boxart/Overwatch-272x380.jpg",
+                "template": "https://static-cdn.jtvnw.net/ttv-boxart/Overwatch-{width}x{height}.jpg"
+              },
+              "giantbomb_id": 48190,
+              "logo": {},
+              "name": "Overwatch",
+              "popularity": 117057
+            }"#,
+        )
+        .unwrap();
+
+        let g = HelixGame::from(g);
+        assert_eq!(g.id, "488552");
+        assert_eq!(g.name, "Overwatch");
+        assert_eq!(
+            g.box_art_url,
+            "https://static-cdn.jtvnw.net/ttv-boxart/Overwatch-{width}x{height}.jpg"
+        );
+    }
+}
@@ -0,0 +1,267 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+//! Twitch Drops entitlements
+//!
+//! Games grant the rewards users claimed on Twitch and mark them as
+//! fulfilled afterwards, so they aren't granted twice.
+
+use chrono::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use super::{
+    Endpoint,
+    HelixPage,
+    HelixResponse,
+};
+
+use crate::{
+    response::{
+        ApiError,
+        TwitchResult,
+    },
+    TwitchClient,
+};
+
+pub const GET: Endpoint = Endpoint::new("/entitlements/drops");
+
+/// Gets the Drops entitlements of the organization owning the client
+///
+/// With an app access token `opts` can filter by any user, with a user
+/// access token only the entitlements of that user are returned.
+///
+/// #### Authentication: App or user access token
+pub fn get(
+    c: &TwitchClient,
+    opts: &EntitlementOpts,
+) -> TwitchResult<HelixPage<Entitlement>>
+{
+    let mut params = Vec::new();
+    if let Some(ref user_id) = opts.user_id {
+        params.push(("user_id", user_id.clone()));
+    }
+    if let Some(ref game_id) = opts.game_id {
+        params.push(("game_id", game_id.clone()));
+    }
+    if let Some(status) = opts.fulfillment_status {
+        params.push(("fulfillment_status", status.as_str().to_owned()));
+    }
+    if let Some(first) = opts.first {
+        params.push(("first", first.to_string()));
+    }
+    if let Some(ref after) = opts.after {
+        params.push(("after", after.clone()));
+    }
+    let r = c.helix_get::<HelixResponse<Entitlement>, _>(&GET, &params)?;
+    Ok(HelixPage::from(r))
+}
+
+pub const UPDATE: Endpoint = Endpoint::new("/entitlements/drops");
+
+/// Sets the fulfillment status of up to 100 entitlements
+///
+/// Ids which couldn't be updated don't fail the request, they are grouped
+/// by the reason in the returned results.
+///
+/// #### Authentication: App or user access token
+pub fn update(
+    c: &TwitchClient,
+    entitlement_ids: &[&str],
+    fulfillment_status: FulfillmentStatus,
+) -> TwitchResult<Vec<EntitlementUpdate>>
+{
+    if entitlement_ids.len() > 100 {
+        return Err(ApiError::invalid_input(
+            "at most 100 entitlements can be updated at once",
+        ));
+    }
+    let r = c.helix_patch::<_, HelixResponse<EntitlementUpdate>, _>(
+        &UPDATE,
+        &(),
+        &NewFulfillmentStatus {
+            entitlement_ids,
+            fulfillment_status,
+        },
+    )?;
+    Ok(r.data)
+}
+
+///////////////////////////////////////
+// EntitlementOpts
+///////////////////////////////////////
+/// Filters of `get`
+#[derive(Debug, Default, Clone)]
+pub struct EntitlementOpts {
+    pub user_id: Option<String>,
+    pub game_id: Option<String>,
+    pub fulfillment_status: Option<FulfillmentStatus>,
+    /// Maximum number of entitlements per page, at most 1000
+    pub first: Option<u32>,
+    /// Cursor of the page to continue after
+    pub after: Option<String>,
+}
+
+///////////////////////////////////////
+// GetDropsEntitlements
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone)]
+pub struct Entitlement {
+    pub id: String,
+    pub benefit_id: String,
+    /// When the user claimed the reward
+    pub timestamp: DateTime<Utc>,
+    pub user_id: String,
+    pub game_id: String,
+    pub fulfillment_status: FulfillmentStatus,
+    pub last_updated: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum FulfillmentStatus {
+    /// The user claimed the reward, but the game didn't grant it yet
+    Claimed,
+    /// The game granted the reward
+    Fulfilled,
+}
+
+impl FulfillmentStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FulfillmentStatus::Claimed => "CLAIMED",
+            FulfillmentStatus::Fulfilled => "FULFILLED",
+        }
+    }
+}
+
+///////////////////////////////////////
+// UpdateDropsEntitlements
+///////////////////////////////////////
+/// The entitlements of an update which ended with the same `status`
+#[derive(Deserialize, Debug, Clone)]
+pub struct EntitlementUpdate {
+    pub status: UpdateStatus,
+    pub ids: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum UpdateStatus {
+    Success,
+    /// The id isn't a valid entitlement id
+    InvalidId,
+    /// The entitlement doesn't exist
+    NotFound,
+    /// The client isn't allowed to update the entitlement
+    Unauthorized,
+    /// Twitch failed to update the entitlement, retry later
+    UpdateFailed,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Serialize, Debug)]
+struct NewFulfillmentStatus<'a> {
+    entitlement_ids: &'a [&'a str],
+    fulfillment_status: FulfillmentStatus,
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        EntitlementOpts,
+        FulfillmentStatus,
+        UpdateStatus,
+    };
+    use crate::{
+        mock,
+        new,
+        response::ApiError,
+    };
+
+    #[test]
+    fn get() {
+        let server = mock::serve(vec![mock::response(
+            200,
+            &[],
+            r#"{
+              "data": [{
+                "id": "fb78259e-fb81-4d1b-8333-34a06ffc24c0",
+                "benefit_id": "74c52265-e214-48a6-91b9-23b6014e8041",
+                "timestamp": "2019-01-28T04:17:53.325Z",
+                "user_id": "25009227",
+                "game_id": "33214",
+                "fulfillment_status": "CLAIMED",
+                "last_updated": "2019-01-28T04:17:53.325Z"
+              }],
+              "pagination": {"cursor": "eyJiIjpudWxsLCJhIjp7fX0"}
+            }"#,
+        )]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let opts = EntitlementOpts {
+            game_id: Some("33214".to_owned()),
+            fulfillment_status: Some(FulfillmentStatus::Claimed),
+            ..EntitlementOpts::default()
+        };
+        let page = super::get(&c, &opts).unwrap();
+        assert_eq!(page.data[0].user_id, "25009227");
+        assert_eq!(page.data[0].fulfillment_status, FulfillmentStatus::Claimed);
+        assert!(page.cursor.is_some());
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with(
+            "GET /entitlements/drops?game_id=33214&fulfillment_status=CLAIMED "
+        ));
+    }
+
+    #[test]
+    fn update() {
+        let server = mock::serve(vec![mock::response(
+            200,
+            &[],
+            r#"{
+              "data": [
+                {"status": "SUCCESS", "ids": ["fb78259e-fb81-4d1b-8333-34a06ffc24c0"]},
+                {"status": "NOT_FOUND", "ids": ["862750a5-265e-4ab6-9f0a-c64df3d54dd0"]}
+              ]
+            }"#,
+        )]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let results = super::update(
+            &c,
+            &[
+                "fb78259e-fb81-4d1b-8333-34a06ffc24c0",
+                "862750a5-265e-4ab6-9f0a-c64df3d54dd0",
+            ],
+            FulfillmentStatus::Fulfilled,
+        )
+        .unwrap();
+        assert_eq!(results[0].status, UpdateStatus::Success);
+        assert_eq!(results[1].status, UpdateStatus::NotFound);
+        assert_eq!(results[1].ids, ["862750a5-265e-4ab6-9f0a-c64df3d54dd0"]);
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with("PATCH /entitlements/drops "));
+        assert!(requests[0].ends_with(r#""fulfillment_status":"FULFILLED"}"#));
+    }
+
+    #[test]
+    fn too_many_updates() {
+        let c = new(String::from("<clientid>"));
+        let ids = vec!["id"; 101];
+        match super::update(&c, &ids, FulfillmentStatus::Fulfilled) {
+            Err(ApiError::InvalidInput(_)) => (),
+            r => panic!("{:?}", r),
+        }
+    }
+}
@@ -0,0 +1,428 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+//! Conduits spread the notifications of EventSub subscriptions across
+//! several webhook or websocket shards
+//!
+//! All endpoints require an app access token of the client owning the
+//! conduits.
+//!
+//! https://dev.twitch.tv/docs/eventsub/handling-conduit-events
+
+use chrono::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    helix::{
+        Endpoint,
+        HelixPage,
+        HelixResponse,
+        PageOpts,
+    },
+    response::TwitchResult,
+    TwitchClient,
+};
+
+pub const GET: Endpoint = Endpoint::new("/eventsub/conduits");
+
+/// Gets the conduits of the client
+///
+/// #### Authentication: App access token
+pub fn get(c: &TwitchClient) -> TwitchResult<Vec<Conduit>> {
+    let r = c.helix_get::<HelixResponse<Conduit>, _>(&GET, &())?;
+    Ok(r.data)
+}
+
+pub const CREATE: Endpoint = Endpoint::new("/eventsub/conduits");
+
+/// Creates a conduit with `shard_count` shards
+///
+/// The shards have no transport yet, assign them one with `update_shards`.
+///
+/// #### Authentication: App access token
+pub fn create(
+    c: &TwitchClient,
+    shard_count: u32,
+) -> TwitchResult<Conduit>
+{
+    let r = c.helix_post::<_, HelixResponse<Conduit>, _>(
+        &CREATE,
+        &(),
+        &ConduitUpdate {
+            id: None,
+            shard_count,
+        },
+    )?;
+    r.single()
+}
+
+pub const UPDATE: Endpoint = Endpoint::new("/eventsub/conduits");
+
+/// Changes the number of shards of a conduit
+///
+/// Shards beyond the new count are removed, new shards have no transport.
+///
+/// #### Authentication: App access token
+pub fn update(
+    c: &TwitchClient,
+    id: &str,
+    shard_count: u32,
+) -> TwitchResult<Conduit>
+{
+    let r = c.helix_patch::<_, HelixResponse<Conduit>, _>(
+        &UPDATE,
+        &(),
+        &ConduitUpdate {
+            id: Some(id),
+            shard_count,
+        },
+    )?;
+    r.single()
+}
+
+pub const DELETE: Endpoint = Endpoint::new("/eventsub/conduits");
+
+/// Deletes a conduit together with its shards
+///
+/// #### Authentication: App access token
+pub fn delete(
+    c: &TwitchClient,
+    id: &str,
+) -> TwitchResult<()>
+{
+    c.helix_delete::<(), _>(&DELETE, &[("id", id)])
+}
+
+pub const GET_SHARDS: Endpoint = Endpoint::new("/eventsub/conduits/shards");
+
+/// Gets the shards of a conduit, optionally only those with `status`
+///
+/// #### Authentication: App access token
+pub fn get_shards(
+    c: &TwitchClient,
+    conduit_id: &str,
+    status: Option<ShardStatus>,
+    opts: &PageOpts,
+) -> TwitchResult<HelixPage<Shard>>
+{
+    opts.check_forward()?;
+    let r =
+        c.helix_get::<HelixResponse<Shard>, _>(&GET_SHARDS, &ShardQuery {
+            conduit_id,
+            status,
+            after: opts.after.as_deref(),
+        })?;
+    Ok(HelixPage::from(r))
+}
+
+pub const UPDATE_SHARDS: Endpoint = Endpoint::new("/eventsub/conduits/shards");
+
+/// Assigns transports to shards of a conduit
+///
+/// Shards Twitch couldn't update are reported in `ShardUpdates::errors`
+/// instead of failing the whole request.
+///
+/// #### Authentication: App access token
+pub fn update_shards(
+    c: &TwitchClient,
+    conduit_id: &str,
+    shards: &[ShardUpdate],
+) -> TwitchResult<ShardUpdates>
+{
+    c.helix_patch(&UPDATE_SHARDS, &(), &ShardsUpdate { conduit_id, shards })
+}
+
+///////////////////////////////////////
+// Conduits
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Conduit {
+    pub id: String,
+    pub shard_count: u32,
+}
+
+#[derive(Serialize, Debug)]
+struct ConduitUpdate<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<&'a str>,
+    shard_count: u32,
+}
+
+///////////////////////////////////////
+// Shards
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone)]
+pub struct Shard {
+    /// Index of the shard, starting at `"0"`
+    pub id: String,
+    pub status: ShardStatus,
+    pub transport: ShardTransport,
+}
+
+/// Why a shard is, or isn't, receiving notifications
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShardStatus {
+    Enabled,
+    WebhookCallbackVerificationPending,
+    WebhookCallbackVerificationFailed,
+    NotificationFailuresExceeded,
+    WebsocketDisconnected,
+    WebsocketFailedPingPong,
+    WebsocketReceivedInboundTraffic,
+    WebsocketInternalError,
+    WebsocketNetworkTimeout,
+    WebsocketNetworkError,
+    WebsocketFailedToReconnect,
+    /// A status this crate doesn't know yet, don't filter by it
+    #[serde(other)]
+    Unknown,
+}
+
+impl ShardStatus {
+    /// Whether the shard is disabled and needs a new transport
+    pub fn is_disabled(self) -> bool {
+        self != ShardStatus::Enabled
+            && self != ShardStatus::WebhookCallbackVerificationPending
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum ShardTransport {
+    Webhook {
+        callback: String,
+    },
+    Websocket {
+        session_id: String,
+        connected_at: Option<DateTime<Utc>>,
+        disconnected_at: Option<DateTime<Utc>>,
+    },
+}
+
+#[derive(Serialize, Debug)]
+struct ShardQuery<'a> {
+    conduit_id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<ShardStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    after: Option<&'a str>,
+}
+
+/// The transport to assign to the shard `id`
+#[derive(Serialize, Debug, Clone)]
+pub struct ShardUpdate {
+    pub id: String,
+    pub transport: TransportUpdate,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum TransportUpdate {
+    /// `secret` signs the notifications, 10 to 100 ASCII characters
+    Webhook { callback: String, secret: String },
+    /// `session_id` from the welcome message of the websocket
+    Websocket { session_id: String },
+}
+
+#[derive(Serialize, Debug)]
+struct ShardsUpdate<'a> {
+    conduit_id: &'a str,
+    shards: &'a [ShardUpdate],
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ShardUpdates {
+    /// The shards which were updated
+    pub data: Vec<Shard>,
+    #[serde(default)]
+    pub errors: Vec<ShardError>,
+}
+
+/// A shard Twitch refused to update
+#[derive(Deserialize, Debug, Clone)]
+pub struct ShardError {
+    /// Index of the shard
+    pub id: String,
+    pub message: String,
+    pub code: String,
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Shard,
+        ShardStatus,
+        ShardTransport,
+        ShardUpdate,
+        TransportUpdate,
+    };
+    use crate::{
+        helix::{
+            HelixResponse,
+            PageOpts,
+        },
+        mock,
+        new,
+    };
+
+    #[test]
+    fn shards() {
+        let r: HelixResponse<Shard> = serde_json::from_str(
+            r#"{
+              "data": [{
+                "id": "0",
+                "status": "enabled",
+                "transport": {
+                  "method": "webhook",
+                  "callback": "https://this-is-a-callback.com"
+                }
+              }, {
+                "id": "1",
+                "status": "websocket_disconnected",
+                "transport": {
+                  "method": "websocket",
+                  "session_id": "9fd5164a-a958-4c60-b7f4-6a7202506ca0",
+                  "connected_at": "2020-11-10T14:32:18.730260295Z",
+                  "disconnected_at": "2020-11-11T14:32:18.730260295Z"
+                }
+              }, {
+                "id": "2",
+                "status": "some_future_status",
+                "transport": {
+                  "method": "websocket",
+                  "session_id": "238b4b08-13f1-4b8f-8d31-56665a7a9d9f",
+                  "connected_at": "2020-11-10T14:32:18.730260295Z",
+                  "disconnected_at": null
+                }
+              }],
+              "pagination": {}
+            }"#,
+        )
+        .unwrap();
+
+        assert!(!r.data[0].status.is_disabled());
+        assert!(r.data[1].status.is_disabled());
+        assert_eq!(r.data[2].status, ShardStatus::Unknown);
+        match r.data[2].transport {
+            ShardTransport::Websocket {
+                disconnected_at: None,
+                ..
+            } => (),
+            ref t => panic!("{:?}", t),
+        }
+    }
+
+    #[test]
+    fn create_update_delete() {
+        let conduit = r#"{"data": [{
+          "id": "bfcfc993-26b1-b876-44d9-afe75a379dac",
+          "shard_count": 5
+        }]}"#;
+        let server = mock::serve(vec![
+            mock::response(200, &[], conduit),
+            mock::response(200, &[], conduit),
+            mock::response(204, &[], ""),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let id = super::create(&c, 5).unwrap().id;
+        assert_eq!(super::update(&c, &id, 5).unwrap().shard_count, 5);
+        super::delete(&c, &id).unwrap();
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with("POST /eventsub/conduits "));
+        assert!(requests[0].ends_with(r#"{"shard_count":5}"#));
+        assert!(requests[1].starts_with("PATCH /eventsub/conduits "));
+        assert!(requests[1].ends_with(
+            r#"{"id":"bfcfc993-26b1-b876-44d9-afe75a379dac","shard_count":5}"#
+        ));
+        assert!(requests[2].starts_with(
+            "DELETE /eventsub/conduits?id=bfcfc993-26b1-b876-44d9-afe75a379dac "
+        ));
+    }
+
+    #[test]
+    fn get_shards_filter() {
+        let server = mock::serve(vec![mock::response(
+            200,
+            &[],
+            r#"{"data": [], "pagination": {}}"#,
+        )]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let page = super::get_shards(
+            &c,
+            "bfcfc993",
+            Some(ShardStatus::WebsocketDisconnected),
+            &PageOpts::default(),
+        )
+        .unwrap();
+        assert!(page.data.is_empty());
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with(
+            "GET /eventsub/conduits/shards?conduit_id=bfcfc993&status=websocket_disconnected "
+        ));
+    }
+
+    #[test]
+    fn update_shards_errors() {
+        let server = mock::serve(vec![mock::response(
+            202,
+            &[],
+            r#"{
+              "data": [{
+                "id": "0",
+                "status": "enabled",
+                "transport": {
+                  "method": "webhook",
+                  "callback": "https://this-is-a-callback.com"
+                }
+              }],
+              "errors": [{
+                "id": "1",
+                "message": "The length of the string in the secret field is not valid.",
+                "code": "invalid_parameter"
+              }]
+            }"#,
+        )]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let r = super::update_shards(&c, "bfcfc993", &[
+            ShardUpdate {
+                id: "0".to_owned(),
+                transport: TransportUpdate::Webhook {
+                    callback: "https://this-is-a-callback.com".to_owned(),
+                    secret: "s3cre7s3cre7".to_owned(),
+                },
+            },
+            ShardUpdate {
+                id: "1".to_owned(),
+                transport: TransportUpdate::Webhook {
+                    callback: "https://this-is-a-callback.com".to_owned(),
+                    secret: "short".to_owned(),
+                },
+            },
+        ])
+        .unwrap();
+        assert_eq!(r.data.len(), 1);
+        assert_eq!(r.errors[0].id, "1");
+        assert_eq!(r.errors[0].code, "invalid_parameter");
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with("PATCH /eventsub/conduits/shards "));
+        assert!(requests[0].contains(
+            r#""shards":[{"id":"0","transport":{"method":"webhook","callback""#
+        ));
+    }
+}
@@ -0,0 +1,677 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+//! Typed EventSub notifications
+//!
+//! `Notification::from_slice` parses the body Twitch sends to a webhook, or
+//! the `payload` of a websocket notification message, and picks the event
+//! struct by the subscription type.
+//!
+//! https://dev.twitch.tv/docs/eventsub/eventsub-reference
+
+use chrono::prelude::*;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::subscriptions::EventSubSubscription;
+
+use crate::{
+    helix::subscriptions::SubTier,
+    response::TwitchResult,
+};
+
+///////////////////////////////////////
+// Notification
+///////////////////////////////////////
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub subscription: EventSubSubscription,
+    pub event: EventSubEvent,
+}
+
+impl Notification {
+    /// Parses a notification, events of unknown types become
+    /// `EventSubEvent::Unknown`
+    pub fn from_slice(body: &[u8]) -> TwitchResult<Notification> {
+        Notification::from_value(serde_json::from_slice(body)?)
+    }
+
+    /// Like `from_slice`, for a body which was already parsed
+    pub fn from_value(body: Value) -> TwitchResult<Notification> {
+        #[derive(Deserialize)]
+        struct Raw {
+            subscription: EventSubSubscription,
+            event: Value,
+        }
+
+        let raw: Raw = serde_json::from_value(body)?;
+        let event = EventSubEvent::parse(&raw.subscription._type, raw.event)?;
+        Ok(Notification {
+            subscription: raw.subscription,
+            event,
+        })
+    }
+}
+
+/// The event of a notification, by subscription type
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum EventSubEvent {
+    /// `stream.online`
+    StreamOnline(StreamOnlineEvent),
+    /// `stream.offline`
+    StreamOffline(StreamOfflineEvent),
+    /// `channel.subscribe`, a new subscription, gifted or not, but no
+    /// resubscriptions
+    ChannelSubscribe(ChannelSubscribeEvent),
+    /// `channel.subscription.gift`, one user gifting one or more
+    /// subscriptions, each of which is also sent as `ChannelSubscribe`
+    ChannelSubscriptionGift(ChannelSubscriptionGiftEvent),
+    /// `channel.subscription.message`, a resubscription shared in chat
+    ChannelSubscriptionMessage(ChannelSubscriptionMessageEvent),
+    /// `channel.cheer`
+    ChannelCheer(ChannelCheerEvent),
+    /// `channel.raid`
+    ChannelRaid(ChannelRaidEvent),
+    /// `channel.shared_chat.begin`
+    SharedChatBegin(SharedChatEvent),
+    /// `channel.shared_chat.update`
+    SharedChatUpdate(SharedChatEvent),
+    /// `channel.shared_chat.end`
+    SharedChatEnd(SharedChatEndEvent),
+    /// A type this crate has no struct for, with the event as sent
+    Unknown { _type: String, event: Value },
+}
+
+impl EventSubEvent {
+    /// Parses `event` as the event struct of `subscription_type`
+    pub fn parse(
+        subscription_type: &str,
+        event: Value,
+    ) -> TwitchResult<EventSubEvent>
+    {
+        Ok(match subscription_type {
+            "stream.online" => {
+                EventSubEvent::StreamOnline(serde_json::from_value(event)?)
+            }
+            "stream.offline" => {
+                EventSubEvent::StreamOffline(serde_json::from_value(event)?)
+            }
+            "channel.subscribe" => {
+                EventSubEvent::ChannelSubscribe(serde_json::from_value(event)?)
+            }
+            "channel.subscription.gift" => {
+                EventSubEvent::ChannelSubscriptionGift(serde_json::from_value(
+                    event,
+                )?)
+            }
+            "channel.subscription.message" => {
+                EventSubEvent::ChannelSubscriptionMessage(
+                    serde_json::from_value(event)?,
+                )
+            }
+            "channel.cheer" => {
+                EventSubEvent::ChannelCheer(serde_json::from_value(event)?)
+            }
+            "channel.raid" => {
+                EventSubEvent::ChannelRaid(serde_json::from_value(event)?)
+            }
+            "channel.shared_chat.begin" => {
+                EventSubEvent::SharedChatBegin(serde_json::from_value(event)?)
+            }
+            "channel.shared_chat.update" => {
+                EventSubEvent::SharedChatUpdate(serde_json::from_value(event)?)
+            }
+            "channel.shared_chat.end" => {
+                EventSubEvent::SharedChatEnd(serde_json::from_value(event)?)
+            }
+            _ => EventSubEvent::Unknown {
+                _type: subscription_type.to_owned(),
+                event,
+            },
+        })
+    }
+}
+
+///////////////////////////////////////
+// StreamOnline
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct StreamOnlineEvent {
+    /// Id of the stream, as in `HelixStream::id`
+    pub id: String,
+    pub broadcaster_user_id: String,
+    pub broadcaster_user_login: String,
+    pub broadcaster_user_name: String,
+    #[serde(rename = "type")]
+    pub _type: OnlineType,
+    pub started_at: DateTime<Utc>,
+}
+
+/// What kind of stream went online
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OnlineType {
+    Live,
+    Playlist,
+    WatchParty,
+    Premiere,
+    Rerun,
+    /// A type this crate doesn't know yet
+    #[serde(other)]
+    Other,
+}
+
+///////////////////////////////////////
+// StreamOffline
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct StreamOfflineEvent {
+    pub broadcaster_user_id: String,
+    pub broadcaster_user_login: String,
+    pub broadcaster_user_name: String,
+}
+
+///////////////////////////////////////
+// ChannelSubscribe
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChannelSubscribeEvent {
+    /// The subscriber, the recipient for gifted subscriptions
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+    pub broadcaster_user_id: String,
+    pub broadcaster_user_login: String,
+    pub broadcaster_user_name: String,
+    pub tier: SubTier,
+    pub is_gift: bool,
+}
+
+///////////////////////////////////////
+// ChannelSubscriptionGift
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChannelSubscriptionGiftEvent {
+    pub is_anonymous: bool,
+    /// The gifter, `None` if `is_anonymous`
+    pub user_id: Option<String>,
+    pub user_login: Option<String>,
+    pub user_name: Option<String>,
+    pub broadcaster_user_id: String,
+    pub broadcaster_user_login: String,
+    pub broadcaster_user_name: String,
+    /// Subscriptions gifted at once
+    pub total: u32,
+    pub tier: SubTier,
+    /// Subscriptions the user gifted in the channel so far, `None` if
+    /// `is_anonymous` or the user doesn't share it
+    pub cumulative_total: Option<u32>,
+}
+
+///////////////////////////////////////
+// ChannelSubscriptionMessage
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChannelSubscriptionMessageEvent {
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+    pub broadcaster_user_id: String,
+    pub broadcaster_user_login: String,
+    pub broadcaster_user_name: String,
+    pub tier: SubTier,
+    pub message: SubscriptionMessage,
+    /// Months the user was subscribed in total
+    pub cumulative_months: u32,
+    /// Months the user was subscribed in a row, `None` if they don't share
+    /// it
+    pub streak_months: Option<u32>,
+    /// Months the subscription was paid for in advance
+    pub duration_months: u32,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SubscriptionMessage {
+    pub text: String,
+    /// Emotes in `text`, `None` if there are none
+    pub emotes: Option<Vec<MessageEmote>>,
+}
+
+/// An emote in a message, by the character positions of its name
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MessageEmote {
+    pub begin: usize,
+    pub end: usize,
+    pub id: String,
+}
+
+///////////////////////////////////////
+// ChannelCheer
+///////////////////////////////////////
+/// Bits cheered in the broadcaster's chat
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChannelCheerEvent {
+    pub is_anonymous: bool,
+    /// The cheering user, `None` if `is_anonymous`
+    pub user_id: Option<String>,
+    pub user_login: Option<String>,
+    pub user_name: Option<String>,
+    pub broadcaster_user_id: String,
+    pub broadcaster_user_login: String,
+    pub broadcaster_user_name: String,
+    /// The chat message, including the cheermotes
+    pub message: String,
+    pub bits: u32,
+}
+
+///////////////////////////////////////
+// ChannelRaid
+///////////////////////////////////////
+/// A raid from one channel to another
+///
+/// Subscribe with `Condition::raid_to` for raids a broadcaster receives,
+/// e.g. to welcome the raiders, or `Condition::raid_from` for the raids
+/// they start.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChannelRaidEvent {
+    pub from_broadcaster_user_id: String,
+    pub from_broadcaster_user_login: String,
+    pub from_broadcaster_user_name: String,
+    pub to_broadcaster_user_id: String,
+    pub to_broadcaster_user_login: String,
+    pub to_broadcaster_user_name: String,
+    /// Viewers who came along, can be 0
+    pub viewers: u32,
+}
+
+///////////////////////////////////////
+// SharedChat
+///////////////////////////////////////
+/// A shared chat session the broadcaster's channel takes part in, sent
+/// when it begins and whenever the participants change
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SharedChatEvent {
+    pub session_id: String,
+    pub broadcaster_user_id: String,
+    pub broadcaster_user_login: String,
+    pub broadcaster_user_name: String,
+    /// The channel which started the session
+    pub host_broadcaster_user_id: String,
+    pub host_broadcaster_user_login: String,
+    pub host_broadcaster_user_name: String,
+    /// Every channel in the session, including the host
+    pub participants: Vec<SharedChatParticipant>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SharedChatParticipant {
+    pub broadcaster_user_id: String,
+    pub broadcaster_user_login: String,
+    pub broadcaster_user_name: String,
+}
+
+/// The broadcaster's channel left a shared chat session, or the session
+/// ended
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SharedChatEndEvent {
+    pub session_id: String,
+    pub broadcaster_user_id: String,
+    pub broadcaster_user_login: String,
+    pub broadcaster_user_name: String,
+    pub host_broadcaster_user_id: String,
+    pub host_broadcaster_user_login: String,
+    pub host_broadcaster_user_name: String,
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        EventSubEvent,
+        Notification,
+        OnlineType,
+    };
+    use crate::helix::subscriptions::SubTier;
+
+    fn notification(
+        subscription_type: &str,
+        event: &str,
+    ) -> String
+    {
+        format!(
+            r#"{{
+              "subscription": {{
+                "id": "f1c2a387-161a-49f9-a165-0f21d7a4e1c4",
+                "type": "{}",
+                "version": "1",
+                "status": "enabled",
+                "cost": 0,
+                "condition": {{"broadcaster_user_id": "1337"}},
+                "transport": {{
+                  "method": "webhook",
+                  "callback": "https://example.com/webhooks/callback"
+                }},
+                "created_at": "2019-11-16T10:11:12.634234626Z"
+              }},
+              "event": {}
+            }}"#,
+            subscription_type, event
+        )
+    }
+
+    #[test]
+    fn stream_online() {
+        let body = notification(
+            "stream.online",
+            r#"{
+              "id": "9001",
+              "broadcaster_user_id": "1337",
+              "broadcaster_user_login": "cool_user",
+              "broadcaster_user_name": "Cool_User",
+              "type": "watch_party",
+              "started_at": "2020-10-11T10:11:12.123Z"
+            }"#,
+        );
+
+        let n = Notification::from_slice(body.as_bytes()).unwrap();
+        assert_eq!(n.subscription._type, "stream.online");
+        match n.event {
+            EventSubEvent::StreamOnline(e) => {
+                assert_eq!(e.id, "9001");
+                assert_eq!(e._type, OnlineType::WatchParty);
+            }
+            e => panic!("{:?}", e),
+        }
+
+        let body = body.replace("watch_party", "co_stream");
+        match Notification::from_slice(body.as_bytes()).unwrap().event {
+            EventSubEvent::StreamOnline(e) => {
+                assert_eq!(e._type, OnlineType::Other)
+            }
+            e => panic!("{:?}", e),
+        }
+    }
+
+    #[test]
+    fn stream_offline() {
+        let body = notification(
+            "stream.offline",
+            r#"{
+              "broadcaster_user_id": "1337",
+              "broadcaster_user_login": "cool_user",
+              "broadcaster_user_name": "Cool_User"
+            }"#,
+        );
+
+        match Notification::from_slice(body.as_bytes()).unwrap().event {
+            EventSubEvent::StreamOffline(e) => {
+                assert_eq!(e.broadcaster_user_login, "cool_user")
+            }
+            e => panic!("{:?}", e),
+        }
+    }
+
+    #[test]
+    fn channel_subscribe() {
+        let body = notification(
+            "channel.subscribe",
+            r#"{
+              "user_id": "1234",
+              "user_login": "cool_user",
+              "user_name": "Cool_User",
+              "broadcaster_user_id": "1337",
+              "broadcaster_user_login": "cooler_user",
+              "broadcaster_user_name": "Cooler_User",
+              "tier": "1000",
+              "is_gift": true
+            }"#,
+        );
+        match Notification::from_slice(body.as_bytes()).unwrap().event {
+            EventSubEvent::ChannelSubscribe(e) => {
+                assert_eq!(e.user_login, "cool_user");
+                assert_eq!(e.tier, SubTier::Tier1);
+                assert!(e.is_gift);
+            }
+            e => panic!("{:?}", e),
+        }
+    }
+
+    #[test]
+    fn channel_subscription_gift() {
+        let body = notification(
+            "channel.subscription.gift",
+            r#"{
+              "user_id": "1234",
+              "user_login": "cool_user",
+              "user_name": "Cool_User",
+              "broadcaster_user_id": "1337",
+              "broadcaster_user_login": "cooler_user",
+              "broadcaster_user_name": "Cooler_User",
+              "total": 2,
+              "tier": "1000",
+              "cumulative_total": 284,
+              "is_anonymous": false
+            }"#,
+        );
+        match Notification::from_slice(body.as_bytes()).unwrap().event {
+            EventSubEvent::ChannelSubscriptionGift(e) => {
+                assert!(!e.is_anonymous);
+                assert_eq!(e.user_login.as_deref(), Some("cool_user"));
+                assert_eq!(e.total, 2);
+                assert_eq!(e.cumulative_total, Some(284));
+            }
+            e => panic!("{:?}", e),
+        }
+
+        let body = notification(
+            "channel.subscription.gift",
+            r#"{
+              "user_id": null,
+              "user_login": null,
+              "user_name": null,
+              "broadcaster_user_id": "1337",
+              "broadcaster_user_login": "cooler_user",
+              "broadcaster_user_name": "Cooler_User",
+              "total": 5,
+              "tier": "3000",
+              "cumulative_total": null,
+              "is_anonymous": true
+            }"#,
+        );
+        match Notification::from_slice(body.as_bytes()).unwrap().event {
+            EventSubEvent::ChannelSubscriptionGift(e) => {
+                assert!(e.is_anonymous);
+                assert_eq!(e.user_id, None);
+                assert_eq!(e.user_name, None);
+                assert_eq!(e.tier, SubTier::Tier3);
+                assert_eq!(e.cumulative_total, None);
+            }
+            e => panic!("{:?}", e),
+        }
+    }
+
+    #[test]
+    fn channel_subscription_message() {
+        let body = notification(
+            "channel.subscription.message",
+            r#"{
+              "user_id": "1234",
+              "user_login": "cool_user",
+              "user_name": "Cool_User",
+              "broadcaster_user_id": "1337",
+              "broadcaster_user_login": "cooler_user",
+              "broadcaster_user_name": "Cooler_User",
+              "tier": "2000",
+              "message": {
+                "text": "Love the stream! FevziGG",
+                "emotes": [{"begin": 17, "end": 23, "id": "302976485"}]
+              },
+              "cumulative_months": 15,
+              "streak_months": 1,
+              "duration_months": 6
+            }"#,
+        );
+        match Notification::from_slice(body.as_bytes()).unwrap().event {
+            EventSubEvent::ChannelSubscriptionMessage(e) => {
+                assert_eq!(e.tier, SubTier::Tier2);
+                assert_eq!(e.message.text, "Love the stream! FevziGG");
+                let emotes = e.message.emotes.unwrap();
+                assert_eq!((emotes[0].begin, emotes[0].end), (17, 23));
+                assert_eq!(e.cumulative_months, 15);
+                assert_eq!(e.streak_months, Some(1));
+                assert_eq!(e.duration_months, 6);
+            }
+            e => panic!("{:?}", e),
+        }
+    }
+
+    #[test]
+    fn channel_cheer() {
+        let body = notification(
+            "channel.cheer",
+            r#"{
+              "is_anonymous": false,
+              "user_id": "1234",
+              "user_login": "cool_user",
+              "user_name": "Cool_User",
+              "broadcaster_user_id": "1337",
+              "broadcaster_user_login": "cooler_user",
+              "broadcaster_user_name": "Cooler_User",
+              "message": "pogchamp Cheer1000",
+              "bits": 1000
+            }"#,
+        );
+        match Notification::from_slice(body.as_bytes()).unwrap().event {
+            EventSubEvent::ChannelCheer(e) => {
+                assert!(!e.is_anonymous);
+                assert_eq!(e.user_login.as_deref(), Some("cool_user"));
+                assert_eq!(e.message, "pogchamp Cheer1000");
+                assert_eq!(e.bits, 1000);
+            }
+            e => panic!("{:?}", e),
+        }
+
+        let body = notification(
+            "channel.cheer",
+            r#"{
+              "is_anonymous": true,
+              "user_id": null,
+              "user_login": null,
+              "user_name": null,
+              "broadcaster_user_id": "1337",
+              "broadcaster_user_login": "cooler_user",
+              "broadcaster_user_name": "Cooler_User",
+              "message": "Cheer100",
+              "bits": 100
+            }"#,
+        );
+        match Notification::from_slice(body.as_bytes()).unwrap().event {
+            EventSubEvent::ChannelCheer(e) => {
+                assert!(e.is_anonymous);
+                assert_eq!(e.user_id, None);
+                assert_eq!(e.user_login, None);
+                assert_eq!(e.user_name, None);
+                assert_eq!(e.bits, 100);
+            }
+            e => panic!("{:?}", e),
+        }
+    }
+
+    #[test]
+    fn channel_raid() {
+        let raid = |viewers: u32| {
+            let body = notification(
+                "channel.raid",
+                &format!(
+                    r#"{{
+                      "from_broadcaster_user_id": "1234",
+                      "from_broadcaster_user_login": "cool_user",
+                      "from_broadcaster_user_name": "Cool_User",
+                      "to_broadcaster_user_id": "1337",
+                      "to_broadcaster_user_login": "cooler_user",
+                      "to_broadcaster_user_name": "Cooler_User",
+                      "viewers": {}
+                    }}"#,
+                    viewers
+                ),
+            );
+            match Notification::from_slice(body.as_bytes()).unwrap().event {
+                EventSubEvent::ChannelRaid(e) => e,
+                e => panic!("{:?}", e),
+            }
+        };
+
+        let e = raid(9001);
+        assert_eq!(e.from_broadcaster_user_login, "cool_user");
+        assert_eq!(e.to_broadcaster_user_id, "1337");
+        assert_eq!(e.viewers, 9001);
+        assert_eq!(raid(0).viewers, 0);
+    }
+
+    #[test]
+    fn shared_chat() {
+        let session = r#"
+          "session_id": "2b64a92a-dbb8-424e-b1c3-304423ba1b6f",
+          "broadcaster_user_id": "1971641",
+          "broadcaster_user_login": "streamer",
+          "broadcaster_user_name": "streamer",
+          "host_broadcaster_user_id": "2914196",
+          "host_broadcaster_user_login": "streamer33",
+          "host_broadcaster_user_name": "streamer33""#;
+        let body = notification(
+            "channel.shared_chat.update",
+            &format!(
+                r#"{{{},
+                  "participants": [{{
+                    "broadcaster_user_id": "2914196",
+                    "broadcaster_user_name": "streamer33",
+                    "broadcaster_user_login": "streamer33"
+                  }}, {{
+                    "broadcaster_user_id": "1971641",
+                    "broadcaster_user_name": "streamer",
+                    "broadcaster_user_login": "streamer"
+                  }}]
+                }}"#,
+                session
+            ),
+        );
+        match Notification::from_slice(body.as_bytes()).unwrap().event {
+            EventSubEvent::SharedChatUpdate(e) => {
+                assert_eq!(e.host_broadcaster_user_login, "streamer33");
+                assert_eq!(e.participants.len(), 2);
+                assert_eq!(e.participants[1].broadcaster_user_id, "1971641");
+            }
+            e => panic!("{:?}", e),
+        }
+
+        let body = notification(
+            "channel.shared_chat.end",
+            &format!("{{{}}}", session),
+        );
+        match Notification::from_slice(body.as_bytes()).unwrap().event {
+            EventSubEvent::SharedChatEnd(e) => {
+                assert_eq!(e.session_id, "2b64a92a-dbb8-424e-b1c3-304423ba1b6f")
+            }
+            e => panic!("{:?}", e),
+        }
+    }
+
+    #[test]
+    fn unknown_and_malformed() {
+        let body =
+            notification("channel.hype_train.begin", r#"{"total": 137}"#);
+        match Notification::from_slice(body.as_bytes()).unwrap().event {
+            EventSubEvent::Unknown { _type, event } => {
+                assert_eq!(_type, "channel.hype_train.begin");
+                assert_eq!(event["total"], 137);
+            }
+            e => panic!("{:?}", e),
+        }
+
+        let body = notification("stream.online", r#"{"id": "9001"}"#);
+        assert!(Notification::from_slice(body.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,10 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+//! EventSub, Twitch's push notifications for channel events
+//!
+//! https://dev.twitch.tv/docs/eventsub
+
+pub mod conduits;
+pub mod events;
+pub mod subscriptions;
+pub mod websocket;
@@ -0,0 +1,628 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+//! Creating EventSub subscriptions
+//!
+//! `EventSubBuilder` holds the transport shared by all subscriptions of an
+//! app, so bootstrapping many of them only needs the type and condition of
+//! each:
+//!
+//! ```no_run
+//! # use libtwitch_rs::helix::eventsub::subscriptions::*;
+//! # fn main() -> libtwitch_rs::response::TwitchResult<()> {
+//! # let c = libtwitch_rs::new(String::new());
+//! let events = EventSubBuilder::webhook(
+//!     "https://example.com/eventsub",
+//!     "s3cre7-s3cre7-s3cre7",
+//! )?;
+//! for ty in &[
+//!     SubscriptionType::StreamOnline,
+//!     SubscriptionType::StreamOffline,
+//! ] {
+//!     create(
+//!         &c,
+//!         &events.subscription(*ty, Condition::broadcaster("1337")),
+//!     )?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! https://dev.twitch.tv/docs/eventsub/manage-subscriptions
+
+use std::{
+    collections::HashMap,
+    thread,
+};
+
+use chrono::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    helix::{
+        Endpoint,
+        HelixPage,
+        HelixResponse,
+        PageOpts,
+    },
+    response::{
+        ApiError,
+        TwitchResult,
+    },
+    TwitchClient,
+};
+
+pub const CREATE: Endpoint = Endpoint::new("/eventsub/subscriptions");
+
+/// Creates an EventSub subscription
+///
+/// Webhook and conduit subscriptions need an app access token, websocket
+/// subscriptions a user access token. The user has to have granted the
+/// scopes the subscription type requires, either to the token or, for app
+/// access tokens, to the client.
+///
+/// #### Authentication: App or user access token, see above
+pub fn create(
+    c: &TwitchClient,
+    subscription: &NewSubscription,
+) -> TwitchResult<EventSubSubscription>
+{
+    let r = c.helix_post::<_, HelixResponse<EventSubSubscription>, _>(
+        &CREATE,
+        &(),
+        subscription,
+    )?;
+    r.single()
+}
+
+pub const GET: Endpoint = Endpoint::new("/eventsub/subscriptions");
+
+/// Gets the EventSub subscriptions of the client, optionally only those of
+/// type `ty`
+///
+/// #### Authentication: App or user access token, websocket subscriptions
+/// are only listed for user access tokens
+pub fn get(
+    c: &TwitchClient,
+    ty: Option<SubscriptionType>,
+    opts: &PageOpts,
+) -> TwitchResult<HelixPage<EventSubSubscription>>
+{
+    let mut params = Vec::new();
+    if let Some(ty) = ty {
+        params.push(("type", ty.as_str().to_owned()));
+    }
+    params.extend(opts.forward_params()?);
+    let r =
+        c.helix_get::<HelixResponse<EventSubSubscription>, _>(&GET, &params)?;
+    Ok(HelixPage::from(r))
+}
+
+pub const DELETE: Endpoint = Endpoint::new("/eventsub/subscriptions");
+
+/// Deletes an EventSub subscription
+///
+/// #### Authentication: App or user access token, the one it was created
+/// with
+pub fn delete(
+    c: &TwitchClient,
+    id: &str,
+) -> TwitchResult<()>
+{
+    c.helix_delete::<(), _>(&DELETE, &[("id", id)])
+}
+
+/// How many subscriptions `delete_all_by_type` deletes at once
+const DELETE_PARALLELISM: usize = 4;
+
+/// Deletes every EventSub subscription of type `ty`, e.g. those left over
+/// from a previous websocket session, and returns how many were deleted
+///
+/// Subscriptions which are already gone when they're deleted aren't an
+/// error, they just don't count.
+///
+/// #### Authentication: App or user access token, see `get` and `delete`
+pub fn delete_all_by_type(
+    c: &TwitchClient,
+    ty: SubscriptionType,
+) -> TwitchResult<usize>
+{
+    let ids = c.aggregate_pages(
+        &GET,
+        &[("type", ty.as_str())],
+        Vec::new(),
+        |mut ids, s: &EventSubSubscription| {
+            ids.push(s.id.clone());
+            ids
+        },
+    )?;
+
+    let mut deleted = 0;
+    for chunk in ids.chunks(DELETE_PARALLELISM) {
+        let results: Vec<TwitchResult<()>> = thread::scope(|s| {
+            let handles: Vec<_> = chunk
+                .iter()
+                .map(|id| s.spawn(move || delete(c, id)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().expect("subscription delete panicked"))
+                .collect()
+        });
+        for r in results {
+            match r {
+                Ok(()) => deleted += 1,
+                Err(ApiError::TwitchError(ref e)) if e.status == 404 => (),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+    Ok(deleted)
+}
+
+///////////////////////////////////////
+// EventSubBuilder
+///////////////////////////////////////
+/// Creates subscriptions sharing one transport
+#[derive(Debug, Clone)]
+pub struct EventSubBuilder {
+    transport: Transport,
+}
+
+impl EventSubBuilder {
+    /// Notifications are posted to `callback` and signed with `secret`,
+    /// which has to be 10 to 100 ASCII characters long
+    pub fn webhook(
+        callback: &str,
+        secret: &str,
+    ) -> TwitchResult<EventSubBuilder>
+    {
+        if !secret.is_ascii() || !(10..=100).contains(&secret.len()) {
+            return Err(ApiError::invalid_input(
+                "webhook secrets must be 10 to 100 ASCII characters long",
+            ));
+        }
+        Ok(EventSubBuilder {
+            transport: Transport::Webhook {
+                callback: callback.to_owned(),
+                secret: secret.to_owned(),
+            },
+        })
+    }
+
+    /// Notifications are sent over the websocket of `session_id`, from its
+    /// welcome message
+    pub fn websocket(session_id: &str) -> EventSubBuilder {
+        EventSubBuilder {
+            transport: Transport::Websocket {
+                session_id: session_id.to_owned(),
+            },
+        }
+    }
+
+    /// Notifications are spread across the shards of a conduit, see
+    /// `eventsub::conduits`
+    pub fn conduit(conduit_id: &str) -> EventSubBuilder {
+        EventSubBuilder {
+            transport: Transport::Conduit {
+                conduit_id: conduit_id.to_owned(),
+            },
+        }
+    }
+
+    /// A subscription to `ty` events matching `condition`, ready for
+    /// `create`
+    pub fn subscription(
+        &self,
+        ty: SubscriptionType,
+        condition: Condition,
+    ) -> NewSubscription
+    {
+        NewSubscription {
+            _type: ty.as_str(),
+            version: ty.version(),
+            condition,
+            transport: self.transport.clone(),
+        }
+    }
+}
+
+///////////////////////////////////////
+// CreateEventSubSubscription
+///////////////////////////////////////
+#[derive(Serialize, Debug, Clone)]
+pub struct NewSubscription {
+    #[serde(rename = "type")]
+    pub _type: &'static str,
+    pub version: &'static str,
+    pub condition: Condition,
+    pub transport: Transport,
+}
+
+/// The event types this crate knows, with the version it uses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SubscriptionType {
+    ChannelUpdate,
+    /// Needs `Condition::with_moderator`
+    ChannelFollow,
+    ChannelSubscribe,
+    ChannelSubscriptionGift,
+    ChannelSubscriptionMessage,
+    ChannelCheer,
+    /// Needs `Condition::raid_from` or `Condition::raid_to`
+    ChannelRaid,
+    ChannelBan,
+    /// Needs `Condition::with_user`
+    ChannelChatMessage,
+    ChannelPointsRedemptionAdd,
+    ChannelSharedChatBegin,
+    ChannelSharedChatUpdate,
+    ChannelSharedChatEnd,
+    StreamOnline,
+    StreamOffline,
+}
+
+impl SubscriptionType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SubscriptionType::ChannelUpdate => "channel.update",
+            SubscriptionType::ChannelFollow => "channel.follow",
+            SubscriptionType::ChannelSubscribe => "channel.subscribe",
+            SubscriptionType::ChannelSubscriptionGift => {
+                "channel.subscription.gift"
+            }
+            SubscriptionType::ChannelSubscriptionMessage => {
+                "channel.subscription.message"
+            }
+            SubscriptionType::ChannelCheer => "channel.cheer",
+            SubscriptionType::ChannelRaid => "channel.raid",
+            SubscriptionType::ChannelBan => "channel.ban",
+            SubscriptionType::ChannelChatMessage => "channel.chat.message",
+            SubscriptionType::ChannelPointsRedemptionAdd => {
+                "channel.channel_points_custom_reward_redemption.add"
+            }
+            SubscriptionType::ChannelSharedChatBegin => {
+                "channel.shared_chat.begin"
+            }
+            SubscriptionType::ChannelSharedChatUpdate => {
+                "channel.shared_chat.update"
+            }
+            SubscriptionType::ChannelSharedChatEnd => "channel.shared_chat.end",
+            SubscriptionType::StreamOnline => "stream.online",
+            SubscriptionType::StreamOffline => "stream.offline",
+        }
+    }
+
+    pub fn version(self) -> &'static str {
+        match self {
+            SubscriptionType::ChannelUpdate
+            | SubscriptionType::ChannelFollow => "2",
+            _ => "1",
+        }
+    }
+}
+
+/// Which events of a subscription type are sent
+///
+/// Start with the broadcaster, or the raid direction for `ChannelRaid`,
+/// and add what the type needs on top.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Condition {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub broadcaster_user_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub moderator_user_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_broadcaster_user_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_broadcaster_user_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reward_id: Option<String>,
+}
+
+impl Condition {
+    pub fn broadcaster(broadcaster_user_id: &str) -> Condition {
+        Condition {
+            broadcaster_user_id: Some(broadcaster_user_id.to_owned()),
+            ..Condition::default()
+        }
+    }
+
+    /// Raids the broadcaster starts
+    pub fn raid_from(broadcaster_user_id: &str) -> Condition {
+        Condition {
+            from_broadcaster_user_id: Some(broadcaster_user_id.to_owned()),
+            ..Condition::default()
+        }
+    }
+
+    /// Raids the broadcaster receives
+    pub fn raid_to(broadcaster_user_id: &str) -> Condition {
+        Condition {
+            to_broadcaster_user_id: Some(broadcaster_user_id.to_owned()),
+            ..Condition::default()
+        }
+    }
+
+    pub fn with_moderator(
+        mut self,
+        moderator_user_id: &str,
+    ) -> Condition
+    {
+        self.moderator_user_id = Some(moderator_user_id.to_owned());
+        self
+    }
+
+    pub fn with_user(
+        mut self,
+        user_id: &str,
+    ) -> Condition
+    {
+        self.user_id = Some(user_id.to_owned());
+        self
+    }
+
+    /// Only redemptions of the custom reward `reward_id`
+    pub fn with_reward(
+        mut self,
+        reward_id: &str,
+    ) -> Condition
+    {
+        self.reward_id = Some(reward_id.to_owned());
+        self
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Transport {
+    Webhook { callback: String, secret: String },
+    Websocket { session_id: String },
+    Conduit { conduit_id: String },
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct EventSubSubscription {
+    pub id: String,
+    /// `enabled`, or why notifications aren't sent, e.g.
+    /// `webhook_callback_verification_pending`
+    pub status: String,
+    #[serde(rename = "type")]
+    pub _type: String,
+    pub version: String,
+    pub condition: HashMap<String, String>,
+    pub created_at: DateTime<Utc>,
+    pub transport: SubscriptionTransport,
+    /// What the subscription counts towards the client's limit
+    pub cost: u32,
+}
+
+/// `Transport` as Twitch reports it, without the webhook secret
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum SubscriptionTransport {
+    Webhook {
+        callback: String,
+    },
+    Websocket {
+        session_id: String,
+        connected_at: Option<DateTime<Utc>>,
+        disconnected_at: Option<DateTime<Utc>>,
+    },
+    Conduit {
+        conduit_id: String,
+    },
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Condition,
+        EventSubBuilder,
+        SubscriptionTransport,
+        SubscriptionType,
+    };
+    use crate::{
+        mock,
+        new,
+        response::ApiError,
+    };
+
+    #[test]
+    fn builder() {
+        let events = EventSubBuilder::websocket("AQoQexAWVYKSTIu4ec_2VAxyuhAB");
+
+        let follow = events.subscription(
+            SubscriptionType::ChannelFollow,
+            Condition::broadcaster("1337").with_moderator("1337"),
+        );
+        assert_eq!(
+            serde_json::to_string(&follow).unwrap(),
+            r#"{"type":"channel.follow","version":"2","condition":{"broadcaster_user_id":"1337","moderator_user_id":"1337"},"transport":{"method":"websocket","session_id":"AQoQexAWVYKSTIu4ec_2VAxyuhAB"}}"#
+        );
+
+        let raid = events.subscription(
+            SubscriptionType::ChannelRaid,
+            Condition::raid_to("1337"),
+        );
+        assert_eq!(raid.version, "1");
+        assert_eq!(raid.transport, follow.transport);
+        assert_eq!(
+            serde_json::to_value(&raid).unwrap()["condition"],
+            serde_json::json!({"to_broadcaster_user_id": "1337"})
+        );
+    }
+
+    #[test]
+    fn webhook_secret() {
+        for secret in &["too-short", &"x".repeat(101), "nön-äscii-secret"] {
+            match EventSubBuilder::webhook("https://example.com", secret) {
+                Err(ApiError::InvalidInput(_)) => (),
+                r => panic!("{}: {:?}", secret, r),
+            }
+        }
+        EventSubBuilder::webhook("https://example.com", "0123456789").unwrap();
+        EventSubBuilder::webhook("https://example.com", &"x".repeat(100))
+            .unwrap();
+    }
+
+    #[test]
+    fn create() {
+        let server = mock::serve(vec![mock::response(
+            202,
+            &[],
+            r#"{
+              "data": [{
+                "id": "26b1c993-bfcf-44d9-b876-379dacafe75a",
+                "status": "webhook_callback_verification_pending",
+                "type": "stream.online",
+                "version": "1",
+                "condition": {"broadcaster_user_id": "1337"},
+                "created_at": "2019-11-16T10:11:12.634234626Z",
+                "transport": {
+                  "method": "webhook",
+                  "callback": "https://example.com/eventsub"
+                },
+                "cost": 1
+              }],
+              "total": 1,
+              "total_cost": 1,
+              "max_total_cost": 10000
+            }"#,
+        )]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let events = EventSubBuilder::webhook(
+            "https://example.com/eventsub",
+            "s3cre7-s3cre7-s3cre7",
+        )
+        .unwrap();
+        let sub = super::create(
+            &c,
+            &events.subscription(
+                SubscriptionType::StreamOnline,
+                Condition::broadcaster("1337"),
+            ),
+        )
+        .unwrap();
+        assert_eq!(sub.condition["broadcaster_user_id"], "1337");
+        assert_eq!(sub.transport, SubscriptionTransport::Webhook {
+            callback: "https://example.com/eventsub".to_owned(),
+        });
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with("POST /eventsub/subscriptions "));
+        assert!(requests[0].contains(r#""secret":"s3cre7-s3cre7-s3cre7""#));
+    }
+
+    fn subscription(id: &str) -> String {
+        format!(
+            r#"{{
+              "id": "{}",
+              "status": "websocket_disconnected",
+              "type": "channel.raid",
+              "version": "1",
+              "condition": {{"to_broadcaster_user_id": "1337"}},
+              "created_at": "2023-04-11T10:11:12.123Z",
+              "transport": {{
+                "method": "websocket",
+                "session_id": "AQoQexAWVYKSTIu4ec_2VAxyuhAB",
+                "connected_at": "2023-04-11T10:11:12.123Z",
+                "disconnected_at": "2023-04-11T10:21:12.123Z"
+              }},
+              "cost": 1
+            }}"#,
+            id
+        )
+    }
+
+    #[test]
+    fn delete_all_by_type() {
+        let server = mock::serve_routes(vec![
+            (
+                "/eventsub/subscriptions?type=channel.raid&after=c1",
+                mock::response(
+                    200,
+                    &[],
+                    &format!(
+                        r#"{{"data": [{}], "pagination": {{}}}}"#,
+                        subscription("c")
+                    ),
+                ),
+            ),
+            (
+                "/eventsub/subscriptions?type=channel.raid",
+                mock::response(
+                    200,
+                    &[],
+                    &format!(
+                        r#"{{"data": [{}, {}], "pagination": {{"cursor": "c1"}}}}"#,
+                        subscription("a"),
+                        subscription("b")
+                    ),
+                ),
+            ),
+            ("/eventsub/subscriptions?id=a", mock::response(204, &[], "")),
+            (
+                "/eventsub/subscriptions?id=b",
+                mock::response(
+                    404,
+                    &[],
+                    r#"{"error": "Not Found", "status": 404, "message": "subscription not found"}"#,
+                ),
+            ),
+            ("/eventsub/subscriptions?id=c", mock::response(204, &[], "")),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let deleted =
+            super::delete_all_by_type(&c, SubscriptionType::ChannelRaid)
+                .unwrap();
+        assert_eq!(deleted, 2);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 5);
+        for id in &["a", "b", "c"] {
+            let line = format!("DELETE /eventsub/subscriptions?id={} ", id);
+            assert!(requests.iter().any(|r| r.starts_with(&line)));
+        }
+    }
+
+    #[test]
+    fn delete_all_by_type_fails() {
+        let server = mock::serve(vec![
+            mock::response(
+                200,
+                &[],
+                &format!(
+                    r#"{{"data": [{}], "pagination": {{}}}}"#,
+                    subscription("a")
+                ),
+            ),
+            mock::response(
+                401,
+                &[],
+                r#"{"error": "Unauthorized", "status": 401, "message": "Invalid OAuth token"}"#,
+            ),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        match super::delete_all_by_type(&c, SubscriptionType::ChannelRaid) {
+            Err(ApiError::TwitchError(ref e)) if e.status == 401 => (),
+            r => panic!("{:?}", r),
+        }
+        server.requests();
+    }
+}
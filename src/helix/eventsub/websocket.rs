@@ -0,0 +1,663 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+//! Receiving EventSub notifications over a websocket
+//!
+//! This crate has no websocket client, the caller plugs theirs in by
+//! implementing `Connect` and `Socket`. `EventStream` then takes care of the
+//! session: it skips keepalives, follows `session_reconnect` messages,
+//! reconnects after the connection dropped and only yields the events.
+//!
+//! ```no_run
+//! # use libtwitch_rs::helix::eventsub::{subscriptions::*, websocket::*};
+//! # fn run<C: Connect>(connector: C) {
+//! # let c = libtwitch_rs::new(String::new());
+//! let events = EventStream::new(connector, move |session_id: &str| {
+//!     let events = EventSubBuilder::websocket(session_id);
+//!     create(
+//!         &c,
+//!         &events.subscription(
+//!             SubscriptionType::StreamOnline,
+//!             Condition::broadcaster("1337"),
+//!         ),
+//!     )?;
+//!     Ok(())
+//! });
+//! for event in events {
+//!     println!("{:?}", event);
+//! }
+//! # }
+//! ```
+//!
+//! https://dev.twitch.tv/docs/eventsub/handling-websocket-events
+
+use std::{
+    io,
+    thread,
+    time::Duration,
+};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::events::{
+    EventSubEvent,
+    Notification,
+};
+
+use crate::response::TwitchResult;
+
+/// Where new sessions are opened
+pub const DEFAULT_URL: &str = "wss://eventsub.wss.twitch.tv/ws";
+
+/// Extra time on top of the keepalive interval before a silent connection
+/// counts as dropped
+const KEEPALIVE_GRACE: Duration = Duration::from_secs(5);
+
+/// Default for `EventStream::with_backoff`
+pub const DEFAULT_BACKOFF: Duration = Duration::from_secs(1);
+
+/// The longest `EventStream` waits between two connection attempts
+pub const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// An open websocket connection
+pub trait Socket {
+    /// Waits for the next text message, `None` once the connection closed
+    ///
+    /// Failing with kind `TimedOut` or `WouldBlock` after the duration of
+    /// `set_read_timeout` passed without a message counts as a dropped
+    /// connection.
+    fn recv(&mut self) -> io::Result<Option<String>>;
+
+    /// Limits how long `recv` waits, called with the keepalive interval of
+    /// each session
+    fn set_read_timeout(
+        &mut self,
+        _timeout: Duration,
+    ) -> io::Result<()>
+    {
+        Ok(())
+    }
+}
+
+/// Opens websocket connections
+pub trait Connect {
+    type Socket: Socket;
+
+    fn connect(
+        &mut self,
+        url: &str,
+    ) -> io::Result<Self::Socket>;
+}
+
+/// Subscribes a new session to the events the stream yields
+type Subscribe = Box<dyn FnMut(&str) -> TwitchResult<()> + Send>;
+
+///////////////////////////////////////
+// EventStream
+///////////////////////////////////////
+/// The events of an EventSub websocket session, across reconnects
+///
+/// `subscribe` is called with the id of every new session, which starts
+/// without subscriptions, so it has to create them with
+/// `EventSubBuilder::websocket`. Sessions Twitch moves with a
+/// `session_reconnect` message keep their subscriptions. Revocations are
+/// skipped, check the subscription status to find out why events stopped.
+///
+/// The iterator never ends. Errors, e.g. a failed reconnect, are yielded
+/// and the next call tries again. If `subscribe` fails or the connection to
+/// the `session_reconnect` URL can't be opened, the session is closed and
+/// the next call subscribes a new one. Consecutive connections
+/// which didn't end up with a subscribed session are spaced out by a
+/// delay doubling from `with_backoff` up to `MAX_BACKOFF`, so iterating
+/// doesn't hammer Twitch during an outage.
+pub struct EventStream<C: Connect> {
+    connector: C,
+    subscribe: Subscribe,
+    socket: Option<C::Socket>,
+    /// Where new sessions are opened
+    url: String,
+    session_id: Option<String>,
+    backoff: Duration,
+    /// Connections opened since the last subscribed session
+    attempts: u32,
+}
+
+impl<C: Connect> EventStream<C> {
+    /// Connects to `DEFAULT_URL` on the first call to `next`
+    pub fn new<F>(
+        connector: C,
+        subscribe: F,
+    ) -> EventStream<C>
+    where
+        F: FnMut(&str) -> TwitchResult<()> + Send + 'static,
+    {
+        EventStream::with_url(connector, DEFAULT_URL, subscribe)
+    }
+
+    /// Like `new`, but opens new sessions at `url`, e.g. the Twitch CLI's
+    /// mock server
+    pub fn with_url<F>(
+        connector: C,
+        url: &str,
+        subscribe: F,
+    ) -> EventStream<C>
+    where
+        F: FnMut(&str) -> TwitchResult<()> + Send + 'static,
+    {
+        EventStream {
+            connector,
+            subscribe: Box::new(subscribe),
+            socket: None,
+            url: url.to_owned(),
+            session_id: None,
+            backoff: DEFAULT_BACKOFF,
+            attempts: 0,
+        }
+    }
+
+    /// Sets the delay before the second connection attempt in a row,
+    /// doubled for every further attempt
+    pub fn with_backoff(
+        mut self,
+        backoff: Duration,
+    ) -> EventStream<C>
+    {
+        self.backoff = backoff;
+        self
+    }
+
+    /// The delay before the next connection attempt
+    fn delay(&self) -> Duration {
+        match self.attempts {
+            0 => Duration::from_secs(0),
+            n => self
+                .backoff
+                .checked_mul(2u32.saturating_pow(n - 1))
+                .map_or(MAX_BACKOFF, |d| d.min(MAX_BACKOFF)),
+        }
+    }
+
+    /// Id of the current session, `None` before the first welcome message
+    pub fn session_id(&self) -> Option<&str> {
+        self.session_id.as_deref()
+    }
+
+    /// Handles the next message, `None` if it wasn't an event
+    fn step(&mut self) -> TwitchResult<Option<EventSubEvent>> {
+        if self.socket.is_none() {
+            thread::sleep(self.delay());
+            self.attempts = self.attempts.saturating_add(1);
+            self.socket = Some(self.connector.connect(&self.url)?);
+        }
+        let socket = self.socket.as_mut().unwrap();
+
+        let text = match socket.recv() {
+            Ok(Some(text)) => text,
+            Ok(None) => return Ok(self.dropped()),
+            Err(ref e)
+                if e.kind() == io::ErrorKind::TimedOut
+                    || e.kind() == io::ErrorKind::WouldBlock =>
+            {
+                return Ok(self.dropped());
+            }
+            Err(e) => {
+                self.dropped();
+                return Err(e.into());
+            }
+        };
+
+        let mut message: Message = serde_json::from_str(&text)?;
+        match message.metadata.message_type.as_str() {
+            "session_welcome" => {
+                let session: Session =
+                    serde_json::from_value(message.payload["session"].take())?;
+                if let Some(secs) = session.keepalive_timeout_seconds {
+                    socket.set_read_timeout(
+                        Duration::from_secs(secs) + KEEPALIVE_GRACE,
+                    )?;
+                }
+                let is_new =
+                    self.session_id.as_deref() != Some(session.id.as_str());
+                if is_new {
+                    // A session missing subscriptions would never yield an
+                    // event, so a fresh one is opened to subscribe again
+                    if let Err(e) = (self.subscribe)(&session.id) {
+                        self.dropped();
+                        return Err(e);
+                    }
+                }
+                self.session_id = Some(session.id);
+                self.attempts = 0;
+                Ok(None)
+            }
+            "session_reconnect" => {
+                let session: Session =
+                    serde_json::from_value(message.payload["session"].take())?;
+                if let Some(url) = session.reconnect_url {
+                    // The old connection is closed right away instead of
+                    // draining it until the new one is welcomed, which would
+                    // need a thread per socket
+                    match self.connector.connect(&url) {
+                        Ok(socket) => self.socket = Some(socket),
+                        Err(e) => {
+                            self.dropped();
+                            return Err(e.into());
+                        }
+                    }
+                }
+                Ok(None)
+            }
+            "notification" => {
+                Ok(Some(Notification::from_value(message.payload)?.event))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Forgets the socket and session, so the next step starts a new one
+    fn dropped(&mut self) -> Option<EventSubEvent> {
+        self.socket = None;
+        self.session_id = None;
+        None
+    }
+}
+
+impl<C: Connect> Iterator for EventStream<C> {
+    type Item = TwitchResult<EventSubEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.step() {
+                Ok(Some(event)) => return Some(Ok(event)),
+                Ok(None) => (),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct Message {
+    metadata: Metadata,
+    #[serde(default)]
+    payload: Value,
+}
+
+#[derive(Deserialize, Debug)]
+struct Metadata {
+    message_type: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct Session {
+    id: String,
+    #[serde(default)]
+    keepalive_timeout_seconds: Option<u64>,
+    #[serde(default)]
+    reconnect_url: Option<String>,
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        io,
+        sync::{
+            Arc,
+            Mutex,
+        },
+        thread,
+        time::{
+            Duration,
+            Instant,
+        },
+    };
+
+    use super::{
+        Connect,
+        EventStream,
+        Socket,
+        DEFAULT_URL,
+    };
+    use crate::{
+        helix::eventsub::events::EventSubEvent,
+        response::ApiError,
+    };
+
+    /// What a connection receives, `Err` for a read timing out
+    type Script = Vec<Result<String, io::ErrorKind>>;
+
+    struct MockSocket(VecDeque<Result<String, io::ErrorKind>>);
+
+    impl Socket for MockSocket {
+        fn recv(&mut self) -> io::Result<Option<String>> {
+            match self.0.pop_front() {
+                Some(Ok(text)) => Ok(Some(text)),
+                Some(Err(kind)) => Err(io::Error::new(kind, "mock")),
+                None => Ok(None),
+            }
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct MockConnector {
+        scripts: Arc<Mutex<VecDeque<Script>>>,
+        urls: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Connect for MockConnector {
+        type Socket = MockSocket;
+
+        fn connect(
+            &mut self,
+            url: &str,
+        ) -> io::Result<MockSocket>
+        {
+            self.urls.lock().unwrap().push(url.to_owned());
+            match self.scripts.lock().unwrap().pop_front() {
+                Some(script) => Ok(MockSocket(script.into())),
+                None => Err(io::Error::new(
+                    io::ErrorKind::ConnectionRefused,
+                    "mock",
+                )),
+            }
+        }
+    }
+
+    fn welcome(session_id: &str) -> Result<String, io::ErrorKind> {
+        Ok(format!(
+            r#"{{
+              "metadata": {{
+                "message_id": "96a3f3b5-5dec-4eed-908e-e11ee657416c",
+                "message_type": "session_welcome",
+                "message_timestamp": "2023-07-19T14:56:51.634234626Z"
+              }},
+              "payload": {{
+                "session": {{
+                  "id": "{}",
+                  "status": "connected",
+                  "connected_at": "2023-07-19T14:56:51.616329898Z",
+                  "keepalive_timeout_seconds": 10,
+                  "reconnect_url": null
+                }}
+              }}
+            }}"#,
+            session_id
+        ))
+    }
+
+    fn keepalive() -> Result<String, io::ErrorKind> {
+        Ok(String::from(
+            r#"{
+              "metadata": {
+                "message_id": "84c1e79a-2a4b-4c13-ba0b-4312293e9308",
+                "message_type": "session_keepalive",
+                "message_timestamp": "2023-07-19T10:11:12.634234626Z"
+              },
+              "payload": {}
+            }"#,
+        ))
+    }
+
+    fn reconnect(url: &str) -> Result<String, io::ErrorKind> {
+        Ok(format!(
+            r#"{{
+              "metadata": {{
+                "message_id": "84c1e79a-2a4b-4c13-ba0b-4312293e9308",
+                "message_type": "session_reconnect",
+                "message_timestamp": "2023-07-19T10:11:12.634234626Z"
+              }},
+              "payload": {{
+                "session": {{
+                  "id": "AQoQexAWVYKSTIu4ec_2VAxyuhAB",
+                  "status": "reconnecting",
+                  "keepalive_timeout_seconds": null,
+                  "reconnect_url": "{}",
+                  "connected_at": "2023-07-19T10:11:12.634234626Z"
+                }}
+              }}
+            }}"#,
+            url
+        ))
+    }
+
+    fn offline(login: &str) -> Result<String, io::ErrorKind> {
+        Ok(format!(
+            r#"{{
+              "metadata": {{
+                "message_id": "befa7b53-d79d-478f-86b9-120f112b044e",
+                "message_type": "notification",
+                "message_timestamp": "2022-11-16T10:11:12.464757833Z",
+                "subscription_type": "stream.offline",
+                "subscription_version": "1"
+              }},
+              "payload": {{
+                "subscription": {{
+                  "id": "f1c2a387-161a-49f9-a165-0f21d7a4e1c4",
+                  "status": "enabled",
+                  "type": "stream.offline",
+                  "version": "1",
+                  "cost": 1,
+                  "condition": {{"broadcaster_user_id": "1337"}},
+                  "transport": {{
+                    "method": "websocket",
+                    "session_id": "AQoQexAWVYKSTIu4ec_2VAxyuhAB"
+                  }},
+                  "created_at": "2022-11-16T10:11:12.464757833Z"
+                }},
+                "event": {{
+                  "broadcaster_user_id": "1337",
+                  "broadcaster_user_login": "{}",
+                  "broadcaster_user_name": "Cool_User"
+                }}
+              }}
+            }}"#,
+            login
+        ))
+    }
+
+    fn login(event: Option<Result<EventSubEvent, ApiError>>) -> String {
+        match event {
+            Some(Ok(EventSubEvent::StreamOffline(e))) => {
+                e.broadcaster_user_login
+            }
+            e => panic!("{:?}", e),
+        }
+    }
+
+    #[test]
+    fn events_across_reconnects() {
+        let connector = MockConnector::default();
+        *connector.scripts.lock().unwrap() = VecDeque::from(vec![
+            vec![
+                welcome("session1"),
+                keepalive(),
+                offline("first"),
+                reconnect("wss://eventsub.wss.twitch.tv/ws?id=moved"),
+            ],
+            // Moved by Twitch, the subscriptions carry over
+            vec![
+                welcome("session1"),
+                offline("second"),
+                Err(io::ErrorKind::TimedOut),
+            ],
+            // Dropped, the new session has to subscribe again
+            vec![welcome("session2"), offline("third")],
+        ]);
+        let sessions = Arc::new(Mutex::new(Vec::new()));
+        let subscribed = sessions.clone();
+        let mut events =
+            EventStream::new(connector.clone(), move |session_id: &str| {
+                subscribed.lock().unwrap().push(session_id.to_owned());
+                Ok(())
+            })
+            .with_backoff(Duration::from_millis(1));
+
+        assert_eq!(login(events.next()), "first");
+        assert_eq!(events.session_id(), Some("session1"));
+        assert_eq!(login(events.next()), "second");
+        assert_eq!(login(events.next()), "third");
+        assert_eq!(events.session_id(), Some("session2"));
+
+        assert_eq!(*sessions.lock().unwrap(), vec!["session1", "session2"]);
+        assert_eq!(*connector.urls.lock().unwrap(), vec![
+            DEFAULT_URL,
+            "wss://eventsub.wss.twitch.tv/ws?id=moved",
+            DEFAULT_URL,
+        ]);
+
+        // Failing to reconnect is reported, the stream doesn't end
+        match events.next() {
+            Some(Err(ApiError::IoError(_))) => (),
+            e => panic!("{:?}", e),
+        }
+        connector
+            .scripts
+            .lock()
+            .unwrap()
+            .push_back(vec![welcome("session3"), offline("fourth")]);
+        assert_eq!(login(events.next()), "fourth");
+    }
+
+    #[test]
+    fn failed_reconnect_opens_new_session() {
+        const URL: &str = "ws://127.0.0.1:8080/ws";
+        let connector = MockConnector::default();
+        *connector.scripts.lock().unwrap() = VecDeque::from(vec![vec![
+            welcome("session1"),
+            reconnect("wss://eventsub.wss.twitch.tv/ws?id=moved"),
+        ]]);
+        let sessions = Arc::new(Mutex::new(Vec::new()));
+        let subscribed = sessions.clone();
+        let mut events =
+            EventStream::with_url(connector.clone(), URL, move |id: &str| {
+                subscribed.lock().unwrap().push(id.to_owned());
+                Ok(())
+            })
+            .with_backoff(Duration::from_millis(1));
+
+        // Consumed on a worker thread, like any blocking stream
+        let scripts = connector.scripts.clone();
+        let worker = thread::spawn(move || {
+            match events.next() {
+                Some(Err(ApiError::IoError(_))) => (),
+                e => panic!("{:?}", e),
+            }
+            assert_eq!(events.session_id(), None);
+            scripts
+                .lock()
+                .unwrap()
+                .push_back(vec![welcome("session2"), offline("first")]);
+            login(events.next())
+        });
+
+        assert_eq!(worker.join().unwrap(), "first");
+        assert_eq!(*sessions.lock().unwrap(), vec!["session1", "session2"]);
+        assert_eq!(*connector.urls.lock().unwrap(), vec![
+            URL,
+            "wss://eventsub.wss.twitch.tv/ws?id=moved",
+            URL,
+        ]);
+    }
+
+    #[test]
+    fn keepalive_timeout() {
+        struct TimeoutSocket(Arc<Mutex<Option<Duration>>>);
+
+        impl Socket for TimeoutSocket {
+            fn recv(&mut self) -> io::Result<Option<String>> {
+                Ok(Some(welcome("session1").unwrap()))
+            }
+
+            fn set_read_timeout(
+                &mut self,
+                timeout: Duration,
+            ) -> io::Result<()>
+            {
+                *self.0.lock().unwrap() = Some(timeout);
+                Ok(())
+            }
+        }
+
+        struct TimeoutConnector(Arc<Mutex<Option<Duration>>>);
+
+        impl Connect for TimeoutConnector {
+            type Socket = TimeoutSocket;
+
+            fn connect(
+                &mut self,
+                _url: &str,
+            ) -> io::Result<TimeoutSocket>
+            {
+                Ok(TimeoutSocket(self.0.clone()))
+            }
+        }
+
+        let timeout = Arc::new(Mutex::new(None));
+        let mut events =
+            EventStream::new(TimeoutConnector(timeout.clone()), |_: &str| {
+                Err(ApiError::invalid_input("no subscriptions"))
+            });
+        match events.next() {
+            Some(Err(ApiError::InvalidInput(_))) => (),
+            e => panic!("{:?}", e),
+        }
+        assert_eq!(*timeout.lock().unwrap(), Some(Duration::from_secs(15)));
+    }
+
+    #[test]
+    fn failed_subscribe_opens_new_session() {
+        let connector = MockConnector::default();
+        *connector.scripts.lock().unwrap() = VecDeque::from(vec![
+            vec![welcome("session1"), offline("lost")],
+            vec![welcome("session2"), offline("first")],
+        ]);
+        let sessions = Arc::new(Mutex::new(Vec::new()));
+        let subscribed = sessions.clone();
+        let mut events =
+            EventStream::new(connector.clone(), move |session_id: &str| {
+                let mut sessions = subscribed.lock().unwrap();
+                sessions.push(session_id.to_owned());
+                if sessions.len() == 1 {
+                    return Err(ApiError::invalid_input("subscription failed"));
+                }
+                Ok(())
+            })
+            .with_backoff(Duration::from_millis(1));
+
+        match events.next() {
+            Some(Err(ApiError::InvalidInput(_))) => (),
+            e => panic!("{:?}", e),
+        }
+        assert_eq!(events.session_id(), None);
+        assert_eq!(login(events.next()), "first");
+        assert_eq!(events.session_id(), Some("session2"));
+        assert_eq!(*sessions.lock().unwrap(), vec!["session1", "session2"]);
+    }
+
+    #[test]
+    fn backoff() {
+        let mut events =
+            EventStream::new(MockConnector::default(), |_: &str| Ok(()))
+                .with_backoff(Duration::from_millis(10));
+        assert_eq!(events.delay(), Duration::from_secs(0));
+
+        let started = Instant::now();
+        for _ in 0..3 {
+            match events.next() {
+                Some(Err(ApiError::IoError(_))) => (),
+                e => panic!("{:?}", e),
+            }
+        }
+        // Waited 10 and 20 milliseconds before the second and third attempt
+        assert!(started.elapsed() >= Duration::from_millis(30));
+        assert_eq!(events.delay(), Duration::from_millis(40));
+
+        events.attempts = 100;
+        assert_eq!(events.delay(), super::MAX_BACKOFF);
+    }
+}
@@ -0,0 +1,406 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+use chrono::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use super::{
+    Endpoint,
+    HelixPage,
+    HelixResponse,
+    PageOpts,
+};
+
+use crate::{
+    response::{
+        ApiError,
+        TwitchResult,
+    },
+    TwitchClient,
+};
+
+pub const TRANSACTIONS: Endpoint = Endpoint::new("/extensions/transactions");
+
+/// Gets the Bits transactions of an extension, most recent first
+///
+/// #### Authentication: App access token of the extension's client
+pub fn transactions(
+    c: &TwitchClient,
+    extension_id: &str,
+    opts: &PageOpts,
+) -> TwitchResult<HelixPage<ExtensionTransaction>>
+{
+    let mut params = vec![("extension_id", extension_id.to_owned())];
+    params.extend(opts.forward_params()?);
+    let r = c.helix_get::<HelixResponse<ExtensionTransaction>, _>(
+        &TRANSACTIONS,
+        &params,
+    )?;
+    Ok(HelixPage::from(r))
+}
+
+pub const CONFIGURATIONS: Endpoint =
+    Endpoint::new("/extensions/configurations");
+
+/// Gets a configuration segment of an extension, `None` if it was never set
+///
+/// `broadcaster_id` is required for the `Broadcaster` and `Developer`
+/// segments and has to be `None` for `Global`.
+///
+/// #### Authentication: JWT signed with the extension secret, with the
+/// `external` role, set as the client's token
+pub fn get_configuration(
+    c: &TwitchClient,
+    extension_id: &str,
+    segment: ConfigSegment,
+    broadcaster_id: Option<&str>,
+) -> TwitchResult<Option<ConfigurationSegment>>
+{
+    segment.check_broadcaster(broadcaster_id)?;
+    let mut params = vec![
+        ("extension_id", extension_id),
+        ("segment", segment.as_str()),
+    ];
+    if let Some(broadcaster_id) = broadcaster_id {
+        params.push(("broadcaster_id", broadcaster_id));
+    }
+    let r = c.helix_get::<HelixResponse<ConfigurationSegment>, _>(
+        &CONFIGURATIONS,
+        &params,
+    )?;
+    Ok(r.data.into_iter().next())
+}
+
+/// Twitch's limit for the `content` of a configuration segment, in bytes
+pub const MAX_CONFIGURATION_BYTES: usize = 5 * 1024;
+
+pub const SET_CONFIGURATION: Endpoint =
+    Endpoint::new("/extensions/configurations");
+
+/// Stores `content` in a configuration segment of an extension
+///
+/// `broadcaster_id` is required for the `Broadcaster` and `Developer`
+/// segments and has to be `None` for `Global`. `content` can be at most
+/// `MAX_CONFIGURATION_BYTES` long, `version` is free-form.
+///
+/// #### Authentication: JWT signed with the extension secret, with the
+/// `external` role, set as the client's token
+pub fn set_configuration(
+    c: &TwitchClient,
+    extension_id: &str,
+    segment: ConfigSegment,
+    broadcaster_id: Option<&str>,
+    content: &str,
+    version: Option<&str>,
+) -> TwitchResult<()>
+{
+    segment.check_broadcaster(broadcaster_id)?;
+    if content.len() > MAX_CONFIGURATION_BYTES {
+        return Err(ApiError::invalid_input(
+            "configuration segments hold at most 5 KB",
+        ));
+    }
+    c.helix_put::<_, (), _>(&SET_CONFIGURATION, &(), &NewConfiguration {
+        extension_id,
+        segment: segment.as_str(),
+        broadcaster_id,
+        content,
+        version,
+    })
+}
+
+pub const SET_REQUIRED_CONFIGURATION: Endpoint =
+    Endpoint::new("/extensions/required_configuration");
+
+/// Marks the configuration a broadcaster's extension needs as done, so
+/// the extension can be activated
+///
+/// `required_configuration` has to match the value set for
+/// `extension_version` in the developer console.
+///
+/// #### Authentication: JWT signed with the extension secret, with the
+/// `external` role, set as the client's token
+pub fn set_required_configuration(
+    c: &TwitchClient,
+    extension_id: &str,
+    extension_version: &str,
+    broadcaster_id: &str,
+    required_configuration: &str,
+) -> TwitchResult<()>
+{
+    c.helix_put::<_, (), _>(
+        &SET_REQUIRED_CONFIGURATION,
+        &[("broadcaster_id", broadcaster_id)],
+        &RequiredConfiguration {
+            extension_id,
+            extension_version,
+            required_configuration,
+        },
+    )
+}
+
+///////////////////////////////////////
+// GetExtensionConfigurationSegment
+///////////////////////////////////////
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigSegment {
+    /// Set by the broadcaster, per channel
+    Broadcaster,
+    /// Set by the developer, per channel
+    Developer,
+    /// Set by the developer, shared by all channels
+    Global,
+}
+
+impl ConfigSegment {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ConfigSegment::Broadcaster => "broadcaster",
+            ConfigSegment::Developer => "developer",
+            ConfigSegment::Global => "global",
+        }
+    }
+
+    fn check_broadcaster(
+        self,
+        broadcaster_id: Option<&str>,
+    ) -> TwitchResult<()>
+    {
+        match (self, broadcaster_id) {
+            (ConfigSegment::Global, Some(_)) => Err(ApiError::invalid_input(
+                "the global segment isn't set per broadcaster",
+            )),
+            (ConfigSegment::Global, None) | (_, Some(_)) => Ok(()),
+            (_, None) => Err(ApiError::invalid_input(
+                "the broadcaster and developer segments need a broadcaster",
+            )),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ConfigurationSegment {
+    /// `broadcaster`, `developer` or `global`
+    pub segment: String,
+    /// `None` for the global segment
+    #[serde(default)]
+    pub broadcaster_id: Option<String>,
+    pub content: String,
+    pub version: String,
+}
+
+#[derive(Serialize, Debug)]
+struct NewConfiguration<'a> {
+    extension_id: &'a str,
+    segment: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    broadcaster_id: Option<&'a str>,
+    content: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<&'a str>,
+}
+
+#[derive(Serialize, Debug)]
+struct RequiredConfiguration<'a> {
+    extension_id: &'a str,
+    extension_version: &'a str,
+    required_configuration: &'a str,
+}
+
+///////////////////////////////////////
+// GetExtensionTransactions
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone)]
+pub struct ExtensionTransaction {
+    pub id: String,
+    pub timestamp: DateTime<Utc>,
+    pub broadcaster_id: String,
+    pub broadcaster_login: String,
+    pub broadcaster_name: String,
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+    /// Always `BITS_IN_EXTENSION`
+    pub product_type: String,
+    pub product_data: ProductData,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ProductData {
+    pub domain: String,
+    pub sku: String,
+    pub cost: ProductCost,
+    pub in_development: bool,
+    pub display_name: String,
+    pub expiration: String,
+    pub broadcast: bool,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ProductCost {
+    pub amount: i64,
+    /// Always `bits`
+    #[serde(rename = "type")]
+    pub _type: String,
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ConfigSegment,
+        ExtensionTransaction,
+        MAX_CONFIGURATION_BYTES,
+    };
+    use crate::{
+        helix::HelixResponse,
+        mock,
+        new,
+        response::ApiError,
+    };
+
+    #[test]
+    fn transactions() {
+        let r: HelixResponse<ExtensionTransaction> = serde_json::from_str(
+            r#"{
+              "data": [{
+                "id": "74c52265-e214-48a6-91b9-23b6014e8041",
+                "timestamp": "2019-01-28T04:15:17.65Z",
+                "broadcaster_id": "439964613",
+                "broadcaster_login": "chikuseuma",
+                "broadcaster_name": "chikuseuma",
+                "user_id": "424596340",
+                "user_login": "quotrok",
+                "user_name": "quotrok",
+                "product_type": "BITS_IN_EXTENSION",
+                "product_data": {
+                  "domain": "twitch.ext.uo6dggojyb8d6soh92zknwmi5ej1q2",
+                  "sku": "testSku100",
+                  "cost": {"amount": 100, "type": "bits"},
+                  "inDevelopment": false,
+                  "displayName": "Test Product 100",
+                  "expiration": "",
+                  "broadcast": false
+                }
+              }],
+              "pagination": {"cursor": "cursorString"}
+            }"#,
+        )
+        .unwrap();
+
+        let tx = &r.data[0];
+        assert_eq!(tx.product_data.cost.amount, 100);
+        assert_eq!(tx.product_data.display_name, "Test Product 100");
+        assert_eq!(r.cursor(), Some("cursorString"));
+    }
+
+    const EXTENSION: &str = "uo6dggojyb8d6soh92zknwmi5ej1q2";
+
+    #[test]
+    fn configuration() {
+        let server = mock::serve(vec![
+            mock::response(
+                200,
+                &[],
+                r#"{
+                  "data": [{
+                    "segment": "broadcaster",
+                    "broadcaster_id": "141981764",
+                    "content": "{\"foo\":\"bar\"}",
+                    "version": "0.0.1"
+                  }]
+                }"#,
+            ),
+            mock::response(200, &[], r#"{"data": []}"#),
+            mock::response(204, &[], ""),
+            mock::response(204, &[], ""),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let config = super::get_configuration(
+            &c,
+            EXTENSION,
+            ConfigSegment::Broadcaster,
+            Some("141981764"),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(config.content, r#"{"foo":"bar"}"#);
+        assert_eq!(config.version, "0.0.1");
+
+        let global = super::get_configuration(
+            &c,
+            EXTENSION,
+            ConfigSegment::Global,
+            None,
+        )
+        .unwrap();
+        assert_eq!(global, None);
+
+        super::set_configuration(
+            &c,
+            EXTENSION,
+            ConfigSegment::Global,
+            None,
+            r#"{"foo":"baz"}"#,
+            Some("0.0.2"),
+        )
+        .unwrap();
+        super::set_required_configuration(
+            &c,
+            EXTENSION,
+            "0.0.1",
+            "141981764",
+            "RCS",
+        )
+        .unwrap();
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with(
+            "GET /extensions/configurations?extension_id=uo6dggojyb8d6soh92zknwmi5ej1q2&segment=broadcaster&broadcaster_id=141981764 "
+        ));
+        assert!(requests[1].starts_with(
+            "GET /extensions/configurations?extension_id=uo6dggojyb8d6soh92zknwmi5ej1q2&segment=global "
+        ));
+        assert!(requests[2].starts_with("PUT /extensions/configurations "));
+        assert!(requests[2].ends_with(
+            r#"{"extension_id":"uo6dggojyb8d6soh92zknwmi5ej1q2","segment":"global","content":"{\"foo\":\"baz\"}","version":"0.0.2"}"#
+        ));
+        assert!(requests[3].starts_with(
+            "PUT /extensions/required_configuration?broadcaster_id=141981764 "
+        ));
+        assert!(requests[3].contains(r#""required_configuration":"RCS""#));
+    }
+
+    #[test]
+    fn configuration_invalid() {
+        let c = new(String::from("<clientid>"));
+
+        let too_large = "x".repeat(MAX_CONFIGURATION_BYTES + 1);
+        for (segment, broadcaster_id, content) in &[
+            (ConfigSegment::Global, Some("141981764"), "{}"),
+            (ConfigSegment::Broadcaster, None, "{}"),
+            (ConfigSegment::Developer, None, "{}"),
+            (ConfigSegment::Developer, Some("141981764"), &too_large[..]),
+        ] {
+            match super::set_configuration(
+                &c,
+                EXTENSION,
+                *segment,
+                *broadcaster_id,
+                content,
+                None,
+            ) {
+                Err(ApiError::InvalidInput(_)) => (),
+                r => panic!("{:?}: {:?}", segment, r),
+            }
+        }
+    }
+}
@@ -0,0 +1,813 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+//! Helpers combining several Helix endpoints into the shapes UIs need
+
+use std::{
+    collections::{
+        HashMap,
+        HashSet,
+    },
+    io,
+    thread,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use chrono::prelude::*;
+
+use super::{
+    channels::{
+        self,
+        ChannelInfo,
+    },
+    games::{
+        self,
+        HelixGame,
+    },
+    streams::{
+        self,
+        HelixStream,
+    },
+    users::{
+        self,
+        HelixUser,
+    },
+    videos::{
+        self,
+        VideoOpts,
+        VideoType,
+    },
+    BatchMode,
+    HelixResponse,
+    PageOpts,
+};
+
+use crate::{
+    response::{
+        ApiError,
+        TwitchResult,
+    },
+    retry::RetryPolicy,
+    util::normalize_login,
+    TwitchClient,
+};
+
+impl TwitchClient {
+    /// Gets everything needed to show a channel in a directory
+    ///
+    /// Resolves `login`, then fetches the stream and the channel information
+    /// concurrently. `stream` is `None` if the channel is offline, the title
+    /// and game are those last set on the channel in that case.
+    ///
+    /// `login` is normalized with `util::normalize_login` first. Fails with
+    /// `ApiError::EmptyResponse` if there is no user `login`.
+    ///
+    /// #### Authentication: App or user access token
+    pub fn stream_card(
+        &self,
+        login: &str,
+    ) -> TwitchResult<StreamCard>
+    {
+        let login = normalize_login(login)?;
+        let user = users::get(self, &[], &[&login])?
+            .into_iter()
+            .next()
+            .ok_or_else(ApiError::empty_response)?;
+
+        let (stream, channel) = thread::scope(|s| {
+            let stream = s.spawn(|| {
+                streams::get(self, &[&user.id], &[], &PageOpts::default())
+            });
+            let channel = channels::get_info(self, &user.id);
+            (stream.join().expect("stream lookup panicked"), channel)
+        });
+        let ChannelInfo {
+            title,
+            game_id,
+            game_name,
+            ..
+        } = channel?;
+
+        Ok(StreamCard {
+            user_id: user.id,
+            login: user.login,
+            display_name: user.display_name,
+            avatar: user.profile_image_url,
+            title,
+            game_id,
+            game_name,
+            stream: stream?.data.into_iter().next(),
+        })
+    }
+
+    /// Joins each of `streams` with its broadcaster and game
+    ///
+    /// Every user and game is only looked up once, the users and games are
+    /// fetched concurrently in batches of 100. Streams keep their order.
+    /// Broadcasters whose lookup failed are left out as `user: None`.
+    ///
+    /// #### Authentication: App or user access token
+    pub fn enrich_streams(
+        &self,
+        streams: Vec<HelixStream>,
+    ) -> TwitchResult<Vec<EnrichedStream>>
+    {
+        let mut seen = HashSet::new();
+        let user_ids: Vec<&str> = streams
+            .iter()
+            .map(|s| s.user_id.as_str())
+            .filter(|id| seen.insert(*id))
+            .collect();
+
+        let (users, games) = thread::scope(|s| {
+            let users = s.spawn(|| {
+                super::batch(&user_ids, BatchMode::Collect, |chunk| {
+                    users::get(self, chunk, &[])
+                })
+            });
+            let games = games::for_streams(self, &streams);
+            (users.join().expect("user lookup panicked"), games)
+        });
+        let users: HashMap<_, _> =
+            users?.ok.into_iter().map(|u| (u.id.clone(), u)).collect();
+        let games = games?;
+
+        Ok(streams
+            .into_iter()
+            .map(|stream| EnrichedStream {
+                user: users.get(&stream.user_id).cloned(),
+                game: games.get(&stream.game_id).cloned(),
+                stream,
+            })
+            .collect())
+    }
+
+    /// Polls the streams endpoint every `poll_interval` until the channel
+    /// `login` is live and returns its stream
+    ///
+    /// `login` is normalized with `util::normalize_login` first.
+    /// Transient errors, see `ApiError::is_retryable`, don't end the wait.
+    /// After a `429` the next poll waits until the rate limit bucket is full
+    /// again, after other errors the interval doubles with every one in a
+    /// row, up to 32 times `poll_interval`. Fails with an `ApiError::IoError`
+    /// of kind `TimedOut` once `timeout` passed without the channel going
+    /// live.
+    ///
+    /// This is handy for scripts, services should subscribe to the
+    /// `stream.online` EventSub event instead of polling.
+    ///
+    /// #### Authentication: App or user access token
+    pub fn wait_until_live(
+        &self,
+        login: &str,
+        poll_interval: Duration,
+        timeout: Option<Duration>,
+    ) -> TwitchResult<HelixStream>
+    {
+        let login = normalize_login(login)?;
+        let deadline = timeout.map(|t| Instant::now() + t);
+        let backoff = RetryPolicy {
+            backoff: poll_interval,
+            ..RetryPolicy::default()
+        };
+        let mut failures = 0;
+        loop {
+            let mut wait = match self
+                .helix_get::<HelixResponse<HelixStream>, _>(&streams::GET, &[(
+                    "user_login",
+                    &login,
+                )]) {
+                Ok(r) => {
+                    if let Some(stream) = r.data.into_iter().next() {
+                        return Ok(stream);
+                    }
+                    failures = 0;
+                    poll_interval
+                }
+                Err(ref e) if e.is_retryable() => {
+                    let wait = backoff.delay_for(
+                        e,
+                        failures,
+                        self.rate_limit_status(),
+                    );
+                    failures = (failures + 1).min(5);
+                    wait
+                }
+                Err(e) => return Err(e),
+            };
+
+            if let Some(deadline) = deadline {
+                let left = deadline.saturating_duration_since(Instant::now());
+                if left.is_zero() {
+                    return Err(ApiError::from(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        "the channel didn't go live in time",
+                    )));
+                }
+                wait = wait.min(left);
+            }
+            thread::sleep(wait);
+        }
+    }
+
+    /// Looks up the game called `name`, e.g. to get the `game_id` to filter
+    /// streams by
+    ///
+    /// Twitch ignores the case of `name` but otherwise only returns exact
+    /// matches, `None` if there is no such game. Found games are remembered
+    /// for the lifetime of the client since their ids never change.
+    ///
+    /// #### Authentication: App or user access token
+    pub fn resolve_game(
+        &self,
+        name: &str,
+    ) -> TwitchResult<Option<HelixGame>>
+    {
+        let key = name.to_lowercase();
+        if let Some(game) = self.games.lock().unwrap().get(&key) {
+            return Ok(Some(game.clone()));
+        }
+
+        let game = games::get(self, &[], &[name])?
+            .into_iter()
+            .find(|g| g.name.to_lowercase() == key);
+        if let Some(ref game) = game {
+            self.games.lock().unwrap().insert(key, game.clone());
+        }
+        Ok(game)
+    }
+
+    /// How long `user_id` has been following `broadcaster_id`, `None` if they
+    /// don't follow the broadcaster
+    ///
+    /// The token has to belong to the broadcaster or one of their moderators
+    /// and carry `moderator:read:followers`, without it Twitch leaves out the
+    /// followers and every user looks like they aren't following. Follow
+    /// times ahead of the local clock count as zero.
+    ///
+    /// #### Authentication: `moderator:read:followers`
+    pub fn followage(
+        &self,
+        user_id: &str,
+        broadcaster_id: &str,
+    ) -> TwitchResult<Option<chrono::Duration>>
+    {
+        let page = channels::followers(
+            self,
+            broadcaster_id,
+            Some(user_id),
+            &PageOpts::default(),
+        )?;
+        Ok(page
+            .data
+            .into_iter()
+            .find(|f| f.user_id == user_id)
+            .map(|f| {
+                Utc::now()
+                    .signed_duration_since(f.followed_at)
+                    .max(chrono::Duration::zero())
+            }))
+    }
+
+    /// Averages the `view_count` of the latest `vod_count` past broadcasts of
+    /// a specified broadcaster
+    ///
+    /// This is only an approximation of the average live viewers, which
+    /// Twitch doesn't keep: VOD views include everyone who watched the
+    /// recording later. `vod_count` is capped at 100, `None` if there are no
+    /// past broadcasts.
+    ///
+    /// #### Authentication: App or user access token
+    pub fn recent_avg_viewers(
+        &self,
+        broadcaster_id: &str,
+        vod_count: usize,
+    ) -> TwitchResult<Option<f64>>
+    {
+        let first = vod_count.min(100);
+        if first == 0 {
+            return Ok(None);
+        }
+        let opts = VideoOpts::default()
+            .user_id(broadcaster_id)
+            .video_type(VideoType::Archive)
+            .first(first as u32);
+        let vods = videos::list(self, &opts)?.data;
+        if vods.is_empty() {
+            return Ok(None);
+        }
+        let total: i64 = vods.iter().map(|v| v.view_count).sum();
+        Ok(Some(total as f64 / vods.len() as f64))
+    }
+}
+
+///////////////////////////////////////
+// StreamCard
+///////////////////////////////////////
+#[derive(Debug, Clone)]
+pub struct StreamCard {
+    pub user_id: String,
+    pub login: String,
+    pub display_name: String,
+    /// URL of the profile image
+    pub avatar: String,
+    pub title: String,
+    pub game_id: String,
+    pub game_name: String,
+    /// `None` if the channel is offline
+    pub stream: Option<HelixStream>,
+}
+
+impl StreamCard {
+    pub fn is_live(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// Current number of viewers, `None` if offline
+    pub fn viewers(&self) -> Option<i64> {
+        self.stream.as_ref().map(|s| s.viewer_count)
+    }
+
+    /// How long the channel has been live, `None` if offline
+    pub fn uptime(&self) -> Option<chrono::Duration> {
+        self.stream
+            .as_ref()
+            .map(|s| Utc::now().signed_duration_since(s.started_at))
+    }
+}
+
+///////////////////////////////////////
+// EnrichedStream
+///////////////////////////////////////
+#[derive(Debug, Clone)]
+pub struct EnrichedStream {
+    pub stream: HelixStream,
+    /// `None` if the broadcaster couldn't be looked up, e.g. because they
+    /// got banned in the meantime
+    pub user: Option<HelixUser>,
+    /// `None` if no game is set
+    pub game: Option<HelixGame>,
+}
+
+impl EnrichedStream {
+    /// URL of the broadcaster's profile image
+    pub fn avatar(&self) -> Option<&str> {
+        self.user.as_ref().map(|u| u.profile_image_url.as_str())
+    }
+
+    /// URL of the game's box art, with `{width}` and `{height}`
+    /// placeholders
+    pub fn box_art_url(&self) -> Option<&str> {
+        self.game.as_ref().map(|g| g.box_art_url.as_str())
+    }
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        time::Duration,
+    };
+
+    use chrono::Utc;
+
+    use crate::{
+        helix::streams::HelixStream,
+        mock,
+        new,
+        response::ApiError,
+    };
+
+    const USERS: &str = r#"{"data": [{
+      "id": "141981764",
+      "login": "twitchdev",
+      "display_name": "TwitchDev",
+      "type": "",
+      "broadcaster_type": "partner",
+      "description": "",
+      "profile_image_url": "https://static-cdn.jtvnw.net/jtv_user_pictures/twitchdev-profile_image-300x300.png",
+      "offline_image_url": "",
+      "created_at": "2016-12-14T20:32:28Z"
+    }]}"#;
+
+    const CHANNELS: &str = r#"{"data": [{
+      "broadcaster_id": "141981764",
+      "broadcaster_login": "twitchdev",
+      "broadcaster_name": "TwitchDev",
+      "broadcaster_language": "en",
+      "game_id": "509670",
+      "game_name": "Science & Technology",
+      "title": "TwitchDev Monthly Update",
+      "delay": 0
+    }]}"#;
+
+    #[test]
+    fn stream_card_live() {
+        let server = mock::serve_routes(vec![
+            ("/users", mock::response(200, &[], USERS)),
+            ("/channels", mock::response(200, &[], CHANNELS)),
+            (
+                "/streams",
+                mock::response(
+                    200,
+                    &[],
+                    r#"{"data": [{
+                      "id": "40952121085",
+                      "user_id": "141981764",
+                      "user_login": "twitchdev",
+                      "user_name": "TwitchDev",
+                      "game_id": "509670",
+                      "game_name": "Science & Technology",
+                      "type": "live",
+                      "title": "TwitchDev Monthly Update",
+                      "viewer_count": 1490,
+                      "started_at": "2021-03-10T03:18:11Z",
+                      "language": "en",
+                      "thumbnail_url": "",
+                      "is_mature": false
+                    }], "pagination": {}}"#,
+                ),
+            ),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let card = c.stream_card("twitchdev").unwrap();
+        assert!(card.is_live());
+        assert_eq!(card.viewers(), Some(1490));
+        assert!(card.uptime().unwrap() > chrono::Duration::zero());
+        assert_eq!(card.game_name, "Science & Technology");
+        assert!(card.avatar.ends_with("profile_image-300x300.png"));
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with("GET /users?login=twitchdev "));
+    }
+
+    #[test]
+    fn stream_card_offline() {
+        let server = mock::serve_routes(vec![
+            ("/users", mock::response(200, &[], USERS)),
+            ("/channels", mock::response(200, &[], CHANNELS)),
+            (
+                "/streams",
+                mock::response(200, &[], r#"{"data": [], "pagination": {}}"#),
+            ),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let card = c.stream_card("twitchdev").unwrap();
+        assert!(!card.is_live());
+        assert_eq!(card.viewers(), None);
+        assert_eq!(card.title, "TwitchDev Monthly Update");
+    }
+
+    #[test]
+    fn wait_until_live() {
+        let server = mock::serve(vec![
+            mock::response(200, &[], r#"{"data": [], "pagination": {}}"#),
+            mock::response(503, &[], ""),
+            mock::response(
+                200,
+                &[],
+                r#"{"data": [{
+                  "id": "40952121085",
+                  "user_id": "141981764",
+                  "user_login": "twitchdev",
+                  "user_name": "TwitchDev",
+                  "game_id": "509670",
+                  "game_name": "Science & Technology",
+                  "type": "live",
+                  "title": "TwitchDev Monthly Update",
+                  "viewer_count": 1490,
+                  "started_at": "2021-03-10T03:18:11Z",
+                  "language": "en",
+                  "thumbnail_url": "",
+                  "is_mature": false
+                }], "pagination": {}}"#,
+            ),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let stream = c
+            .wait_until_live("twitchdev", Duration::from_millis(10), None)
+            .unwrap();
+        assert_eq!(stream.id, "40952121085");
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        assert!(requests[0].starts_with("GET /streams?user_login=twitchdev "));
+    }
+
+    #[test]
+    fn wait_until_live_rate_limited() {
+        let reset = (Utc::now().timestamp() + 1).to_string();
+        let server = mock::serve(vec![
+            mock::response(
+                429,
+                &[
+                    ("Ratelimit-Limit", "800"),
+                    ("Ratelimit-Remaining", "0"),
+                    ("Ratelimit-Reset", &reset),
+                ],
+                "",
+            ),
+            mock::response(
+                200,
+                &[],
+                r#"{"data": [{
+                  "id": "40952121085",
+                  "user_id": "141981764",
+                  "user_login": "twitchdev",
+                  "user_name": "TwitchDev",
+                  "game_id": "509670",
+                  "game_name": "Science & Technology",
+                  "type": "live",
+                  "title": "TwitchDev Monthly Update",
+                  "viewer_count": 1490,
+                  "started_at": "2021-03-10T03:18:11Z",
+                  "language": "en",
+                  "thumbnail_url": "",
+                  "is_mature": false
+                }], "pagination": {}}"#,
+            ),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        c.wait_until_live("twitchdev", Duration::from_millis(10), None)
+            .unwrap();
+        // Polled again after the reset, not after the poll interval
+        assert!(Utc::now().timestamp() >= reset.parse::<i64>().unwrap());
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[test]
+    fn wait_until_live_timeout() {
+        let server = mock::serve(vec![mock::response(
+            200,
+            &[],
+            r#"{"data": [], "pagination": {}}"#,
+        )]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        match c.wait_until_live(
+            "twitchdev",
+            Duration::from_secs(60),
+            Some(Duration::from_secs(0)),
+        ) {
+            Err(ApiError::IoError(ref e))
+                if e.kind() == io::ErrorKind::TimedOut => {}
+            r => panic!("{:?}", r),
+        }
+        // polls once, the deadline passed before the next poll
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[test]
+    fn enrich_streams() {
+        let stream = |id: &str, game_id: &str| -> HelixStream {
+            serde_json::from_str(&format!(
+                r#"{{
+                  "id": "{}",
+                  "user_id": "141981764",
+                  "user_login": "twitchdev",
+                  "user_name": "TwitchDev",
+                  "game_id": "{}",
+                  "game_name": "",
+                  "type": "live",
+                  "title": "TwitchDev Monthly Update",
+                  "viewer_count": 1490,
+                  "started_at": "2021-03-10T03:18:11Z",
+                  "language": "en",
+                  "thumbnail_url": "",
+                  "is_mature": false
+                }}"#,
+                id, game_id
+            ))
+            .unwrap()
+        };
+        let server = mock::serve_routes(vec![
+            ("/users", mock::response(200, &[], USERS)),
+            (
+                "/games",
+                mock::response(
+                    200,
+                    &[],
+                    r#"{"data": [{
+                      "id": "509670",
+                      "name": "Science & Technology",
+                      "box_art_url": "https://static-cdn.jtvnw.net/ttv-boxart/509670-{width}x{height}.jpg"
+                    }]}"#,
+                ),
+            ),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let enriched = c
+            .enrich_streams(vec![stream("1", "509670"), stream("2", "")])
+            .unwrap();
+        assert_eq!(enriched[0].stream.id, "1");
+        assert!(enriched[0].avatar().unwrap().ends_with("300x300.png"));
+        assert_eq!(
+            enriched[0].game.as_ref().unwrap().name,
+            "Science & Technology"
+        );
+        assert!(enriched[1].box_art_url().is_none());
+        assert_eq!(
+            enriched[1].user.as_ref().unwrap().display_name,
+            "TwitchDev"
+        );
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests
+            .iter()
+            .any(|r| r.starts_with("GET /users?id=141981764 ")));
+        assert!(requests
+            .iter()
+            .any(|r| r.starts_with("GET /games?id=509670 ")));
+    }
+
+    #[test]
+    fn enrich_streams_failed_users() {
+        let stream: HelixStream = serde_json::from_str(
+            r#"{
+              "id": "1",
+              "user_id": "141981764",
+              "user_login": "twitchdev",
+              "user_name": "TwitchDev",
+              "game_id": "",
+              "game_name": "",
+              "type": "live",
+              "title": "TwitchDev Monthly Update",
+              "viewer_count": 1490,
+              "started_at": "2021-03-10T03:18:11Z",
+              "language": "en",
+              "thumbnail_url": "",
+              "is_mature": false
+            }"#,
+        )
+        .unwrap();
+        let server = mock::serve(vec![mock::response(500, &[], "")]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let enriched = c.enrich_streams(vec![stream]).unwrap();
+        assert_eq!(enriched[0].stream.id, "1");
+        assert!(enriched[0].user.is_none());
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[test]
+    fn stream_card_invalid_login() {
+        let c = new(String::from("<clientid>"));
+        match c.stream_card("@twitch dev") {
+            Err(ApiError::InvalidInput(_)) => (),
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn resolve_game() {
+        let server = mock::serve(vec![
+            mock::response(
+                200,
+                &[],
+                r#"{"data": [{
+                  "id": "33214",
+                  "name": "Fortnite",
+                  "box_art_url": "https://static-cdn.jtvnw.net/ttv-boxart/33214-{width}x{height}.jpg",
+                  "igdb_id": "1905"
+                }]}"#,
+            ),
+            mock::response(200, &[], r#"{"data": []}"#),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let game = c.resolve_game("fortnite").unwrap().unwrap();
+        assert_eq!(game.id, "33214");
+        // Served from memory, no request is sent
+        assert_eq!(c.resolve_game("FORTNITE").unwrap().unwrap().id, "33214");
+        assert!(c.resolve_game("Fortnight").unwrap().is_none());
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].starts_with("GET /games?name=fortnite "));
+        assert!(requests[1].starts_with("GET /games?name=Fortnight "));
+    }
+
+    fn vod(view_count: i64) -> String {
+        format!(
+            r#"{{
+              "id": "335921245",
+              "stream_id": "40952121085",
+              "user_id": "141981764",
+              "user_login": "twitchdev",
+              "user_name": "TwitchDev",
+              "title": "TwitchDev Monthly Update",
+              "description": "",
+              "created_at": "2021-03-10T03:18:11Z",
+              "published_at": "2021-03-10T03:18:11Z",
+              "url": "https://www.twitch.tv/videos/335921245",
+              "thumbnail_url": "",
+              "viewable": "public",
+              "view_count": {},
+              "language": "en",
+              "type": "archive",
+              "duration": "1h2m3s",
+              "muted_segments": null
+            }}"#,
+            view_count
+        )
+    }
+
+    #[test]
+    fn recent_avg_viewers() {
+        let server = mock::serve(vec![
+            mock::response(
+                200,
+                &[],
+                &format!(
+                    r#"{{"data": [{}, {}, {}], "pagination": {{}}}}"#,
+                    vod(100),
+                    vod(250),
+                    vod(30)
+                ),
+            ),
+            mock::response(200, &[], r#"{"data": [], "pagination": {}}"#),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let avg = c.recent_avg_viewers("141981764", 500).unwrap();
+        assert_eq!(avg, Some(380.0 / 3.0));
+        assert_eq!(c.recent_avg_viewers("141981764", 5).unwrap(), None);
+        // Nothing to average, no request is sent
+        assert_eq!(c.recent_avg_viewers("141981764", 0).unwrap(), None);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].starts_with(
+            "GET /videos?user_id=141981764&type=archive&first=100 "
+        ));
+        assert!(requests[1].starts_with(
+            "GET /videos?user_id=141981764&type=archive&first=5 "
+        ));
+    }
+
+    #[test]
+    fn followage() {
+        let follower = |followed_at: &str| {
+            mock::response(
+                200,
+                &[],
+                &format!(
+                    r#"{{
+                      "total": 8,
+                      "data": [{{
+                        "user_id": "11111",
+                        "user_name": "UserDisplayName",
+                        "user_login": "userloginname",
+                        "followed_at": "{}"
+                      }}],
+                      "pagination": {{}}
+                    }}"#,
+                    followed_at
+                ),
+            )
+        };
+        let server = mock::serve(vec![
+            follower("2022-05-24T22:22:08Z"),
+            follower("2999-01-01T00:00:00Z"),
+            mock::response(
+                200,
+                &[],
+                r#"{"total": 8, "data": [], "pagination": {}}"#,
+            ),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let age = c.followage("11111", "141981764").unwrap().unwrap();
+        assert!(age > chrono::Duration::days(365));
+        let age = c.followage("11111", "141981764").unwrap().unwrap();
+        assert_eq!(age, chrono::Duration::zero());
+        assert_eq!(c.followage("11111", "141981764").unwrap(), None);
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with(
+            "GET /channels/followers?broadcaster_id=141981764&user_id=11111 "
+        ));
+    }
+}
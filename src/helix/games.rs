@@ -0,0 +1,309 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+use std::collections::{
+    HashMap,
+    HashSet,
+};
+
+use serde::Deserialize;
+
+use super::{
+    streams::HelixStream,
+    BatchMode,
+    BatchResult,
+    Endpoint,
+    HelixResponse,
+};
+
+use crate::{
+    response::{
+        ApiError,
+        TwitchResult,
+    },
+    TwitchClient,
+};
+
+pub const GET: Endpoint = Endpoint::new("/games");
+
+/// Gets the games with the specified ids or names
+///
+/// At most 100 ids and names can be looked up at once. Unknown games are
+/// left out of the result.
+///
+/// #### Authentication: App or user access token
+pub fn get(
+    c: &TwitchClient,
+    ids: &[&str],
+    names: &[&str],
+) -> TwitchResult<Vec<HelixGame>>
+{
+    if ids.len() + names.len() > 100 {
+        return Err(ApiError::invalid_input(
+            "at most 100 games can be looked up at once",
+        ));
+    }
+    let mut params: Vec<(&str, &str)> = Vec::new();
+    params.extend(ids.iter().map(|id| ("id", *id)));
+    params.extend(names.iter().map(|name| ("name", *name)));
+    let r = c.helix_get::<HelixResponse<HelixGame>, _>(&GET, &params)?;
+    Ok(r.data)
+}
+
+/// Gets the games with the specified ids, any number at once
+///
+/// Sends one request per 100 ids. With `BatchMode::Collect` the ids of
+/// failed requests are returned in `BatchResult::errors`.
+///
+/// #### Authentication: App or user access token
+pub fn get_by_ids(
+    c: &TwitchClient,
+    ids: &[&str],
+    mode: BatchMode,
+) -> TwitchResult<BatchResult<Vec<String>, HelixGame>>
+{
+    super::batch(ids, mode, |chunk| get(c, chunk, &[]))
+}
+
+/// Gets the games played on `streams`, keyed by game id
+///
+/// Every game is only looked up once, streams without a game are skipped.
+/// Needs one request per 100 distinct games.
+///
+/// #### Authentication: App or user access token
+pub fn for_streams(
+    c: &TwitchClient,
+    streams: &[HelixStream],
+) -> TwitchResult<HashMap<String, HelixGame>>
+{
+    let mut seen = HashSet::new();
+    let ids: Vec<&str> = streams
+        .iter()
+        .map(|s| s.game_id.as_str())
+        .filter(|id| !id.is_empty() && seen.insert(*id))
+        .collect();
+
+    let r = get_by_ids(c, &ids, BatchMode::FailFast)?;
+    Ok(r.ok.into_iter().map(|g| (g.id.clone(), g)).collect())
+}
+
+/// Gets the games for a category grid, each with its box art URL at
+/// `art_width`x`art_height`
+///
+/// All-digit entries of `names_or_ids` are looked up as ids, everything
+/// else as names. Games keep the order of `names_or_ids`, each game is
+/// only returned once and unknown games are left out. Needs one request
+/// per 100 distinct entries.
+///
+/// #### Authentication: App or user access token
+pub fn grid(
+    c: &TwitchClient,
+    names_or_ids: &[&str],
+    art_width: u32,
+    art_height: u32,
+) -> TwitchResult<Vec<(HelixGame, String)>>
+{
+    let is_id = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    let mut seen = HashSet::new();
+    let wanted: Vec<&str> = names_or_ids
+        .iter()
+        .copied()
+        .filter(|s| !s.is_empty() && seen.insert(s.to_lowercase()))
+        .collect();
+
+    let mut by_id = HashMap::new();
+    for chunk in wanted.chunks(100) {
+        let (ids, names): (Vec<&str>, Vec<&str>) =
+            chunk.iter().partition(|s| is_id(s));
+        for game in get(c, &ids, &names)? {
+            by_id.insert(game.id.clone(), game);
+        }
+    }
+    let by_name: HashMap<_, _> = by_id
+        .values()
+        .map(|g| (g.name.to_lowercase(), g.id.clone()))
+        .collect();
+
+    let mut returned = HashSet::new();
+    Ok(wanted
+        .iter()
+        .filter_map(|s| {
+            let id = if is_id(s) {
+                s.to_string()
+            }
+            else {
+                by_name.get(&s.to_lowercase())?.clone()
+            };
+            let game =
+                by_id.get(&id).filter(|_| returned.insert(id.clone()))?;
+            let art = game.box_art(art_width, art_height);
+            Some((game.clone(), art))
+        })
+        .collect())
+}
+
+///////////////////////////////////////
+// GetGames
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone)]
+pub struct HelixGame {
+    pub id: String,
+    pub name: String,
+    /// Contains `{width}` and `{height}` placeholders
+    pub box_art_url: String,
+    /// Empty if the game isn't listed on IGDB
+    #[serde(default)]
+    pub igdb_id: String,
+}
+
+impl HelixGame {
+    /// `box_art_url` at `width`x`height` pixels
+    pub fn box_art(
+        &self,
+        width: u32,
+        height: u32,
+    ) -> String
+    {
+        self.box_art_url
+            .replace("{width}", &width.to_string())
+            .replace("{height}", &height.to_string())
+    }
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        helix::{
+            streams::HelixStream,
+            HelixResponse,
+        },
+        mock,
+        new,
+    };
+
+    fn stream(game_id: &str) -> HelixStream {
+        let r: HelixResponse<HelixStream> = serde_json::from_str(&format!(
+            r#"{{"data": [{{
+              "id": "1",
+              "user_id": "2",
+              "user_login": "streamer",
+              "user_name": "Streamer",
+              "game_id": "{}",
+              "game_name": "",
+              "type": "live",
+              "title": "",
+              "viewer_count": 1,
+              "started_at": "2021-03-10T03:18:11Z",
+              "language": "en",
+              "thumbnail_url": "",
+              "is_mature": false
+            }}]}}"#,
+            game_id
+        ))
+        .unwrap();
+        r.single().unwrap()
+    }
+
+    fn game(id: &str) -> String {
+        format!(
+            r#"{{"id":"{}","name":"Game {}","box_art_url":"","igdb_id":""}}"#,
+            id, id
+        )
+    }
+
+    #[test]
+    fn for_streams() {
+        let server = mock::serve(vec![mock::response(
+            200,
+            &[],
+            &format!(r#"{{"data": [{}, {}]}}"#, game("33214"), game("509658")),
+        )]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let streams = vec![
+            stream("33214"),
+            stream(""),
+            stream("509658"),
+            stream("33214"),
+        ];
+        let games = super::for_streams(&c, &streams).unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games["509658"].name, "Game 509658");
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with("GET /games?id=33214&id=509658 "));
+    }
+
+    #[test]
+    fn for_streams_chunks() {
+        let server = mock::serve(vec![
+            mock::response(200, &[], r#"{"data": []}"#),
+            mock::response(
+                200,
+                &[],
+                &format!(r#"{{"data": [{}]}}"#, game("100")),
+            ),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let streams: Vec<_> =
+            (0..101).map(|i| stream(&i.to_string())).collect();
+        let games = super::for_streams(&c, &streams).unwrap();
+        assert_eq!(games.len(), 1);
+
+        let requests = server.requests();
+        assert_eq!(requests[0].matches("id=").count(), 100);
+        assert!(requests[1].starts_with("GET /games?id=100 "));
+    }
+
+    #[test]
+    fn for_streams_without_games() {
+        let c = new(String::from("<clientid>"));
+        assert!(super::for_streams(&c, &[stream("")]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn grid() {
+        let server = mock::serve(vec![mock::response(
+            200,
+            &[],
+            r#"{"data": [{
+              "id": "509658",
+              "name": "Just Chatting",
+              "box_art_url": "https://static-cdn.jtvnw.net/ttv-boxart/509658-{width}x{height}.jpg",
+              "igdb_id": ""
+            }, {
+              "id": "33214",
+              "name": "Fortnite",
+              "box_art_url": "https://static-cdn.jtvnw.net/ttv-boxart/33214-{width}x{height}.jpg",
+              "igdb_id": "1905"
+            }]}"#,
+        )]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let grid = super::grid(
+            &c,
+            &["Fortnite", "No Such Game", "509658", "fortnite", "33214"],
+            144,
+            192,
+        )
+        .unwrap();
+        let names: Vec<_> = grid.iter().map(|(g, _)| g.name.as_str()).collect();
+        assert_eq!(names, vec!["Fortnite", "Just Chatting"]);
+        assert_eq!(
+            grid[0].1,
+            "https://static-cdn.jtvnw.net/ttv-boxart/33214-144x192.jpg"
+        );
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with(
+            "GET /games?id=509658&id=33214&name=Fortnite&name=No+Such+Game "
+        ));
+    }
+}
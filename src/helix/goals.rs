@@ -0,0 +1,180 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+use chrono::prelude::*;
+use serde::Deserialize;
+
+use super::{
+    scope::HelixScope,
+    Endpoint,
+    HelixResponse,
+};
+
+use crate::{
+    response::TwitchResult,
+    TwitchClient,
+};
+
+pub const GET: Endpoint =
+    Endpoint::new("/goals").with_scopes(&[HelixScope::ChannelReadGoals]);
+
+/// Gets the creator goals a specified broadcaster is currently working
+/// towards
+///
+/// Returns an empty list if the broadcaster has no active goal.
+///
+/// #### Authentication: `channel:read:goals`
+pub fn get(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+) -> TwitchResult<Vec<Goal>>
+{
+    let r = c.helix_get::<HelixResponse<Goal>, _>(&GET, &[(
+        "broadcaster_id",
+        broadcaster_id,
+    )])?;
+    Ok(r.data)
+}
+
+///////////////////////////////////////
+// GetCreatorGoals
+///////////////////////////////////////
+#[derive(Deserialize, Debug)]
+pub struct Goal {
+    pub id: String,
+    pub broadcaster_id: String,
+    pub broadcaster_name: String,
+    pub broadcaster_login: String,
+    #[serde(rename = "type")]
+    pub _type: GoalType,
+    pub description: String,
+    pub current_amount: i64,
+    pub target_amount: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Goal {
+    /// How far the goal is reached, from 0 to 100
+    ///
+    /// `current_amount` can briefly exceed `target_amount`, the result is
+    /// capped at 100 then. Goals without a target count as 0.
+    pub fn progress_percent(&self) -> f64 {
+        if self.target_amount <= 0 {
+            return 0.0;
+        }
+        let percent =
+            self.current_amount as f64 / self.target_amount as f64 * 100.0;
+        percent.clamp(0.0, 100.0)
+    }
+}
+
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum GoalType {
+    /// Number of followers
+    Follower,
+    /// Subscription points, e.g. a tier 3 sub counts 6 points
+    Subscription,
+    /// Number of subscribers
+    SubscriptionCount,
+    /// New subscription points added during the goal
+    NewSubscription,
+    /// New subscribers added during the goal
+    NewSubscriptionCount,
+    /// A type this crate doesn't know yet, e.g. `new_bit`
+    #[serde(other)]
+    Other,
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Goal,
+        GoalType,
+    };
+    use crate::helix::HelixResponse;
+
+    #[test]
+    fn goals() {
+        let r: HelixResponse<Goal> = serde_json::from_str(
+            r#"{
+              "data": [{
+                "id": "1woowvbkiNv8BRxEWSqmQz6Zk92",
+                "broadcaster_id": "141981764",
+                "broadcaster_name": "TwitchDev",
+                "broadcaster_login": "twitchdev",
+                "type": "follower",
+                "description": "Follow goal for Helix testing",
+                "current_amount": 27062,
+                "target_amount": 30000,
+                "created_at": "2021-08-16T17:22:23Z"
+              }, {
+                "id": "1woowvbkiNv8BRxEWSqmQz6Zk93",
+                "broadcaster_id": "141981764",
+                "broadcaster_name": "TwitchDev",
+                "broadcaster_login": "twitchdev",
+                "type": "new_subscription_count",
+                "description": "",
+                "current_amount": 3,
+                "target_amount": 10,
+                "created_at": "2021-08-16T17:22:23Z"
+              }, {
+                "id": "1woowvbkiNv8BRxEWSqmQz6Zk94",
+                "broadcaster_id": "141981764",
+                "broadcaster_name": "TwitchDev",
+                "broadcaster_login": "twitchdev",
+                "type": "new_cheerer",
+                "description": "",
+                "current_amount": 1,
+                "target_amount": 50,
+                "created_at": "2021-08-16T17:22:23Z"
+              }]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(r.data[0]._type, GoalType::Follower);
+        assert_eq!(r.data[0].current_amount, 27062);
+        assert_eq!(r.data[1]._type, GoalType::NewSubscriptionCount);
+        assert_eq!(r.data[2]._type, GoalType::Other);
+    }
+
+    #[test]
+    fn no_goals() {
+        let r: HelixResponse<Goal> =
+            serde_json::from_str(r#"{"data": []}"#).unwrap();
+        assert!(r.data.is_empty());
+    }
+
+    #[test]
+    fn progress_percent() {
+        let mut r: HelixResponse<Goal> = serde_json::from_str(
+            r#"{
+              "data": [{
+                "id": "1woowvbkiNv8BRxEWSqmQz6Zk92",
+                "broadcaster_id": "141981764",
+                "broadcaster_name": "TwitchDev",
+                "broadcaster_login": "twitchdev",
+                "type": "subscription_count",
+                "description": "",
+                "current_amount": 0,
+                "target_amount": 200,
+                "created_at": "2021-08-16T17:22:23Z"
+              }]
+            }"#,
+        )
+        .unwrap();
+        let goal = &mut r.data[0];
+
+        assert_eq!(goal.progress_percent(), 0.0);
+        goal.current_amount = 100;
+        assert_eq!(goal.progress_percent(), 50.0);
+        goal.current_amount = 201;
+        assert_eq!(goal.progress_percent(), 100.0);
+        goal.target_amount = 0;
+        assert_eq!(goal.progress_percent(), 0.0);
+    }
+}
@@ -0,0 +1,150 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+use chrono::prelude::*;
+use serde::Deserialize;
+
+use super::{
+    scope::HelixScope,
+    Endpoint,
+    HelixPage,
+    HelixResponse,
+    PageOpts,
+};
+
+use crate::{
+    response::TwitchResult,
+    TwitchClient,
+};
+
+pub const EVENTS: Endpoint = Endpoint::new("/hypetrain/events")
+    .with_scopes(&[HelixScope::ChannelReadHypeTrain]);
+
+/// Gets the hype train events of a specified broadcaster, most recent first
+///
+/// The endpoint defaults to a single event per page, use `opts.first` to
+/// fetch up to 100 historical events at once.
+///
+/// #### Authentication: `channel:read:hype_train`
+pub fn events(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    opts: &PageOpts,
+) -> TwitchResult<HelixPage<HypeTrainEvent>>
+{
+    opts.check_forward()?;
+    let mut params = vec![("broadcaster_id", broadcaster_id.to_owned())];
+    if let Some(first) = opts.first {
+        params.push(("first", first.to_string()));
+    }
+    if let Some(ref after) = opts.after {
+        params.push(("cursor", after.clone()));
+    }
+    let r =
+        c.helix_get::<HelixResponse<HypeTrainEvent>, _>(&EVENTS, &params)?;
+    Ok(HelixPage::from(r))
+}
+
+///////////////////////////////////////
+// GetHypeTrainEvents
+///////////////////////////////////////
+#[derive(Deserialize, Debug)]
+pub struct HypeTrainEvent {
+    pub id: String,
+    pub event_type: String,
+    pub event_timestamp: DateTime<Utc>,
+    pub version: String,
+    pub event_data: HypeTrainEventData,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct HypeTrainEventData {
+    pub id: String,
+    pub broadcaster_id: String,
+    pub level: i32,
+    /// Progress towards `goal` of the current level
+    pub total: i64,
+    pub goal: i64,
+    pub started_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub cooldown_end_time: DateTime<Utc>,
+    pub last_contribution: Contribution,
+    pub top_contributions: Vec<Contribution>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Contribution {
+    pub total: i64,
+    #[serde(rename = "type")]
+    pub _type: ContributionType,
+    pub user: String,
+}
+
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ContributionType {
+    Bits,
+    Subs,
+    #[serde(other)]
+    Other,
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ContributionType,
+        HypeTrainEvent,
+    };
+    use crate::helix::{
+        HelixPage,
+        HelixResponse,
+    };
+
+    #[test]
+    fn events() {
+        let r: HelixResponse<HypeTrainEvent> = serde_json::from_str(
+            r#"{
+              "data": [{
+                "id": "1b0AsbInCHZW2SQFQkCzqN07Ib2",
+                "event_type": "hypetrain.progression",
+                "event_timestamp": "2020-04-24T20:07:24Z",
+                "version": "1.0",
+                "event_data": {
+                  "broadcaster_id": "270954519",
+                  "cooldown_end_time": "2020-04-24T20:13:21.003802269Z",
+                  "expires_at": "2020-04-24T20:12:21.003802269Z",
+                  "goal": 1800,
+                  "id": "70f0c7d8-ff60-4c50-b138-f3a352833b50",
+                  "last_contribution": {
+                    "total": 200,
+                    "type": "BITS",
+                    "user": "134247454"
+                  },
+                  "level": 2,
+                  "started_at": "2020-04-24T20:05:47.30473127Z",
+                  "top_contributions": [
+                    {"total": 600, "type": "BITS", "user": "134247450"},
+                    {"total": 1000, "type": "SUBS", "user": "134247454"}
+                  ],
+                  "total": 600
+                }
+              }],
+              "pagination": {"cursor": "MTpoeXBldHJhaW46MTpsYXN0"}
+            }"#,
+        )
+        .unwrap();
+
+        let page = HelixPage::from(r);
+        assert_eq!(page.cursor.as_deref(), Some("MTpoeXBldHJhaW46MTpsYXN0"));
+
+        let data = &page.data[0].event_data;
+        assert_eq!(data.level, 2);
+        assert_eq!(data.total, 600);
+        assert_eq!(data.last_contribution._type, ContributionType::Bits);
+        assert_eq!(data.top_contributions[1]._type, ContributionType::Subs);
+        assert_eq!(data.top_contributions[1].total, 1000);
+    }
+}
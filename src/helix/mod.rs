@@ -0,0 +1,558 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+//! Bindings for the new Twitch API (Helix)
+//!
+//! https://dev.twitch.tv/docs/api/reference
+
+pub mod analytics;
+pub mod bits;
+pub mod channel_points;
+pub mod channels;
+pub mod charity;
+pub mod chat;
+pub mod clips;
+pub mod compat;
+pub mod entitlements;
+pub mod eventsub;
+pub mod extensions;
+pub mod facade;
+pub mod games;
+pub mod goals;
+pub mod hypetrain;
+pub mod moderation;
+pub mod raids;
+pub mod schedule;
+pub mod scope;
+pub mod streams;
+pub mod subscriptions;
+pub mod users;
+pub mod videos;
+
+use serde::Deserialize;
+
+use crate::response::{
+    ApiError,
+    TwitchResult,
+};
+
+use scope::HelixScope;
+
+///////////////////////////////////////
+// Endpoint
+///////////////////////////////////////
+/// Describes a Helix endpoint
+///
+/// Every endpoint module declares one of these per endpoint, which the
+/// `TwitchClient::helix_*` helpers take instead of a bare path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Endpoint {
+    /// Path relative to `https://api.twitch.tv/helix`
+    pub path: &'static str,
+    cost: u32,
+    scopes: &'static [HelixScope],
+}
+
+impl Endpoint {
+    pub const fn new(path: &'static str) -> Endpoint {
+        Endpoint {
+            path,
+            cost: 1,
+            scopes: &[],
+        }
+    }
+
+    /// Sets the scopes granting access to this endpoint
+    pub const fn with_scopes(
+        self,
+        scopes: &'static [HelixScope],
+    ) -> Endpoint
+    {
+        Endpoint { scopes, ..self }
+    }
+
+    /// The scopes granting access to this endpoint
+    ///
+    /// A user access token with any one of them may call the endpoint. If
+    /// there are none, any token works.
+    pub fn scopes(&self) -> &'static [HelixScope] {
+        self.scopes
+    }
+
+    /// Sets the rate limit points a request to this endpoint costs
+    pub const fn with_cost(
+        self,
+        cost: u32,
+    ) -> Endpoint
+    {
+        Endpoint { cost, ..self }
+    }
+
+    /// The rate limit points a request to this endpoint costs
+    ///
+    /// Twitch documents the points per endpoint in its rate limit guide
+    /// (https://dev.twitch.tv/docs/api/guide#rate-limits). Requests cost a
+    /// single point unless stated otherwise, which is the case for all
+    /// endpoints this crate supports so far.
+    pub fn cost(&self) -> u32 {
+        self.cost
+    }
+}
+
+/// Every endpoint this crate supports
+pub const ENDPOINTS: &[Endpoint] = &[
+    analytics::EXTENSIONS,
+    analytics::GAMES,
+    bits::CHEERMOTES,
+    channel_points::REDEMPTIONS,
+    channels::GET_INFO,
+    channels::ADD_VIP,
+    channels::REMOVE_VIP,
+    channels::START_COMMERCIAL,
+    channels::FOLLOWERS,
+    charity::CAMPAIGN,
+    charity::DONATIONS,
+    chat::GET_CHATTERS,
+    chat::GET_USER_COLOR,
+    chat::UPDATE_USER_COLOR,
+    chat::SEND_MESSAGE,
+    chat::GLOBAL_BADGES,
+    chat::CHANNEL_BADGES,
+    chat::CHANNEL_EMOTES,
+    chat::GLOBAL_EMOTES,
+    chat::GET_SETTINGS,
+    clips::GET,
+    entitlements::GET,
+    entitlements::UPDATE,
+    eventsub::conduits::GET,
+    eventsub::conduits::CREATE,
+    eventsub::conduits::UPDATE,
+    eventsub::conduits::DELETE,
+    eventsub::conduits::GET_SHARDS,
+    eventsub::conduits::UPDATE_SHARDS,
+    eventsub::subscriptions::CREATE,
+    eventsub::subscriptions::GET,
+    eventsub::subscriptions::DELETE,
+    extensions::TRANSACTIONS,
+    extensions::CONFIGURATIONS,
+    extensions::SET_CONFIGURATION,
+    extensions::SET_REQUIRED_CONFIGURATION,
+    games::GET,
+    goals::GET,
+    hypetrain::EVENTS,
+    moderation::BLOCKED_TERMS,
+    moderation::ADD_BLOCKED_TERM,
+    moderation::REMOVE_BLOCKED_TERM,
+    moderation::CHECK_AUTOMOD,
+    moderation::MANAGE_HELD_MESSAGE,
+    moderation::SHIELD_MODE,
+    moderation::UPDATE_SHIELD_MODE,
+    moderation::BANNED_USERS,
+    moderation::AUTOMOD_SETTINGS,
+    moderation::UPDATE_AUTOMOD_SETTINGS,
+    moderation::WARN_USER,
+    moderation::ADD_MODERATOR,
+    moderation::REMOVE_MODERATOR,
+    raids::START,
+    raids::CANCEL,
+    schedule::ICALENDAR,
+    streams::GET,
+    streams::GET_STREAM_KEY,
+    streams::TAGS,
+    streams::REPLACE_TAGS,
+    subscriptions::GET,
+    subscriptions::CHECK,
+    users::GET,
+    users::GET_SELF,
+    users::BLOCKS,
+    users::BLOCK_USER,
+    users::UNBLOCK_USER,
+    videos::GET,
+    videos::DELETE,
+];
+
+/// Lists the path and scopes of every endpoint in `ENDPOINTS`
+///
+/// Meant for generating documentation or a screen explaining which
+/// permissions an app asks for. Paths served with several methods occur
+/// once per method.
+pub fn scope_matrix() -> Vec<(&'static str, &'static [HelixScope])> {
+    ENDPOINTS.iter().map(|e| (e.path, e.scopes)).collect()
+}
+
+/// Parses a Twitch id for numeric use, e.g. as a database key
+///
+/// Ids are numeric strings, but Twitch doesn't promise they stay below any
+/// bound, so they are kept as `String` everywhere else. Fails with
+/// `ApiError::InvalidInput` if `id` isn't a number which fits a `u64`.
+pub fn parse_u64(id: &str) -> TwitchResult<u64> {
+    id.parse()
+        .map_err(|_| ApiError::invalid_input("ids have to fit into a u64"))
+}
+
+///////////////////////////////////////
+// HelixResponse
+///////////////////////////////////////
+/// The envelope every Helix response is wrapped in
+///
+/// Results are always delivered as a `data` array, even for endpoints
+/// which only ever return a single resource.
+#[derive(Deserialize, Debug)]
+pub struct HelixResponse<T> {
+    pub data: Vec<T>,
+    #[serde(default)]
+    pub pagination: Option<Pagination>,
+    /// Total number of results across all pages, only sent by some
+    /// endpoints
+    #[serde(default)]
+    pub total: Option<i64>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct Pagination {
+    pub cursor: Option<String>,
+}
+
+impl<T> HelixResponse<T> {
+    /// Returns the sole element of `data`
+    ///
+    /// Fails with `ApiError::EmptyResponse` if `data` is empty and with
+    /// `ApiError::UnexpectedResultCount` if it holds more than one element.
+    pub fn single(self) -> TwitchResult<T> {
+        let cnt = self.data.len();
+        let mut data = self.data.into_iter();
+        match (data.next(), cnt) {
+            (Some(x), 1) => Ok(x),
+            (None, _) => Err(ApiError::empty_response()),
+            (Some(_), cnt) => Err(ApiError::UnexpectedResultCount(cnt)),
+        }
+    }
+
+    /// Returns the cursor pointing to the next page, if there is one
+    pub fn cursor(&self) -> Option<&str> {
+        match self.pagination {
+            Some(Pagination {
+                cursor: Some(ref cursor),
+            }) if !cursor.is_empty() => Some(cursor),
+            _ => None,
+        }
+    }
+}
+
+///////////////////////////////////////
+// HelixPage
+///////////////////////////////////////
+/// A single page of a paginated Helix endpoint
+///
+/// Pass `cursor` as `PageOpts::after` to fetch the next page.
+#[derive(Debug)]
+pub struct HelixPage<T> {
+    pub data: Vec<T>,
+    pub cursor: Option<String>,
+    /// Total number of results across all pages, if the endpoint reports it
+    pub total: Option<i64>,
+}
+
+impl<T> From<HelixResponse<T>> for HelixPage<T> {
+    fn from(r: HelixResponse<T>) -> HelixPage<T> {
+        let cursor = r.cursor().map(String::from);
+        HelixPage {
+            data: r.data,
+            cursor,
+            total: r.total,
+        }
+    }
+}
+
+/// Pagination options shared by the paginated Helix endpoints
+#[derive(Debug, Default, Clone)]
+pub struct PageOpts {
+    /// Maximum number of items per page
+    pub first: Option<u32>,
+    /// Cursor of the page to continue after, or before when paging
+    /// `Direction::Backward`
+    pub after: Option<String>,
+    /// Which way to page from `after`, only endpoints documenting it support
+    /// `Direction::Backward`, the others fail with `ApiError::InvalidInput`
+    pub direction: Direction,
+}
+
+impl PageOpts {
+    /// The query parameter the cursor is sent as, `after` or `before`
+    pub(crate) fn cursor_param(&self) -> &'static str {
+        match self.direction {
+            Direction::Forward => "after",
+            Direction::Backward => "before",
+        }
+    }
+
+    /// The `first` and cursor query parameters, for endpoints which can be
+    /// paged both ways
+    pub(crate) fn params(&self) -> Vec<(&'static str, String)> {
+        let mut params = Vec::new();
+        if let Some(first) = self.first {
+            params.push(("first", first.to_string()));
+        }
+        if let Some(ref after) = self.after {
+            params.push((self.cursor_param(), after.clone()));
+        }
+        params
+    }
+
+    /// Fails with `ApiError::InvalidInput` for `Direction::Backward`, for
+    /// endpoints which can only be paged forward
+    pub(crate) fn check_forward(&self) -> TwitchResult<()> {
+        match self.direction {
+            Direction::Forward => Ok(()),
+            Direction::Backward => Err(ApiError::invalid_input(
+                "this endpoint can't be paged backward",
+            )),
+        }
+    }
+
+    /// Like `params`, for endpoints which can only be paged forward
+    pub(crate) fn forward_params(
+        &self
+    ) -> TwitchResult<Vec<(&'static str, String)>>
+    {
+        self.check_forward()?;
+        Ok(self.params())
+    }
+}
+
+/// Which way a paginated Helix endpoint is paged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Direction {
+    #[default]
+    Forward,
+    Backward,
+}
+
+///////////////////////////////////////
+// BatchResult
+///////////////////////////////////////
+/// What a batch helper does when one of its requests fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchMode {
+    /// Return the first error, discarding what succeeded so far
+    FailFast,
+    /// Keep going and collect the errors in `BatchResult::errors`
+    Collect,
+}
+
+/// The outcome of a batch helper which splits its input into several
+/// requests
+///
+/// `errors` holds the input of every failed request together with the
+/// error, so callers can report or retry exactly what failed.
+#[derive(Debug)]
+pub struct BatchResult<I, T> {
+    pub ok: Vec<T>,
+    pub errors: Vec<(I, ApiError)>,
+}
+
+impl<I, T> BatchResult<I, T> {
+    /// Whether every request of the batch succeeded
+    pub fn is_complete(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Calls `f` with chunks of at most 100 `inputs` and combines the results
+pub(crate) fn batch<T, F>(
+    inputs: &[&str],
+    mode: BatchMode,
+    mut f: F,
+) -> TwitchResult<BatchResult<Vec<String>, T>>
+where
+    F: FnMut(&[&str]) -> TwitchResult<Vec<T>>,
+{
+    let mut r = BatchResult {
+        ok: Vec::new(),
+        errors: Vec::new(),
+    };
+    for chunk in inputs.chunks(100) {
+        match f(chunk) {
+            Ok(mut data) => r.ok.append(&mut data),
+            Err(e) if mode == BatchMode::Collect => {
+                let chunk = chunk.iter().map(|s| s.to_string()).collect();
+                r.errors.push((chunk, e));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(r)
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        BatchMode,
+        Direction,
+        HelixResponse,
+        PageOpts,
+        ENDPOINTS,
+    };
+    use crate::{
+        mock,
+        new,
+        response::ApiError,
+    };
+
+    #[test]
+    fn parse_u64() {
+        assert_eq!(super::parse_u64("141981764").unwrap(), 141981764);
+        assert_eq!(super::parse_u64("18446744073709551615").unwrap(), u64::MAX);
+        for id in &["18446744073709551616", "", "-1", "v131643674"] {
+            match super::parse_u64(id) {
+                Err(ApiError::InvalidInput(_)) => (),
+                r => panic!("{}: {:?}", id, r),
+            }
+        }
+    }
+
+    #[test]
+    fn page_direction() {
+        let server = mock::serve(vec![
+            mock::response(200, &[], r#"{"data": [], "pagination": {}}"#),
+            mock::response(200, &[], r#"{"data": [], "pagination": {}}"#),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+        let back = PageOpts {
+            first: Some(20),
+            after: Some(String::from("eyJiIjpudWxs")),
+            direction: Direction::Backward,
+        };
+
+        super::streams::get(&c, &[], &[], &back).unwrap();
+        super::clips::by_broadcaster(&c, "1337", &back).unwrap();
+        // Get Channel Followers only documents `after`
+        match super::channels::followers(&c, "1337", None, &back) {
+            Err(ApiError::InvalidInput(_)) => (),
+            r => panic!("{:?}", r),
+        }
+
+        let requests = server.requests();
+        assert!(requests[0]
+            .starts_with("GET /streams?first=20&before=eyJiIjpudWxs "));
+        assert!(requests[1].starts_with(
+            "GET /clips?broadcaster_id=1337&first=20&before=eyJiIjpudWxs "
+        ));
+    }
+
+    #[test]
+    fn scope_matrix() {
+        // Endpoints which only need an app access token or none at all
+        let unscoped = [
+            super::bits::CHEERMOTES,
+            super::channels::GET_INFO,
+            super::chat::GET_USER_COLOR,
+            super::chat::GLOBAL_BADGES,
+            super::chat::CHANNEL_BADGES,
+            super::chat::CHANNEL_EMOTES,
+            super::chat::GLOBAL_EMOTES,
+            super::chat::GET_SETTINGS,
+            super::clips::GET,
+            super::entitlements::GET,
+            super::entitlements::UPDATE,
+            super::eventsub::conduits::GET,
+            super::eventsub::conduits::CREATE,
+            super::eventsub::conduits::UPDATE,
+            super::eventsub::conduits::DELETE,
+            super::eventsub::conduits::GET_SHARDS,
+            super::eventsub::conduits::UPDATE_SHARDS,
+            super::eventsub::subscriptions::CREATE,
+            super::eventsub::subscriptions::GET,
+            super::eventsub::subscriptions::DELETE,
+            super::extensions::TRANSACTIONS,
+            super::extensions::CONFIGURATIONS,
+            super::extensions::SET_CONFIGURATION,
+            super::extensions::SET_REQUIRED_CONFIGURATION,
+            super::games::GET,
+            super::schedule::ICALENDAR,
+            super::streams::GET,
+            super::streams::TAGS,
+            super::users::GET,
+            super::users::GET_SELF,
+            super::videos::GET,
+        ];
+        for endpoint in ENDPOINTS {
+            assert_eq!(
+                endpoint.scopes().is_empty(),
+                unscoped.contains(endpoint),
+                "{}",
+                endpoint.path
+            );
+        }
+
+        let matrix = super::scope_matrix();
+        assert_eq!(matrix.len(), ENDPOINTS.len());
+        assert!(matrix.contains(&(
+            "/goals",
+            &[super::scope::HelixScope::ChannelReadGoals][..]
+        )));
+    }
+
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    struct Item {
+        id: String,
+    }
+
+    fn parse(json: &str) -> HelixResponse<Item> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn single() {
+        let r = parse(r#"{"data":[{"id":"1"}]}"#);
+        assert_eq!(r.single().unwrap(), Item { id: "1".to_owned() });
+
+        match parse(r#"{"data":[]}"#).single() {
+            Err(ApiError::EmptyResponse(_)) => (),
+            r => panic!("{:?}", r),
+        }
+
+        match parse(r#"{"data":[{"id":"1"},{"id":"2"}]}"#).single() {
+            Err(ApiError::UnexpectedResultCount(2)) => (),
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn cursor() {
+        let r = parse(r#"{"data":[],"pagination":{"cursor":"eyJiIjpudWxs"}}"#);
+        assert_eq!(r.cursor(), Some("eyJiIjpudWxs"));
+        assert_eq!(parse(r#"{"data":[],"pagination":{}}"#).cursor(), None);
+        assert_eq!(parse(r#"{"data":[]}"#).cursor(), None);
+    }
+
+    #[test]
+    fn batch() {
+        let inputs: Vec<String> = (0..250).map(|i| i.to_string()).collect();
+        let inputs: Vec<&str> = inputs.iter().map(String::as_str).collect();
+        let f = |chunk: &[&str]| {
+            if chunk[0] == "100" {
+                Err(ApiError::empty_response())
+            }
+            else {
+                Ok(chunk.iter().map(|s| s.to_string()).collect())
+            }
+        };
+
+        let r = super::batch(&inputs, BatchMode::Collect, f).unwrap();
+        assert!(!r.is_complete());
+        assert_eq!(r.ok.len(), 150);
+        assert_eq!(r.errors.len(), 1);
+        assert_eq!(r.errors[0].0.len(), 100);
+        assert_eq!(r.errors[0].0[0], "100");
+
+        match super::batch(&inputs, BatchMode::FailFast, f) {
+            Err(ApiError::EmptyResponse(_)) => (),
+            r => panic!("{:?}", r),
+        }
+    }
+}
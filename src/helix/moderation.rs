@@ -0,0 +1,1026 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+use chrono::prelude::*;
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serialize,
+};
+use serde_json::json;
+
+use super::{
+    scope::HelixScope,
+    Endpoint,
+    HelixPage,
+    HelixResponse,
+    PageOpts,
+};
+
+use crate::{
+    response::{
+        ApiError,
+        Rejection,
+        TwitchResult,
+    },
+    TwitchClient,
+};
+
+pub const BLOCKED_TERMS: Endpoint = Endpoint::new("/moderation/blocked_terms")
+    .with_scopes(&[
+        HelixScope::ModeratorReadBlockedTerms,
+        HelixScope::ModeratorManageBlockedTerms,
+    ]);
+
+/// Gets the list of terms blocked in a specified broadcaster's chat
+///
+/// #### Authentication: `moderator:read:blocked_terms` or
+/// `moderator:manage:blocked_terms`
+pub fn blocked_terms(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    moderator_id: &str,
+    opts: &PageOpts,
+) -> TwitchResult<HelixPage<BlockedTerm>>
+{
+    let mut params = vec![
+        ("broadcaster_id", broadcaster_id.to_owned()),
+        ("moderator_id", moderator_id.to_owned()),
+    ];
+    params.extend(opts.forward_params()?);
+    let r =
+        c.helix_get::<HelixResponse<BlockedTerm>, _>(&BLOCKED_TERMS, &params)?;
+    Ok(HelixPage::from(r))
+}
+
+pub const ADD_BLOCKED_TERM: Endpoint =
+    Endpoint::new("/moderation/blocked_terms")
+        .with_scopes(&[HelixScope::ModeratorManageBlockedTerms]);
+
+/// Adds a term to a specified broadcaster's list of blocked terms
+///
+/// The term has to be between 2 and 500 characters long and may contain
+/// `*` as a wildcard, e.g. `bad*` also blocks `badger`. Adding a term that
+/// is already blocked returns the existing term.
+///
+/// #### Authentication: `moderator:manage:blocked_terms`
+pub fn add_blocked_term(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    moderator_id: &str,
+    text: &str,
+) -> TwitchResult<BlockedTerm>
+{
+    let len = text.chars().count();
+    if !(2..=500).contains(&len) {
+        return Err(ApiError::invalid_input(
+            "blocked terms must be between 2 and 500 characters long",
+        ));
+    }
+    let r = c.helix_post::<_, HelixResponse<BlockedTerm>, _>(
+        &ADD_BLOCKED_TERM,
+        &[
+            ("broadcaster_id", broadcaster_id),
+            ("moderator_id", moderator_id),
+        ],
+        &NewBlockedTerm { text },
+    )?;
+    r.single()
+}
+
+pub const REMOVE_BLOCKED_TERM: Endpoint =
+    Endpoint::new("/moderation/blocked_terms")
+        .with_scopes(&[HelixScope::ModeratorManageBlockedTerms]);
+
+/// Removes a term from a specified broadcaster's list of blocked terms
+///
+/// #### Authentication: `moderator:manage:blocked_terms`
+pub fn remove_blocked_term(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    moderator_id: &str,
+    term_id: &str,
+) -> TwitchResult<()>
+{
+    c.helix_delete::<(), _>(&REMOVE_BLOCKED_TERM, &[
+        ("broadcaster_id", broadcaster_id),
+        ("moderator_id", moderator_id),
+        ("id", term_id),
+    ])
+}
+
+pub const CHECK_AUTOMOD: Endpoint =
+    Endpoint::new("/moderation/enforcements/status")
+        .with_scopes(&[HelixScope::ModerationRead]);
+
+/// Checks whether AutoMod would flag the specified messages
+///
+/// `messages` are `(msg_id, msg_text)` pairs, where `msg_id` is any
+/// caller-defined id used to match the results to the messages. Twitch
+/// accepts at most 100 messages per request, larger slices are split into
+/// multiple requests.
+///
+/// #### Authentication: `moderation:read`
+pub fn check_automod(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    messages: &[(&str, &str)],
+) -> TwitchResult<Vec<AutoModStatus>>
+{
+    let mut statuses = Vec::with_capacity(messages.len());
+    for chunk in messages.chunks(100) {
+        let data = chunk
+            .iter()
+            .map(|&(msg_id, msg_text)| AutoModMessage { msg_id, msg_text })
+            .collect();
+        let mut r = c.helix_post::<_, HelixResponse<AutoModStatus>, _>(
+            &CHECK_AUTOMOD,
+            &[("broadcaster_id", broadcaster_id)],
+            &AutoModCheck { data },
+        )?;
+        statuses.append(&mut r.data);
+    }
+    Ok(statuses)
+}
+
+pub const MANAGE_HELD_MESSAGE: Endpoint =
+    Endpoint::new("/moderation/automod/message")
+        .with_scopes(&[HelixScope::ModeratorManageAutomod]);
+
+/// Allows or denies a message that was held for review by AutoMod
+///
+/// `user_id` is the moderator reviewing the message.
+///
+/// #### Authentication: `moderator:manage:automod`
+pub fn manage_held_message(
+    c: &TwitchClient,
+    user_id: &str,
+    msg_id: &str,
+    action: AutoModAction,
+) -> TwitchResult<()>
+{
+    c.helix_post::<_, (), _>(&MANAGE_HELD_MESSAGE, &(), &HeldMessage {
+        user_id,
+        msg_id,
+        action,
+    })
+}
+
+pub const SHIELD_MODE: Endpoint = Endpoint::new("/moderation/shield_mode")
+    .with_scopes(&[
+        HelixScope::ModeratorReadShieldMode,
+        HelixScope::ModeratorManageShieldMode,
+    ]);
+
+/// Gets the shield mode status of a specified broadcaster's chat
+///
+/// `moderator_id` has to match the user of the access token and be the
+/// broadcaster or one of their moderators.
+///
+/// #### Authentication: `moderator:read:shield_mode` or
+/// `moderator:manage:shield_mode`
+pub fn shield_mode(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    moderator_id: &str,
+) -> TwitchResult<ShieldModeStatus>
+{
+    let r =
+        c.helix_get::<HelixResponse<ShieldModeStatus>, _>(&SHIELD_MODE, &[
+            ("broadcaster_id", broadcaster_id),
+            ("moderator_id", moderator_id),
+        ])?;
+    r.single()
+}
+
+pub const UPDATE_SHIELD_MODE: Endpoint =
+    Endpoint::new("/moderation/shield_mode")
+        .with_scopes(&[HelixScope::ModeratorManageShieldMode]);
+
+/// Activates or deactivates shield mode in a specified broadcaster's chat
+///
+/// Activating shield mode while it is already active succeeds without
+/// changing anything, `last_activated_at` keeps the time it was originally
+/// activated at. The same goes for deactivating it.
+///
+/// #### Authentication: `moderator:manage:shield_mode`
+pub fn update_shield_mode(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    moderator_id: &str,
+    is_active: bool,
+) -> TwitchResult<ShieldModeStatus>
+{
+    let r = c.helix_put::<_, HelixResponse<ShieldModeStatus>, _>(
+        &UPDATE_SHIELD_MODE,
+        &[
+            ("broadcaster_id", broadcaster_id),
+            ("moderator_id", moderator_id),
+        ],
+        &ShieldModeUpdate { is_active },
+    )?;
+    r.single()
+}
+
+pub const BANNED_USERS: Endpoint = Endpoint::new("/moderation/banned")
+    .with_scopes(&[HelixScope::ModerationRead]);
+
+/// Gets the users banned or timed out in a specified broadcaster's chat
+///
+/// Pass up to 100 `user_ids` to only check whether those users are banned,
+/// or none to list all bans.
+///
+/// #### Authentication: `moderation:read`
+pub fn banned_users(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    user_ids: &[&str],
+    opts: &PageOpts,
+) -> TwitchResult<HelixPage<BannedUser>>
+{
+    if user_ids.len() > 100 {
+        return Err(ApiError::invalid_input(
+            "at most 100 banned users can be looked up at once",
+        ));
+    }
+    let mut params = vec![("broadcaster_id", broadcaster_id.to_owned())];
+    for id in user_ids {
+        params.push(("user_id", (*id).to_owned()));
+    }
+    params.extend(opts.forward_params()?);
+    let r =
+        c.helix_get::<HelixResponse<BannedUser>, _>(&BANNED_USERS, &params)?;
+    Ok(HelixPage::from(r))
+}
+
+pub const AUTOMOD_SETTINGS: Endpoint =
+    Endpoint::new("/moderation/automod/settings").with_scopes(&[
+        HelixScope::ModeratorReadAutomodSettings,
+        HelixScope::ModeratorManageAutomodSettings,
+    ]);
+
+/// Gets the AutoMod levels of a specified broadcaster's chat
+///
+/// `moderator_id` has to match the user of the access token and be the
+/// broadcaster or one of their moderators.
+///
+/// #### Authentication: `moderator:read:automod_settings` or
+/// `moderator:manage:automod_settings`
+pub fn automod_settings(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    moderator_id: &str,
+) -> TwitchResult<AutoModSettings>
+{
+    let r = c.helix_get::<HelixResponse<AutoModSettings>, _>(
+        &AUTOMOD_SETTINGS,
+        &[
+            ("broadcaster_id", broadcaster_id),
+            ("moderator_id", moderator_id),
+        ],
+    )?;
+    r.single()
+}
+
+pub const UPDATE_AUTOMOD_SETTINGS: Endpoint =
+    Endpoint::new("/moderation/automod/settings")
+        .with_scopes(&[HelixScope::ModeratorManageAutomodSettings]);
+
+/// Sets the AutoMod levels of a specified broadcaster's chat
+///
+/// Either set `overall_level`, which Twitch spreads over the categories, or
+/// the individual categories, but not both. Levels go from `0` (off) to `4`
+/// (most filtering). The update replaces the settings, categories left
+/// unset are turned off.
+///
+/// #### Authentication: `moderator:manage:automod_settings`
+pub fn update_automod_settings(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    moderator_id: &str,
+    levels: &AutoModLevels,
+) -> TwitchResult<AutoModSettings>
+{
+    levels.validate()?;
+    let r = c.helix_put::<_, HelixResponse<AutoModSettings>, _>(
+        &UPDATE_AUTOMOD_SETTINGS,
+        &[
+            ("broadcaster_id", broadcaster_id),
+            ("moderator_id", moderator_id),
+        ],
+        levels,
+    )?;
+    r.single()
+}
+
+pub const WARN_USER: Endpoint = Endpoint::new("/moderation/warnings")
+    .with_scopes(&[HelixScope::ModeratorManageWarnings]);
+
+/// Warns a user in a specified broadcaster's chat, who has to acknowledge
+/// the warning before chatting again
+///
+/// `moderator_id` has to match the user of the access token. `reason` has
+/// to be between 1 and 500 characters long.
+///
+/// Fails with `Rejection::AlreadyWarned` if the user still has to
+/// acknowledge a warning and with `Rejection::CannotWarnModerator` if the
+/// user is a moderator or the broadcaster. Other `409 Conflict`s, sent
+/// while another moderator changes the user's warnings, stay an
+/// `ApiError::TwitchError`, as they are transient and can be retried with
+/// `RetryPolicy::retry_on`.
+///
+/// #### Authentication: `moderator:manage:warnings`
+pub fn warn_user(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    moderator_id: &str,
+    user_id: &str,
+    reason: &str,
+) -> TwitchResult<Warning>
+{
+    let len = reason.chars().count();
+    if len == 0 || len > 500 {
+        return Err(ApiError::invalid_input(
+            "warning reasons have to be between 1 and 500 characters long",
+        ));
+    }
+    let r = c.helix_post::<_, HelixResponse<Warning>, _>(
+        &WARN_USER,
+        &[
+            ("broadcaster_id", broadcaster_id),
+            ("moderator_id", moderator_id),
+        ],
+        &NewWarning {
+            data: WarningData { user_id, reason },
+        },
+    );
+    match r {
+        Ok(r) => r.single(),
+        Err(ApiError::TwitchError(ref e))
+            if e.status == 409
+                && (e.message.contains("pending warning")
+                    || e.message.contains("already")) =>
+        {
+            Err(ApiError::Rejected(Rejection::AlreadyWarned))
+        }
+        Err(ApiError::TwitchError(ref e))
+            if e.status == 400 && e.message.contains("may not be warned") =>
+        {
+            Err(ApiError::Rejected(Rejection::CannotWarnModerator))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+pub const ADD_MODERATOR: Endpoint = Endpoint::new("/moderation/moderators")
+    .with_scopes(&[HelixScope::ChannelManageModerators]);
+
+/// Makes a user a moderator in a specified broadcaster's chat
+///
+/// Fails with `Rejection::AlreadyModerator` if the user already is a
+/// moderator and with `Rejection::UserIsVip` if the user is a VIP, who has
+/// to be removed with `channels::remove_vip` first.
+///
+/// #### Authentication: `channel:manage:moderators`
+pub fn add_moderator(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    user_id: &str,
+) -> TwitchResult<()>
+{
+    if broadcaster_id == user_id {
+        return Err(ApiError::invalid_input(
+            "the broadcaster can't be made a moderator",
+        ));
+    }
+    let r = c.helix_post::<_, (), _>(
+        &ADD_MODERATOR,
+        &[("broadcaster_id", broadcaster_id), ("user_id", user_id)],
+        &json!({}),
+    );
+    match r {
+        Err(ApiError::TwitchError(ref e))
+            if e.status == 400 && e.message.contains("already") =>
+        {
+            Err(ApiError::Rejected(Rejection::AlreadyModerator))
+        }
+        Err(ApiError::TwitchError(ref e)) if e.status == 422 => {
+            Err(ApiError::Rejected(Rejection::UserIsVip))
+        }
+        r => r,
+    }
+}
+
+pub const REMOVE_MODERATOR: Endpoint = Endpoint::new("/moderation/moderators")
+    .with_scopes(&[HelixScope::ChannelManageModerators]);
+
+/// Removes the moderator status of a user in a specified broadcaster's chat
+///
+/// #### Authentication: `channel:manage:moderators`
+pub fn remove_moderator(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    user_id: &str,
+) -> TwitchResult<()>
+{
+    if broadcaster_id == user_id {
+        return Err(ApiError::invalid_input(
+            "the broadcaster can't lose their moderator status",
+        ));
+    }
+    c.helix_delete::<(), _>(&REMOVE_MODERATOR, &[
+        ("broadcaster_id", broadcaster_id),
+        ("user_id", user_id),
+    ])
+}
+
+///////////////////////////////////////
+// BlockedTerms
+///////////////////////////////////////
+#[derive(Deserialize, Debug)]
+pub struct BlockedTerm {
+    pub id: String,
+    pub broadcaster_id: String,
+    pub moderator_id: String,
+    pub text: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// `None` if the term is blocked permanently
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Debug)]
+struct NewBlockedTerm<'a> {
+    text: &'a str,
+}
+
+///////////////////////////////////////
+// AutoMod
+///////////////////////////////////////
+#[derive(Deserialize, Debug)]
+pub struct AutoModStatus {
+    pub msg_id: String,
+    pub is_permitted: bool,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum AutoModAction {
+    Allow,
+    Deny,
+}
+
+#[derive(Serialize, Debug)]
+struct AutoModCheck<'a> {
+    data: Vec<AutoModMessage<'a>>,
+}
+
+#[derive(Serialize, Debug)]
+struct AutoModMessage<'a> {
+    msg_id: &'a str,
+    msg_text: &'a str,
+}
+
+#[derive(Serialize, Debug)]
+struct HeldMessage<'a> {
+    user_id: &'a str,
+    msg_id: &'a str,
+    action: AutoModAction,
+}
+
+/// The AutoMod levels of a chat, from `0` (off) to `4`
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AutoModSettings {
+    pub broadcaster_id: String,
+    pub moderator_id: String,
+    /// `None` if the categories were set individually
+    pub overall_level: Option<u8>,
+    pub aggression: u8,
+    pub bullying: u8,
+    pub disability: u8,
+    pub misogyny: u8,
+    pub race_ethnicity_or_religion: u8,
+    pub sex_based_terms: u8,
+    pub sexuality_sex_or_gender: u8,
+    pub swearing: u8,
+}
+
+/// AutoMod levels to set with `update_automod_settings`
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct AutoModLevels {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub overall_level: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aggression: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bullying: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disability: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub misogyny: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub race_ethnicity_or_religion: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sex_based_terms: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sexuality_sex_or_gender: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub swearing: Option<u8>,
+}
+
+impl AutoModLevels {
+    /// Sets all categories at once
+    pub fn overall(level: u8) -> AutoModLevels {
+        AutoModLevels {
+            overall_level: Some(level),
+            ..AutoModLevels::default()
+        }
+    }
+
+    fn validate(&self) -> TwitchResult<()> {
+        let categories = [
+            self.aggression,
+            self.bullying,
+            self.disability,
+            self.misogyny,
+            self.race_ethnicity_or_religion,
+            self.sex_based_terms,
+            self.sexuality_sex_or_gender,
+            self.swearing,
+        ];
+        let has_categories = categories.iter().any(Option::is_some);
+        if self.overall_level.is_some() == has_categories {
+            return Err(ApiError::invalid_input(
+                "set either the overall AutoMod level or the categories",
+            ));
+        }
+        if categories
+            .iter()
+            .chain(&[self.overall_level])
+            .flatten()
+            .any(|&level| level > 4)
+        {
+            return Err(ApiError::invalid_input(
+                "AutoMod levels have to be between 0 and 4",
+            ));
+        }
+        Ok(())
+    }
+}
+
+///////////////////////////////////////
+// ShieldMode
+///////////////////////////////////////
+#[derive(Deserialize, Debug)]
+pub struct ShieldModeStatus {
+    pub is_active: bool,
+    /// The moderator who last activated shield mode, empty if it never was
+    pub moderator_id: String,
+    pub moderator_login: String,
+    pub moderator_name: String,
+    /// `None` if shield mode was never activated
+    #[serde(deserialize_with = "empty_as_none")]
+    pub last_activated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Debug)]
+struct ShieldModeUpdate {
+    is_active: bool,
+}
+
+/// Twitch sends an empty string instead of `null` for unset timestamps
+fn empty_as_none<'de, D>(d: D) -> Result<Option<DateTime<Utc>>, D::Error>
+where D: Deserializer<'de> {
+    match Option::<String>::deserialize(d)? {
+        Some(ref s) if !s.is_empty() => {
+            s.parse().map(Some).map_err(serde::de::Error::custom)
+        }
+        _ => Ok(None),
+    }
+}
+
+///////////////////////////////////////
+// BannedUsers
+///////////////////////////////////////
+#[derive(Deserialize, Debug)]
+pub struct BannedUser {
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+    /// `None` for permanent bans, the end of the timeout otherwise
+    #[serde(deserialize_with = "empty_as_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub reason: String,
+    pub moderator_id: String,
+    pub moderator_login: String,
+    pub moderator_name: String,
+}
+
+impl BannedUser {
+    /// Whether the user is banned for good rather than timed out
+    pub fn is_permanent(&self) -> bool {
+        self.expires_at.is_none()
+    }
+}
+
+///////////////////////////////////////
+// WarnChatUser
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    pub broadcaster_id: String,
+    pub user_id: String,
+    pub moderator_id: String,
+    pub reason: String,
+}
+
+#[derive(Serialize, Debug)]
+struct NewWarning<'a> {
+    data: WarningData<'a>,
+}
+
+#[derive(Serialize, Debug)]
+struct WarningData<'a> {
+    user_id: &'a str,
+    reason: &'a str,
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        AutoModAction,
+        AutoModLevels,
+        AutoModStatus,
+        BlockedTerm,
+        HeldMessage,
+        ShieldModeStatus,
+    };
+    use crate::{
+        helix::{
+            HelixResponse,
+            PageOpts,
+        },
+        mock,
+        new,
+        response::{
+            ApiError,
+            Rejection,
+        },
+    };
+
+    #[test]
+    fn blocked_term() {
+        let r: HelixResponse<BlockedTerm> = serde_json::from_str(
+            r#"{
+              "data": [{
+                "broadcaster_id": "1234",
+                "moderator_id": "5678",
+                "id": "520e4d4e-0cda-49c7-821e-e5ef4f88c2f2",
+                "text": "A phrase I'm not fond of",
+                "created_at": "2021-09-29T19:45:37Z",
+                "updated_at": "2021-09-29T19:45:37Z",
+                "expires_at": null
+              }],
+              "pagination": {}
+            }"#,
+        )
+        .unwrap();
+
+        let term = r.single().unwrap();
+        assert_eq!(term.text, "A phrase I'm not fond of");
+        assert!(term.expires_at.is_none());
+    }
+
+    #[test]
+    fn add_blocked_term_length() {
+        let c = new(String::from("<clientid>"));
+
+        for text in &["a", &"a".repeat(501)] {
+            match super::add_blocked_term(&c, "1234", "5678", text) {
+                Err(ApiError::InvalidInput(_)) => (),
+                r => panic!("{:?}", r),
+            }
+        }
+    }
+
+    #[test]
+    fn automod_status() {
+        let r: HelixResponse<AutoModStatus> = serde_json::from_str(
+            r#"{"data": [
+              {"msg_id": "123", "is_permitted": true},
+              {"msg_id": "393", "is_permitted": false}
+            ]}"#,
+        )
+        .unwrap();
+
+        assert!(r.data[0].is_permitted);
+        assert!(!r.data[1].is_permitted);
+    }
+
+    #[test]
+    fn held_message() {
+        let body = serde_json::to_string(&HeldMessage {
+            user_id: "9327994",
+            msg_id: "836013710",
+            action: AutoModAction::Allow,
+        })
+        .unwrap();
+
+        assert_eq!(
+            body,
+            r#"{"user_id":"9327994","msg_id":"836013710","action":"ALLOW"}"#
+        );
+    }
+
+    #[test]
+    fn shield_mode_never_activated() {
+        let r: HelixResponse<ShieldModeStatus> = serde_json::from_str(
+            r#"{"data": [{
+              "is_active": false,
+              "moderator_id": "",
+              "moderator_login": "",
+              "moderator_name": "",
+              "last_activated_at": ""
+            }]}"#,
+        )
+        .unwrap();
+
+        let status = r.single().unwrap();
+        assert!(!status.is_active);
+        assert!(status.last_activated_at.is_none());
+    }
+
+    #[test]
+    fn update_shield_mode() {
+        let server = mock::serve(vec![mock::response(
+            200,
+            &[],
+            r#"{"data": [{
+              "is_active": true,
+              "moderator_id": "98765",
+              "moderator_login": "simplysimple",
+              "moderator_name": "SimplySimple",
+              "last_activated_at": "2022-07-26T17:16:03.123Z"
+            }]}"#,
+        )]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let status =
+            super::update_shield_mode(&c, "12345", "98765", true).unwrap();
+        assert!(status.is_active);
+        assert_eq!(
+            status.last_activated_at.unwrap().timestamp_millis(),
+            1_658_855_763_123
+        );
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with(
+            "PUT /moderation/shield_mode?broadcaster_id=12345&moderator_id=98765 "
+        ));
+        assert!(requests[0].ends_with(r#"{"is_active":true}"#));
+    }
+
+    #[test]
+    fn banned_users() {
+        let server = mock::serve(vec![mock::response(
+            200,
+            &[],
+            r#"{
+              "data": [{
+                "user_id": "423374343",
+                "user_login": "glowillig",
+                "user_name": "glowillig",
+                "expires_at": "2022-03-15T02:00:28Z",
+                "created_at": "2022-03-15T01:30:28Z",
+                "reason": "Does not like pineapple on pizza.",
+                "moderator_id": "141981764",
+                "moderator_login": "twitchdev",
+                "moderator_name": "TwitchDev"
+              }, {
+                "user_id": "424596340",
+                "user_login": "quotrok",
+                "user_name": "quotrok",
+                "expires_at": "",
+                "created_at": "2022-08-07T02:07:55Z",
+                "reason": "Inappropriate words.",
+                "moderator_id": "141981764",
+                "moderator_login": "twitchdev",
+                "moderator_name": "TwitchDev"
+              }],
+              "pagination": {"cursor": "eyJiIjpudWxsLCJhIjp7IkN1cnNvciI6IjEwMDQ3MzA2NDo4NjQwNjU3MToxSVZCVDFKMnY5M1BTOXh3d1E0dUdXMkJOMFcifX0"}
+            }"#,
+        )]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let page = super::banned_users(
+            &c,
+            "198704263",
+            &["423374343", "424596340"],
+            &PageOpts::default(),
+        )
+        .unwrap();
+        assert!(!page.data[0].is_permanent());
+        assert_eq!(
+            page.data[0].expires_at.unwrap().to_rfc3339(),
+            "2022-03-15T02:00:28+00:00"
+        );
+        assert!(page.data[1].is_permanent());
+        assert!(page.cursor.is_some());
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with(
+            "GET /moderation/banned?broadcaster_id=198704263&user_id=423374343&user_id=424596340 "
+        ));
+    }
+
+    #[test]
+    fn automod_settings() {
+        let settings = r#"{"data": [{
+          "broadcaster_id": "1234",
+          "moderator_id": "5678",
+          "overall_level": null,
+          "disability": 0,
+          "aggression": 3,
+          "sexuality_sex_or_gender": 0,
+          "misogyny": 0,
+          "bullying": 2,
+          "swearing": 0,
+          "race_ethnicity_or_religion": 0,
+          "sex_based_terms": 0
+        }]}"#;
+        let server = mock::serve(vec![
+            mock::response(200, &[], settings),
+            mock::response(200, &[], settings),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let s = super::automod_settings(&c, "1234", "5678").unwrap();
+        assert_eq!(s.overall_level, None);
+        assert_eq!(s.aggression, 3);
+
+        let levels = AutoModLevels {
+            aggression: Some(3),
+            bullying: Some(2),
+            ..AutoModLevels::default()
+        };
+        let s = super::update_automod_settings(&c, "1234", "5678", &levels)
+            .unwrap();
+        assert_eq!(s.bullying, 2);
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with(
+            "GET /moderation/automod/settings?broadcaster_id=1234&moderator_id=5678 "
+        ));
+        assert!(requests[1].starts_with("PUT /moderation/automod/settings?"));
+        assert!(requests[1].ends_with(r#"{"aggression":3,"bullying":2}"#));
+    }
+
+    #[test]
+    fn invalid_automod_levels() {
+        let c = new(String::from("<clientid>"));
+
+        for levels in &[
+            AutoModLevels::default(),
+            AutoModLevels::overall(5),
+            AutoModLevels {
+                swearing: Some(5),
+                ..AutoModLevels::default()
+            },
+            AutoModLevels {
+                swearing: Some(1),
+                ..AutoModLevels::overall(1)
+            },
+        ] {
+            match super::update_automod_settings(&c, "1234", "5678", levels) {
+                Err(ApiError::InvalidInput(_)) => (),
+                r => panic!("{:?}: {:?}", levels, r),
+            }
+        }
+    }
+
+    #[test]
+    fn warn_user() {
+        let server = mock::serve(vec![
+            mock::response(
+                200,
+                &[],
+                r#"{"data": [{
+                  "broadcaster_id": "404040",
+                  "user_id": "9876",
+                  "moderator_id": "404041",
+                  "reason": "stop doing that!"
+                }]}"#,
+            ),
+            mock::response(
+                409,
+                &[],
+                r#"{"error":"Conflict","status":409,"message":"The user already has a pending warning"}"#,
+            ),
+            mock::response(
+                400,
+                &[],
+                r#"{"error":"Bad Request","status":400,"message":"The user specified in the user_id field may not be warned"}"#,
+            ),
+            mock::response(
+                409,
+                &[],
+                r#"{"error":"Conflict","status":409,"message":"Another moderator is updating the warning state of this user"}"#,
+            ),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let w = super::warn_user(
+            &c,
+            "404040",
+            "404041",
+            "9876",
+            "stop doing that!",
+        )
+        .unwrap();
+        assert_eq!(w.user_id, "9876");
+        assert_eq!(w.reason, "stop doing that!");
+
+        for rejection in
+            &[Rejection::AlreadyWarned, Rejection::CannotWarnModerator]
+        {
+            match super::warn_user(&c, "404040", "404041", "9876", "again") {
+                Err(ApiError::Rejected(r)) => assert_eq!(r, *rejection),
+                r => panic!("{:?}", r),
+            }
+        }
+        match super::warn_user(&c, "404040", "404041", "9876", "again") {
+            Err(ApiError::TwitchError(e)) => assert_eq!(e.status, 409),
+            r => panic!("{:?}", r),
+        }
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with(
+            "POST /moderation/warnings?broadcaster_id=404040&moderator_id=404041 "
+        ));
+        assert!(requests[0].ends_with(
+            r#"{"data":{"user_id":"9876","reason":"stop doing that!"}}"#
+        ));
+    }
+
+    #[test]
+    fn warn_user_reason_length() {
+        let c = new(String::from("<clientid>"));
+
+        for reason in &[String::new(), "a".repeat(501)] {
+            match super::warn_user(&c, "404040", "404041", "9876", reason) {
+                Err(ApiError::InvalidInput(_)) => (),
+                r => panic!("{:?}", r),
+            }
+        }
+    }
+
+    #[test]
+    fn moderators() {
+        let server = mock::serve(vec![
+            mock::response(204, &[], ""),
+            mock::response(
+                400,
+                &[],
+                r#"{"error":"Bad Request","status":400,"message":"user is already a mod"}"#,
+            ),
+            mock::response(
+                422,
+                &[],
+                r#"{"error":"Unprocessable Entity","status":422,"message":"user is a vip"}"#,
+            ),
+            mock::response(204, &[], ""),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        super::add_moderator(&c, "123", "456").unwrap();
+        for rejection in &[Rejection::AlreadyModerator, Rejection::UserIsVip] {
+            match super::add_moderator(&c, "123", "456") {
+                Err(ApiError::Rejected(r)) => assert_eq!(r, *rejection),
+                r => panic!("{:?}", r),
+            }
+        }
+        super::remove_moderator(&c, "123", "456").unwrap();
+
+        match super::add_moderator(&c, "123", "123") {
+            Err(ApiError::InvalidInput(_)) => (),
+            r => panic!("{:?}", r),
+        }
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with(
+            "POST /moderation/moderators?broadcaster_id=123&user_id=456 "
+        ));
+        assert!(requests[3].starts_with(
+            "DELETE /moderation/moderators?broadcaster_id=123&user_id=456 "
+        ));
+    }
+}
@@ -0,0 +1,147 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+use chrono::prelude::*;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{
+    scope::HelixScope,
+    Endpoint,
+    HelixResponse,
+};
+
+use crate::{
+    response::{
+        ApiError,
+        Rejection,
+        TwitchResult,
+    },
+    TwitchClient,
+};
+
+pub const START: Endpoint =
+    Endpoint::new("/raids").with_scopes(&[HelixScope::ChannelManageRaids]);
+
+/// Starts a raid from one broadcaster's channel to another
+///
+/// The raid is only queued, the broadcaster still has to confirm it or wait
+/// for the countdown to finish.
+///
+/// Fails with `Rejection::AlreadyRaiding` if the broadcaster is already
+/// raiding and with `Rejection::RaidTargetUnavailable` if the target can't be
+/// raided.
+///
+/// #### Authentication: `channel:manage:raids`
+pub fn start(
+    c: &TwitchClient,
+    from_broadcaster_id: &str,
+    to_broadcaster_id: &str,
+) -> TwitchResult<Raid>
+{
+    let r = c.helix_post::<_, HelixResponse<Raid>, _>(
+        &START,
+        &[
+            ("from_broadcaster_id", from_broadcaster_id),
+            ("to_broadcaster_id", to_broadcaster_id),
+        ],
+        &json!({}),
+    );
+    match r {
+        Ok(r) => r.single(),
+        Err(ApiError::TwitchError(ref e)) if e.status == 409 => {
+            Err(ApiError::Rejected(Rejection::AlreadyRaiding))
+        }
+        Err(ApiError::TwitchError(ref e)) if e.status == 404 => {
+            Err(ApiError::Rejected(Rejection::RaidTargetUnavailable))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+pub const CANCEL: Endpoint =
+    Endpoint::new("/raids").with_scopes(&[HelixScope::ChannelManageRaids]);
+
+/// Cancels a pending raid of a specified broadcaster
+///
+/// #### Authentication: `channel:manage:raids`
+pub fn cancel(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+) -> TwitchResult<()>
+{
+    c.helix_delete::<(), _>(&CANCEL, &[("broadcaster_id", broadcaster_id)])
+}
+
+///////////////////////////////////////
+// StartRaid
+///////////////////////////////////////
+#[derive(Deserialize, Debug)]
+pub struct Raid {
+    pub created_at: DateTime<Utc>,
+    pub is_mature: bool,
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        mock,
+        new,
+        response::{
+            ApiError,
+            Rejection,
+        },
+    };
+
+    #[test]
+    fn start() {
+        let server = mock::serve(vec![mock::response(
+            200,
+            &[],
+            r#"{"data": [{
+              "created_at": "2022-02-18T07:20:50.52Z",
+              "is_mature": false
+            }]}"#,
+        )]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let raid = super::start(&c, "12345678", "87654321").unwrap();
+        assert!(!raid.is_mature);
+        assert!(server.requests()[0].starts_with(
+            "POST /raids?from_broadcaster_id=12345678&to_broadcaster_id=87654321 "
+        ));
+    }
+
+    #[test]
+    fn start_rejected() {
+        let server = mock::serve(vec![
+            mock::response(
+                409,
+                &[],
+                r#"{"error":"Conflict","status":409,
+                    "message":"The broadcaster is already raiding"}"#,
+            ),
+            mock::response(
+                404,
+                &[],
+                r#"{"error":"Not Found","status":404,
+                    "message":"The targeted channel was not found"}"#,
+            ),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        match super::start(&c, "12345678", "87654321") {
+            Err(ApiError::Rejected(Rejection::AlreadyRaiding)) => (),
+            r => panic!("{:?}", r),
+        }
+        match super::start(&c, "12345678", "87654321") {
+            Err(ApiError::Rejected(Rejection::RaidTargetUnavailable)) => (),
+            r => panic!("{:?}", r),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+use super::Endpoint;
+
+use crate::{
+    response::TwitchResult,
+    TwitchClient,
+};
+
+pub const ICALENDAR: Endpoint = Endpoint::new("/schedule/icalendar");
+
+/// Gets the stream schedule of a specified broadcaster as iCalendar text,
+/// ready to be imported into a calendar
+///
+/// This is the only schedule endpoint which is public, no token or client
+/// id is needed.
+///
+/// #### Authentication: None
+pub fn icalendar(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+) -> TwitchResult<String>
+{
+    c.helix_get_text(&ICALENDAR, &[("broadcaster_id", broadcaster_id)])
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        mock,
+        new,
+        response::ApiError,
+    };
+
+    const CALENDAR: &str = "BEGIN:VCALENDAR\r
+PRODID:-//twitch.tv//StreamSchedule//1.0\r
+VERSION:2.0\r
+CALSCALE:GREGORIAN\r
+REFRESH-INTERVAL;VALUE=DURATION:PT1H\r
+NAME:TwitchDev\r
+BEGIN:VEVENT\r
+UID:e4acc724-371f-402c-81ca-23ada79759d4\r
+DTSTART;TZID=/America/New_York:20210701T140000\r
+DTEND;TZID=/America/New_York:20210701T150000\r
+SUMMARY:TwitchDev Monthly Update // July 1, 2021\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+    #[test]
+    fn icalendar() {
+        let server = mock::serve(vec![
+            mock::response(200, &[("Content-Type", "text/calendar")], CALENDAR),
+            mock::response(
+                404,
+                &[],
+                r#"{"error":"Not Found","status":404,"message":"segments were not found"}"#,
+            ),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let cal = super::icalendar(&c, "141981764").unwrap();
+        assert_eq!(cal, CALENDAR);
+        match super::icalendar(&c, "141981764") {
+            Err(ApiError::TwitchError(e)) => assert_eq!(e.status, 404),
+            r => panic!("{:?}", r),
+        }
+
+        let requests = server.requests();
+        assert!(requests[0]
+            .starts_with("GET /schedule/icalendar?broadcaster_id=141981764 "));
+    }
+}
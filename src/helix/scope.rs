@@ -0,0 +1,221 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+//! The OAuth scopes of the Helix API
+//!
+//! https://dev.twitch.tv/docs/authentication/scopes
+
+use std::{
+    fmt,
+    str::FromStr,
+};
+
+macro_rules! scopes {
+    ($($name:ident => $scope:literal,)*) => {
+        /// A scope a user access token can be granted
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[non_exhaustive]
+        pub enum HelixScope {
+            $($name,)*
+        }
+
+        impl HelixScope {
+            /// Every scope known to this crate
+            pub const ALL: &'static [HelixScope] = &[$(HelixScope::$name,)*];
+
+            /// The scope as Twitch spells it, e.g. `channel:read:goals`
+            pub fn as_str(self) -> &'static str {
+                match self {
+                    $(HelixScope::$name => $scope,)*
+                }
+            }
+        }
+
+        impl FromStr for HelixScope {
+            type Err = UnknownScope;
+
+            fn from_str(s: &str) -> Result<HelixScope, UnknownScope> {
+                match s {
+                    $($scope => Ok(HelixScope::$name),)*
+                    _ => Err(UnknownScope(s.to_owned())),
+                }
+            }
+        }
+    };
+}
+
+scopes! {
+    AnalyticsReadExtensions => "analytics:read:extensions",
+    AnalyticsReadGames => "analytics:read:games",
+    BitsRead => "bits:read",
+    ChannelBot => "channel:bot",
+    ChannelEditCommercial => "channel:edit:commercial",
+    ChannelManageAds => "channel:manage:ads",
+    ChannelManageBroadcast => "channel:manage:broadcast",
+    ChannelManageExtensions => "channel:manage:extensions",
+    ChannelManageGuestStar => "channel:manage:guest_star",
+    ChannelManageModerators => "channel:manage:moderators",
+    ChannelManagePolls => "channel:manage:polls",
+    ChannelManagePredictions => "channel:manage:predictions",
+    ChannelManageRaids => "channel:manage:raids",
+    ChannelManageRedemptions => "channel:manage:redemptions",
+    ChannelManageSchedule => "channel:manage:schedule",
+    ChannelManageVideos => "channel:manage:videos",
+    ChannelManageVips => "channel:manage:vips",
+    ChannelModerate => "channel:moderate",
+    ChannelReadAds => "channel:read:ads",
+    ChannelReadCharity => "channel:read:charity",
+    ChannelReadEditors => "channel:read:editors",
+    ChannelReadGoals => "channel:read:goals",
+    ChannelReadGuestStar => "channel:read:guest_star",
+    ChannelReadHypeTrain => "channel:read:hype_train",
+    ChannelReadPolls => "channel:read:polls",
+    ChannelReadPredictions => "channel:read:predictions",
+    ChannelReadRedemptions => "channel:read:redemptions",
+    ChannelReadStreamKey => "channel:read:stream_key",
+    ChannelReadSubscriptions => "channel:read:subscriptions",
+    ChannelReadVips => "channel:read:vips",
+    ChatEdit => "chat:edit",
+    ChatRead => "chat:read",
+    ClipsEdit => "clips:edit",
+    ModerationRead => "moderation:read",
+    ModeratorManageAnnouncements => "moderator:manage:announcements",
+    ModeratorManageAutomod => "moderator:manage:automod",
+    ModeratorManageAutomodSettings => "moderator:manage:automod_settings",
+    ModeratorManageBannedUsers => "moderator:manage:banned_users",
+    ModeratorManageBlockedTerms => "moderator:manage:blocked_terms",
+    ModeratorManageChatMessages => "moderator:manage:chat_messages",
+    ModeratorManageChatSettings => "moderator:manage:chat_settings",
+    ModeratorManageGuestStar => "moderator:manage:guest_star",
+    ModeratorManageShieldMode => "moderator:manage:shield_mode",
+    ModeratorManageShoutouts => "moderator:manage:shoutouts",
+    ModeratorManageUnbanRequests => "moderator:manage:unban_requests",
+    ModeratorManageWarnings => "moderator:manage:warnings",
+    ModeratorReadAutomodSettings => "moderator:read:automod_settings",
+    ModeratorReadBlockedTerms => "moderator:read:blocked_terms",
+    ModeratorReadChatMessages => "moderator:read:chat_messages",
+    ModeratorReadChatSettings => "moderator:read:chat_settings",
+    ModeratorReadChatters => "moderator:read:chatters",
+    ModeratorReadFollowers => "moderator:read:followers",
+    ModeratorReadGuestStar => "moderator:read:guest_star",
+    ModeratorReadModerators => "moderator:read:moderators",
+    ModeratorReadShieldMode => "moderator:read:shield_mode",
+    ModeratorReadShoutouts => "moderator:read:shoutouts",
+    ModeratorReadSuspiciousUsers => "moderator:read:suspicious_users",
+    ModeratorReadUnbanRequests => "moderator:read:unban_requests",
+    ModeratorReadVips => "moderator:read:vips",
+    ModeratorReadWarnings => "moderator:read:warnings",
+    UserBot => "user:bot",
+    UserEdit => "user:edit",
+    UserEditBroadcast => "user:edit:broadcast",
+    UserManageBlockedUsers => "user:manage:blocked_users",
+    UserManageChatColor => "user:manage:chat_color",
+    UserManageWhispers => "user:manage:whispers",
+    UserReadBlockedUsers => "user:read:blocked_users",
+    UserReadBroadcast => "user:read:broadcast",
+    UserReadChat => "user:read:chat",
+    UserReadEmail => "user:read:email",
+    UserReadEmotes => "user:read:emotes",
+    UserReadFollows => "user:read:follows",
+    UserReadModeratedChannels => "user:read:moderated_channels",
+    UserReadSubscriptions => "user:read:subscriptions",
+    UserReadWhispers => "user:read:whispers",
+    UserWriteChat => "user:write:chat",
+    WhispersRead => "whispers:read",
+}
+
+impl HelixScope {
+    /// Finds the first scope named in an error message of Twitch
+    ///
+    /// Twitch phrases these differently across endpoints, e.g.
+    /// `Missing scope: channel:read:subscriptions` or
+    /// `requires the 'channel:manage:broadcast' scope`, so every word of the
+    /// message is tried.
+    pub fn from_message(message: &str) -> Option<HelixScope> {
+        message
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .map(|word| {
+                word.trim_matches(|c: char| {
+                    !c.is_ascii_alphanumeric() && c != '_'
+                })
+            })
+            .find_map(|word| word.parse().ok())
+    }
+}
+
+impl fmt::Display for HelixScope {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result
+    {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A scope this crate doesn't know about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownScope(pub String);
+
+impl fmt::Display for UnknownScope {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result
+    {
+        write!(f, "UnknownScope: {}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownScope {}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::HelixScope;
+
+    #[test]
+    fn round_trip() {
+        for scope in HelixScope::ALL {
+            assert_eq!(scope.as_str().parse(), Ok(*scope));
+        }
+        assert!("channel:read:everything".parse::<HelixScope>().is_err());
+    }
+
+    #[test]
+    fn from_message() {
+        for (message, scope) in &[
+            (
+                "Missing scope: channel:read:subscriptions",
+                Some(HelixScope::ChannelReadSubscriptions),
+            ),
+            (
+                "Missing required scope: moderator:read:chatters.",
+                Some(HelixScope::ModeratorReadChatters),
+            ),
+            (
+                "The request requires the 'channel:manage:broadcast' scope",
+                Some(HelixScope::ChannelManageBroadcast),
+            ),
+            (
+                "missing scopes: [\"user:read:email\"]",
+                Some(HelixScope::UserReadEmail),
+            ),
+            (
+                "The ID in broadcaster_id must match the user ID found in \
+                 the request's OAuth token.",
+                None,
+            ),
+            ("Missing scope: channel:read:everything", None),
+        ] {
+            assert_eq!(
+                HelixScope::from_message(message),
+                *scope,
+                "{}",
+                message
+            );
+        }
+    }
+}
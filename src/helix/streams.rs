@@ -0,0 +1,538 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+use std::{
+    collections::HashMap,
+    fmt,
+};
+
+use chrono::prelude::*;
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serialize,
+};
+
+use super::{
+    scope::HelixScope,
+    Direction,
+    Endpoint,
+    HelixPage,
+    HelixResponse,
+    PageOpts,
+};
+
+use crate::{
+    response::{
+        ApiError,
+        TwitchResult,
+    },
+    TwitchClient,
+};
+
+pub const GET: Endpoint = Endpoint::new("/streams");
+
+/// Gets a list of live streams, sorted by number of viewers, most popular
+/// first
+///
+/// Filters by up to 100 `user_ids` and 100 `game_ids`, without any filter all
+/// live streams are listed. Supports paging `Direction::Backward` with the
+/// cursor of any page.
+///
+/// #### Authentication: App or user access token
+pub fn get(
+    c: &TwitchClient,
+    user_ids: &[&str],
+    game_ids: &[&str],
+    opts: &PageOpts,
+) -> TwitchResult<HelixPage<HelixStream>>
+{
+    list(c, &StreamOpts {
+        user_ids: user_ids.iter().map(|id| id.to_string()).collect(),
+        game_ids: game_ids.iter().map(|id| id.to_string()).collect(),
+        first: opts.first,
+        after: opts.after.clone(),
+        direction: opts.direction,
+        ..StreamOpts::default()
+    })
+}
+
+/// Gets a list of live streams matching `opts`, sorted by number of
+/// viewers, most popular first
+///
+/// #### Authentication: App or user access token
+pub fn list(
+    c: &TwitchClient,
+    opts: &StreamOpts,
+) -> TwitchResult<HelixPage<HelixStream>>
+{
+    let r =
+        c.helix_get::<HelixResponse<HelixStream>, _>(&GET, &opts.params()?)?;
+    Ok(HelixPage::from(r))
+}
+
+pub const GET_STREAM_KEY: Endpoint = Endpoint::new("/streams/key")
+    .with_scopes(&[HelixScope::ChannelReadStreamKey]);
+
+/// Gets the primary stream key of a specified broadcaster
+///
+/// Unlike `kraken::channels::reset_stream_key` this doesn't invalidate the
+/// current key. Handle the returned key like a password, anyone knowing it
+/// can stream to the channel.
+///
+/// Tokens lacking the scope are rejected with an `ApiError::Forbidden`
+/// whose `missing_scope` is `HelixScope::ChannelReadStreamKey`.
+///
+/// #### Authentication: `channel:read:stream_key`
+pub fn get_stream_key(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+) -> TwitchResult<String>
+{
+    let r =
+        c.helix_get::<HelixResponse<StreamKey>, _>(&GET_STREAM_KEY, &[(
+            "broadcaster_id",
+            broadcaster_id,
+        )])?;
+    Ok(r.single()?.stream_key)
+}
+
+pub const TAGS: Endpoint = Endpoint::new("/streams/tags");
+
+/// Gets the tags of a specified broadcaster's stream
+///
+/// **Legacy:** Twitch replaced tag ids with the free-form `tags` of
+/// `HelixStream` and `ChannelInfo`, this is only for integrations which still
+/// rely on tag ids.
+///
+/// #### Authentication: App or user access token
+pub fn tags(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+) -> TwitchResult<Vec<StreamTag>>
+{
+    let r = c.helix_get::<HelixResponse<StreamTag>, _>(&TAGS, &[(
+        "broadcaster_id",
+        broadcaster_id,
+    )])?;
+    Ok(r.data)
+}
+
+pub const REPLACE_TAGS: Endpoint = Endpoint::new("/streams/tags")
+    .with_scopes(&[HelixScope::ChannelManageBroadcast]);
+
+/// Replaces the manually set tags of a specified broadcaster's stream with
+/// up to 5 `tag_ids`, or removes them if `tag_ids` is empty
+///
+/// Tags Twitch sets automatically, see `StreamTag::is_auto`, can't be set or
+/// removed, they are silently kept.
+///
+/// **Legacy:** see `tags`.
+///
+/// #### Authentication: `channel:manage:broadcast`
+pub fn replace_tags(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    tag_ids: &[&str],
+) -> TwitchResult<()>
+{
+    if tag_ids.len() > 5 {
+        return Err(ApiError::invalid_input(
+            "a stream can have at most 5 manually set tags",
+        ));
+    }
+    c.helix_put(
+        &REPLACE_TAGS,
+        &[("broadcaster_id", broadcaster_id)],
+        &TagIds { tag_ids },
+    )
+}
+
+///////////////////////////////////////
+// StreamOpts
+///////////////////////////////////////
+/// Filters of `list`, e.g.
+/// `StreamOpts::default().game_id("509670").language("en").first(50)`
+///
+/// Each filter takes up to 100 values, streams matching any of them are
+/// listed.
+#[derive(Debug, Default, Clone)]
+pub struct StreamOpts {
+    pub user_ids: Vec<String>,
+    pub user_logins: Vec<String>,
+    pub game_ids: Vec<String>,
+    /// ISO 639-1 codes like `en`, or `other`
+    pub languages: Vec<String>,
+    /// Maximum number of streams per page, at most 100
+    pub first: Option<u32>,
+    /// Cursor of the page to continue after, or before when paging
+    /// `Direction::Backward`
+    pub after: Option<String>,
+    pub direction: Direction,
+}
+
+impl StreamOpts {
+    pub fn user_id(
+        mut self,
+        user_id: &str,
+    ) -> StreamOpts
+    {
+        self.user_ids.push(user_id.to_owned());
+        self
+    }
+
+    pub fn user_login(
+        mut self,
+        user_login: &str,
+    ) -> StreamOpts
+    {
+        self.user_logins.push(user_login.to_owned());
+        self
+    }
+
+    pub fn game_id(
+        mut self,
+        game_id: &str,
+    ) -> StreamOpts
+    {
+        self.game_ids.push(game_id.to_owned());
+        self
+    }
+
+    pub fn language(
+        mut self,
+        language: &str,
+    ) -> StreamOpts
+    {
+        self.languages.push(language.to_owned());
+        self
+    }
+
+    pub fn first(
+        mut self,
+        first: u32,
+    ) -> StreamOpts
+    {
+        self.first = Some(first);
+        self
+    }
+
+    pub fn after(
+        mut self,
+        cursor: &str,
+    ) -> StreamOpts
+    {
+        self.after = Some(cursor.to_owned());
+        self.direction = Direction::Forward;
+        self
+    }
+
+    pub fn before(
+        mut self,
+        cursor: &str,
+    ) -> StreamOpts
+    {
+        self.after = Some(cursor.to_owned());
+        self.direction = Direction::Backward;
+        self
+    }
+
+    fn params(&self) -> TwitchResult<Vec<(&'static str, String)>> {
+        let filters = [
+            ("user_id", &self.user_ids),
+            ("user_login", &self.user_logins),
+            ("game_id", &self.game_ids),
+            ("language", &self.languages),
+        ];
+        let mut params = Vec::new();
+        for (name, values) in filters.iter() {
+            if values.len() > 100 {
+                return Err(ApiError::invalid_input(
+                    "streams can be filtered by at most 100 values each",
+                ));
+            }
+            params.extend(values.iter().map(|v| (*name, v.clone())));
+        }
+        if self.first.is_some_and(|first| first == 0 || first > 100) {
+            return Err(ApiError::invalid_input(
+                "first has to be between 1 and 100",
+            ));
+        }
+        params.extend(
+            PageOpts {
+                first: self.first,
+                after: self.after.clone(),
+                direction: self.direction,
+            }
+            .params(),
+        );
+        Ok(params)
+    }
+}
+
+///////////////////////////////////////
+// GetStreams
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone)]
+pub struct HelixStream {
+    pub id: String,
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+    pub game_id: String,
+    pub game_name: String,
+    #[serde(rename = "type")]
+    pub _type: StreamType,
+    pub title: String,
+    pub viewer_count: i64,
+    pub started_at: DateTime<Utc>,
+    pub language: String,
+    /// Contains `{width}` and `{height}` placeholders
+    pub thumbnail_url: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub is_mature: bool,
+}
+
+impl HelixStream {
+    pub fn is_live(&self) -> bool {
+        self._type.is_live()
+    }
+}
+
+/// The `type` of a stream
+///
+/// Helix only knows `live`, Kraken also reports reruns and playlists. An
+/// empty string, which Twitch sends for streams which went offline, or any
+/// other value is `Unknown`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum StreamType {
+    Live,
+    Rerun,
+    Playlist,
+    #[default]
+    Unknown,
+}
+
+impl StreamType {
+    pub fn is_live(self) -> bool {
+        self == StreamType::Live
+    }
+}
+
+impl<'de> Deserialize<'de> for StreamType {
+    fn deserialize<D>(d: D) -> Result<StreamType, D::Error>
+    where D: Deserializer<'de> {
+        Ok(match Option::<String>::deserialize(d)?.as_deref() {
+            Some("live") => StreamType::Live,
+            Some("rerun") => StreamType::Rerun,
+            Some("playlist") => StreamType::Playlist,
+            _ => StreamType::Unknown,
+        })
+    }
+}
+
+///////////////////////////////////////
+// GetStreamKey
+///////////////////////////////////////
+#[derive(Deserialize)]
+struct StreamKey {
+    stream_key: String,
+}
+
+impl fmt::Debug for StreamKey {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result
+    {
+        f.debug_struct("StreamKey")
+            .field("stream_key", &"<redacted>")
+            .finish()
+    }
+}
+
+///////////////////////////////////////
+// GetStreamTags
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone)]
+pub struct StreamTag {
+    pub tag_id: String,
+    /// Whether Twitch set the tag automatically, e.g. based on the game,
+    /// rather than the broadcaster
+    pub is_auto: bool,
+    /// Names of the tag by locale, e.g. `en-us`
+    pub localization_names: HashMap<String, String>,
+    /// Descriptions of the tag by locale, e.g. `en-us`
+    pub localization_descriptions: HashMap<String, String>,
+}
+
+#[derive(Serialize, Debug)]
+struct TagIds<'a> {
+    tag_ids: &'a [&'a str],
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        HelixStream,
+        StreamKey,
+        StreamOpts,
+        StreamType,
+    };
+    use crate::{
+        helix::HelixResponse,
+        mock,
+        new,
+        response::ApiError,
+    };
+
+    #[test]
+    fn stream_type() {
+        for (json, expected) in &[
+            (r#""live""#, StreamType::Live),
+            (r#""rerun""#, StreamType::Rerun),
+            (r#""playlist""#, StreamType::Playlist),
+            (r#""""#, StreamType::Unknown),
+            (r#""premiere""#, StreamType::Unknown),
+            ("null", StreamType::Unknown),
+        ] {
+            let t: StreamType = serde_json::from_str(json).unwrap();
+            assert_eq!(t, *expected, "{}", json);
+            assert_eq!(t.is_live(), *expected == StreamType::Live);
+        }
+    }
+
+    #[test]
+    fn streams() {
+        let r: HelixResponse<HelixStream> = serde_json::from_str(
+            r#"{
+              "data": [{
+                "id": "40952121085",
+                "user_id": "101051819",
+                "user_login": "afro",
+                "user_name": "Afro",
+                "game_id": "32982",
+                "game_name": "Grand Theft Auto V",
+                "type": "live",
+                "title": "Jacob: Digital Den Laptops & Routers",
+                "tags": ["English"],
+                "viewer_count": 1490,
+                "started_at": "2021-03-10T03:18:11Z",
+                "language": "en",
+                "thumbnail_url": "https://static-cdn.jtvnw.net/previews-ttv/live_user_afro-{width}x{height}.jpg",
+                "tag_ids": [],
+                "is_mature": false
+              }],
+              "pagination": {"cursor": "eyJiIjp7IkN1cnNvciI6ImV5SnpJam96"}
+            }"#,
+        )
+        .unwrap();
+
+        let stream = r.single().unwrap();
+        assert!(stream.is_live());
+        assert_eq!(stream.user_login, "afro");
+        assert_eq!(stream.viewer_count, 1490);
+    }
+
+    #[test]
+    fn stream_opts() {
+        let server = mock::serve(vec![mock::response(
+            200,
+            &[],
+            r#"{"data": [], "pagination": {}}"#,
+        )]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let opts = StreamOpts::default()
+            .game_id("509670")
+            .language("en")
+            .language("de")
+            .first(50);
+        assert!(super::list(&c, &opts).unwrap().data.is_empty());
+
+        let too_many = StreamOpts {
+            user_logins: vec![String::from("twitchdev"); 101],
+            ..StreamOpts::default()
+        };
+        for opts in &[too_many, opts.clone().first(0), opts.first(101)] {
+            match super::list(&c, opts) {
+                Err(ApiError::InvalidInput(_)) => (),
+                r => panic!("{:?}", r),
+            }
+        }
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with(
+            "GET /streams?game_id=509670&language=en&language=de&first=50 "
+        ));
+    }
+
+    #[test]
+    fn stream_key() {
+        let r: HelixResponse<StreamKey> = serde_json::from_str(
+            r#"{"data": [{"stream_key": "live_44322889_a34ub37c8ajv98a0"}]}"#,
+        )
+        .unwrap();
+
+        let debug = format!("{:?}", r);
+        assert!(!debug.contains("live_44322889"));
+        assert_eq!(
+            r.single().unwrap().stream_key,
+            "live_44322889_a34ub37c8ajv98a0"
+        );
+    }
+
+    #[test]
+    fn tags() {
+        let server = mock::serve(vec![
+            mock::response(
+                200,
+                &[],
+                r#"{"data": [{
+                  "tag_id": "6ea6bca4-4712-4ab9-a906-e3336a9d8039",
+                  "is_auto": true,
+                  "localization_names": {"en-us": "English", "de-de": "Englisch"},
+                  "localization_descriptions": {"en-us": "For streams in English"}
+                }, {
+                  "tag_id": "621fb5bf-5498-4d8f-b4ac-db4d40d401bf",
+                  "is_auto": false,
+                  "localization_names": {"en-us": "1 Credit Clear"},
+                  "localization_descriptions": {"en-us": "For when a character is beaten without continuing"}
+                }]}"#,
+            ),
+            mock::response(204, &[], ""),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let tags = super::tags(&c, "257788195").unwrap();
+        assert!(tags[0].is_auto);
+        assert_eq!(tags[0].localization_names["de-de"], "Englisch");
+        assert!(!tags[1].is_auto);
+
+        super::replace_tags(&c, "257788195", &[&tags[1].tag_id]).unwrap();
+
+        let requests = server.requests();
+        assert!(requests[0]
+            .starts_with("GET /streams/tags?broadcaster_id=257788195 "));
+        assert!(requests[1]
+            .starts_with("PUT /streams/tags?broadcaster_id=257788195 "));
+        assert!(requests[1].ends_with(
+            r#"{"tag_ids":["621fb5bf-5498-4d8f-b4ac-db4d40d401bf"]}"#
+        ));
+    }
+
+    #[test]
+    fn too_many_tags() {
+        let c = new(String::from("<clientid>"));
+        match super::replace_tags(&c, "257788195", &["tag"; 6]) {
+            Err(ApiError::InvalidInput(_)) => (),
+            r => panic!("{:?}", r),
+        }
+    }
+}
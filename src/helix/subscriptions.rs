@@ -0,0 +1,282 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+use std::fmt;
+
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serialize,
+    Serializer,
+};
+
+use super::{
+    scope::HelixScope,
+    Endpoint,
+    HelixPage,
+    HelixResponse,
+    PageOpts,
+};
+
+use crate::{
+    response::{
+        ApiError,
+        TwitchResult,
+    },
+    TwitchClient,
+};
+
+pub const GET: Endpoint = Endpoint::new("/subscriptions")
+    .with_scopes(&[HelixScope::ChannelReadSubscriptions]);
+
+/// Gets the subscribers of a specified broadcaster
+///
+/// Pass up to 100 `user_ids` to only check whether those users subscribed,
+/// or none to list all subscribers. `HelixPage::total` is the number of
+/// subscribers across all pages.
+///
+/// #### Authentication: `channel:read:subscriptions`
+pub fn get(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    user_ids: &[&str],
+    opts: &PageOpts,
+) -> TwitchResult<HelixPage<Subscription>>
+{
+    if user_ids.len() > 100 {
+        return Err(ApiError::invalid_input(
+            "at most 100 subscriptions can be looked up at once",
+        ));
+    }
+    let mut params = vec![("broadcaster_id", broadcaster_id.to_owned())];
+    params.extend(user_ids.iter().map(|id| ("user_id", id.to_string())));
+    params.extend(opts.forward_params()?);
+    let r = c.helix_get::<HelixResponse<Subscription>, _>(&GET, &params)?;
+    Ok(HelixPage::from(r))
+}
+
+pub const CHECK: Endpoint = Endpoint::new("/subscriptions/user")
+    .with_scopes(&[HelixScope::UserReadSubscriptions]);
+
+/// Checks whether a specified user subscribed to a specified broadcaster
+///
+/// `user_id` has to match the user of the access token. Returns `None` if
+/// the user didn't subscribe.
+///
+/// #### Authentication: `user:read:subscriptions`
+pub fn check(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    user_id: &str,
+) -> TwitchResult<Option<UserSubscription>>
+{
+    let r = c.helix_get::<HelixResponse<UserSubscription>, _>(&CHECK, &[
+        ("broadcaster_id", broadcaster_id),
+        ("user_id", user_id),
+    ]);
+    match r {
+        Ok(r) => Ok(r.data.into_iter().next()),
+        Err(ApiError::TwitchError(ref e)) if e.status == 404 => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+///////////////////////////////////////
+// SubTier
+///////////////////////////////////////
+/// The tier of a subscription, sent as `"1000"`, `"2000"` and `"3000"`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SubTier {
+    /// Prime subscriptions are tier 1 as well
+    Tier1,
+    Tier2,
+    Tier3,
+}
+
+impl SubTier {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SubTier::Tier1 => "1000",
+            SubTier::Tier2 => "2000",
+            SubTier::Tier3 => "3000",
+        }
+    }
+
+    /// What the subscription counts towards subscription goals
+    pub fn points(self) -> u32 {
+        match self {
+            SubTier::Tier1 => 1,
+            SubTier::Tier2 => 2,
+            SubTier::Tier3 => 6,
+        }
+    }
+}
+
+impl fmt::Display for SubTier {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result
+    {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for SubTier {
+    fn serialize<S>(
+        &self,
+        s: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        s.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for SubTier {
+    fn deserialize<D>(d: D) -> Result<SubTier, D::Error>
+    where D: Deserializer<'de> {
+        match String::deserialize(d)?.as_str() {
+            "1000" => Ok(SubTier::Tier1),
+            "2000" => Ok(SubTier::Tier2),
+            "3000" => Ok(SubTier::Tier3),
+            other => Err(serde::de::Error::invalid_value(
+                serde::de::Unexpected::Str(other),
+                &"1000, 2000 or 3000",
+            )),
+        }
+    }
+}
+
+///////////////////////////////////////
+// GetBroadcasterSubscriptions
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone)]
+pub struct Subscription {
+    pub broadcaster_id: String,
+    pub broadcaster_login: String,
+    pub broadcaster_name: String,
+    /// The gifter, empty if the subscription wasn't gifted
+    pub gifter_id: String,
+    pub gifter_login: String,
+    pub gifter_name: String,
+    pub is_gift: bool,
+    pub plan_name: String,
+    pub tier: SubTier,
+    pub user_id: String,
+    pub user_name: String,
+    pub user_login: String,
+}
+
+///////////////////////////////////////
+// CheckUserSubscription
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone)]
+pub struct UserSubscription {
+    pub broadcaster_id: String,
+    pub broadcaster_login: String,
+    pub broadcaster_name: String,
+    pub is_gift: bool,
+    /// `None` if the subscription wasn't gifted
+    pub gifter_login: Option<String>,
+    pub gifter_name: Option<String>,
+    pub tier: SubTier,
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::SubTier;
+    use crate::{
+        helix::PageOpts,
+        mock,
+        new,
+    };
+
+    #[test]
+    fn sub_tier() {
+        for (tier, json, points) in &[
+            (SubTier::Tier1, r#""1000""#, 1),
+            (SubTier::Tier2, r#""2000""#, 2),
+            (SubTier::Tier3, r#""3000""#, 6),
+        ] {
+            assert_eq!(serde_json::to_string(tier).unwrap(), *json);
+            assert_eq!(serde_json::from_str::<SubTier>(json).unwrap(), *tier);
+            assert_eq!(tier.points(), *points);
+        }
+        assert!(serde_json::from_str::<SubTier>(r#""4000""#).is_err());
+        assert!(serde_json::from_str::<SubTier>("1000").is_err());
+    }
+
+    #[test]
+    fn get() {
+        let server = mock::serve(vec![mock::response(
+            200,
+            &[],
+            r#"{
+              "data": [{
+                "broadcaster_id": "141981764",
+                "broadcaster_login": "twitchdev",
+                "broadcaster_name": "TwitchDev",
+                "gifter_id": "12826",
+                "gifter_login": "twitch",
+                "gifter_name": "Twitch",
+                "is_gift": true,
+                "tier": "1000",
+                "plan_name": "Channel Subscription (twitchdev)",
+                "user_id": "527115020",
+                "user_name": "twitchgaming",
+                "user_login": "twitchgaming"
+              }],
+              "pagination": {"cursor": "xxxx"},
+              "total": 13,
+              "points": 13
+            }"#,
+        )]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let page =
+            super::get(&c, "141981764", &[], &PageOpts::default()).unwrap();
+        assert_eq!(page.data[0].tier, SubTier::Tier1);
+        assert!(page.data[0].is_gift);
+        assert_eq!(page.total, Some(13));
+    }
+
+    #[test]
+    fn check() {
+        let server = mock::serve(vec![
+            mock::response(
+                200,
+                &[],
+                r#"{"data": [{
+                  "broadcaster_id": "141981764",
+                  "broadcaster_name": "TwitchDev",
+                  "broadcaster_login": "twitchdev",
+                  "is_gift": false,
+                  "tier": "3000"
+                }]}"#,
+            ),
+            mock::response(
+                404,
+                &[],
+                r#"{"error":"Not Found","status":404,"message":"twitchgaming has no subscription to twitchdev"}"#,
+            ),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let sub = super::check(&c, "141981764", "527115020").unwrap().unwrap();
+        assert_eq!(sub.tier, SubTier::Tier3);
+        assert_eq!(sub.gifter_login, None);
+        assert!(super::check(&c, "141981764", "12826").unwrap().is_none());
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with(
+            "GET /subscriptions/user?broadcaster_id=141981764&user_id=527115020 "
+        ));
+    }
+}
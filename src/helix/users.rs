@@ -0,0 +1,496 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+use chrono::prelude::*;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{
+    scope::HelixScope,
+    BatchMode,
+    BatchResult,
+    Endpoint,
+    HelixPage,
+    HelixResponse,
+    PageOpts,
+};
+
+use crate::{
+    response::{
+        ApiError,
+        TwitchResult,
+    },
+    util::normalize_login,
+    TwitchClient,
+};
+
+pub const GET: Endpoint = Endpoint::new("/users");
+
+/// Gets the users with the specified ids or logins
+///
+/// At most 100 ids and logins can be looked up at once. Unknown users are
+/// left out of the result.
+///
+/// #### Authentication: App or user access token
+pub fn get(
+    c: &TwitchClient,
+    ids: &[&str],
+    logins: &[&str],
+) -> TwitchResult<Vec<HelixUser>>
+{
+    if ids.len() + logins.len() > 100 {
+        return Err(ApiError::invalid_input(
+            "at most 100 users can be looked up at once",
+        ));
+    }
+    let mut params: Vec<(&str, &str)> = Vec::new();
+    params.extend(ids.iter().map(|id| ("id", *id)));
+    params.extend(logins.iter().map(|login| ("login", *login)));
+    let r = c.helix_get::<HelixResponse<HelixUser>, _>(&GET, &params)?;
+    Ok(r.data)
+}
+
+pub const GET_SELF: Endpoint = Endpoint::new("/users");
+
+/// Gets the user of the access token
+///
+/// `HelixUser::email` is only set if the token has the `user:read:email`
+/// scope, see `Token::has_email_scope`.
+///
+/// #### Authentication: User access token, optionally `user:read:email`
+pub fn get_self(c: &TwitchClient) -> TwitchResult<HelixUser> {
+    let r = c.helix_get::<HelixResponse<HelixUser>, _>(&GET_SELF, &())?;
+    r.single()
+}
+
+/// Gets the users with the specified logins, any number at once
+///
+/// Each login is normalized first, see `normalize_login`. Sends one
+/// request per 100 logins. With `BatchMode::Collect` invalid logins and the
+/// logins of failed requests are returned in `BatchResult::errors`, with
+/// `BatchMode::FailFast` an invalid login fails with
+/// `ApiError::InvalidInput` before any request is sent.
+///
+/// #### Authentication: App or user access token
+pub fn get_by_logins(
+    c: &TwitchClient,
+    logins: &[&str],
+    mode: BatchMode,
+) -> TwitchResult<BatchResult<Vec<String>, HelixUser>>
+{
+    let mut valid = Vec::with_capacity(logins.len());
+    let mut invalid = Vec::new();
+    for login in logins {
+        match normalize_login(login) {
+            Ok(login) => valid.push(login),
+            Err(e) if mode == BatchMode::Collect => {
+                invalid.push((vec![login.to_string()], ApiError::from(e)))
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    let valid: Vec<&str> = valid.iter().map(String::as_str).collect();
+    let mut r = super::batch(&valid, mode, |chunk| get(c, &[], chunk))?;
+    invalid.append(&mut r.errors);
+    r.errors = invalid;
+    Ok(r)
+}
+
+pub const BLOCKS: Endpoint = Endpoint::new("/users/blocks")
+    .with_scopes(&[HelixScope::UserReadBlockedUsers]);
+
+/// Gets the users a specified broadcaster blocked
+///
+/// `broadcaster_id` has to match the user of the access token.
+///
+/// #### Authentication: `user:read:blocked_users`
+pub fn blocks(
+    c: &TwitchClient,
+    broadcaster_id: &str,
+    opts: &PageOpts,
+) -> TwitchResult<HelixPage<BlockedUser>>
+{
+    let mut params = vec![("broadcaster_id", broadcaster_id.to_owned())];
+    params.extend(opts.forward_params()?);
+    let r = c.helix_get::<HelixResponse<BlockedUser>, _>(&BLOCKS, &params)?;
+    Ok(HelixPage::from(r))
+}
+
+pub const BLOCK_USER: Endpoint = Endpoint::new("/users/blocks")
+    .with_scopes(&[HelixScope::UserManageBlockedUsers]);
+
+/// Blocks a user for the user of the access token
+///
+/// `source_context` and `reason` are optional details Twitch records along
+/// with the block.
+///
+/// #### Authentication: `user:manage:blocked_users`
+pub fn block_user(
+    c: &TwitchClient,
+    target_id: &str,
+    source_context: Option<SourceContext>,
+    reason: Option<BlockReason>,
+) -> TwitchResult<()>
+{
+    let mut params = vec![("target_user_id", target_id)];
+    if let Some(source_context) = source_context {
+        params.push(("source_context", source_context.as_str()));
+    }
+    if let Some(reason) = reason {
+        params.push(("reason", reason.as_str()));
+    }
+    c.helix_put::<_, (), _>(&BLOCK_USER, &params, &json!({}))
+}
+
+pub const UNBLOCK_USER: Endpoint = Endpoint::new("/users/blocks")
+    .with_scopes(&[HelixScope::UserManageBlockedUsers]);
+
+/// Unblocks a user for the user of the access token
+///
+/// #### Authentication: `user:manage:blocked_users`
+pub fn unblock_user(
+    c: &TwitchClient,
+    target_id: &str,
+) -> TwitchResult<()>
+{
+    c.helix_delete::<(), _>(&UNBLOCK_USER, &[("target_user_id", target_id)])
+}
+
+///////////////////////////////////////
+// GetUsers
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone)]
+pub struct HelixUser {
+    pub id: String,
+    pub login: String,
+    pub display_name: String,
+    /// `staff`, `admin`, `global_mod` or empty
+    #[serde(rename = "type")]
+    pub _type: String,
+    /// `partner`, `affiliate` or empty
+    pub broadcaster_type: String,
+    pub description: String,
+    pub profile_image_url: String,
+    pub offline_image_url: String,
+    /// Only set by `get_self` with the `user:read:email` scope
+    #[serde(default)]
+    pub email: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl HelixUser {
+    /// `profile_image_url` at `width`x`height`, e.g. `70`x`70` for chat
+    ///
+    /// Twitch serves profile images at 28, 50, 70, 150, 300 and 600 pixels.
+    pub fn profile_image(
+        &self,
+        width: u32,
+        height: u32,
+    ) -> String
+    {
+        with_size(&self.profile_image_url, width, height)
+    }
+
+    /// `offline_image_url` at `width`x`height`, e.g. `1280`x`720`
+    pub fn offline_image(
+        &self,
+        width: u32,
+        height: u32,
+    ) -> String
+    {
+        with_size(&self.offline_image_url, width, height)
+    }
+}
+
+/// Replaces the `-300x300` in front of an image URL's extension, URLs
+/// without such a segment are returned unchanged
+fn with_size(
+    url: &str,
+    width: u32,
+    height: u32,
+) -> String
+{
+    let is_num =
+        |p: &str| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit());
+    let is_size = |s: &str| match s.split_once('x') {
+        Some((w, h)) => is_num(w) && is_num(h),
+        None => false,
+    };
+    let (stem, ext) = match url.rfind('.') {
+        Some(dot) if !url[dot..].contains('/') => url.split_at(dot),
+        _ => (url, ""),
+    };
+    match stem.rfind('-') {
+        Some(dash) if is_size(&stem[dash + 1..]) => {
+            format!("{}-{}x{}{}", &stem[..dash], width, height, ext)
+        }
+        _ => url.to_owned(),
+    }
+}
+
+///////////////////////////////////////
+// GetUserBlockList
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BlockedUser {
+    pub user_id: String,
+    pub user_login: String,
+    pub display_name: String,
+}
+
+/// Where the blocked user bothered the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceContext {
+    Chat,
+    Whisper,
+}
+
+impl SourceContext {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SourceContext::Chat => "chat",
+            SourceContext::Whisper => "whisper",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockReason {
+    Spam,
+    Harassment,
+    Other,
+}
+
+impl BlockReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BlockReason::Spam => "spam",
+            BlockReason::Harassment => "harassment",
+            BlockReason::Other => "other",
+        }
+    }
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        BlockReason,
+        HelixUser,
+        SourceContext,
+    };
+    use crate::{
+        helix::{
+            BatchMode,
+            HelixResponse,
+            PageOpts,
+        },
+        mock,
+        new,
+        response::ApiError,
+    };
+
+    #[test]
+    fn users() {
+        let r: HelixResponse<HelixUser> = serde_json::from_str(
+            r#"{
+              "data": [{
+                "id": "141981764",
+                "login": "twitchdev",
+                "display_name": "TwitchDev",
+                "type": "",
+                "broadcaster_type": "partner",
+                "description": "Supporting third-party developers building Twitch integrations",
+                "profile_image_url": "https://static-cdn.jtvnw.net/jtv_user_pictures/8a6381c7-d0c0-4576-b179-38bd5ce1d6af-profile_image-300x300.png",
+                "offline_image_url": "https://static-cdn.jtvnw.net/jtv_user_pictures/3f13ab61-ec78-4fe6-8481-8682cb3b0ac2-channel_offline_image-1920x1080.png",
+                "view_count": 5980557,
+                "created_at": "2016-12-14T20:32:28Z"
+              }]
+            }"#,
+        )
+        .unwrap();
+
+        let user = r.single().unwrap();
+        assert_eq!(user.login, "twitchdev");
+        assert_eq!(user.broadcaster_type, "partner");
+        assert!(user.email.is_none());
+    }
+
+    #[test]
+    fn get_self_with_email() {
+        let server = mock::serve(vec![mock::response(
+            200,
+            &[],
+            r#"{"data": [{
+              "id": "141981764",
+              "login": "twitchdev",
+              "display_name": "TwitchDev",
+              "type": "",
+              "broadcaster_type": "partner",
+              "description": "",
+              "profile_image_url": "",
+              "offline_image_url": "",
+              "email": "not-real@email.com",
+              "created_at": "2016-12-14T20:32:28Z"
+            }]}"#,
+        )]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let user = super::get_self(&c).unwrap();
+        assert_eq!(user.email.as_deref(), Some("not-real@email.com"));
+        assert!(server.requests()[0].starts_with("GET /users "));
+    }
+
+    #[test]
+    fn get_by_logins_collect() {
+        let server = mock::serve(vec![
+            mock::response(500, &[], ""),
+            mock::response(200, &[], r#"{"data": []}"#),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let logins: Vec<String> =
+            (0..150).map(|i| format!("user{}", i)).collect();
+        let logins: Vec<&str> = logins.iter().map(String::as_str).collect();
+        let r = super::get_by_logins(&c, &logins, BatchMode::Collect).unwrap();
+        assert!(r.ok.is_empty());
+        assert_eq!(r.errors.len(), 1);
+        assert_eq!(r.errors[0].0.len(), 100);
+        match r.errors[0].1 {
+            ApiError::TwitchError(ref e) => assert_eq!(e.status, 500),
+            ref e => panic!("{:?}", e),
+        }
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[test]
+    fn get_by_logins_invalid() {
+        let server = mock::serve(vec![mock::response(
+            200,
+            &[],
+            r#"{"data": [{
+              "id": "141981764",
+              "login": "twitchdev",
+              "display_name": "TwitchDev",
+              "type": "",
+              "broadcaster_type": "partner",
+              "description": "",
+              "profile_image_url": "",
+              "offline_image_url": "",
+              "created_at": "2016-12-14T20:32:28Z"
+            }]}"#,
+        )]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let logins = ["@TwitchDev ", "no", "bad login"];
+        match super::get_by_logins(&c, &logins, BatchMode::FailFast) {
+            Err(ApiError::InvalidInput(_)) => (),
+            r => panic!("{:?}", r),
+        }
+
+        let r = super::get_by_logins(&c, &logins, BatchMode::Collect).unwrap();
+        assert_eq!(r.ok.len(), 1);
+        let invalid: Vec<_> =
+            r.errors.iter().map(|e| e.0[0].as_str()).collect();
+        assert_eq!(invalid, ["no", "bad login"]);
+        assert!(r
+            .errors
+            .iter()
+            .all(|e| matches!(e.1, ApiError::InvalidInput(_))));
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].starts_with("GET /users?login=twitchdev "));
+    }
+
+    #[test]
+    fn image_sizes() {
+        let mut user: HelixUser = serde_json::from_str(
+            r#"{
+              "id": "141981764",
+              "login": "twitchdev",
+              "display_name": "TwitchDev",
+              "type": "",
+              "broadcaster_type": "partner",
+              "description": "",
+              "profile_image_url": "https://static-cdn.jtvnw.net/jtv_user_pictures/8a6381c7-d0c0-4576-b179-38bd5ce1d6af-profile_image-300x300.png",
+              "offline_image_url": "https://static-cdn.jtvnw.net/jtv_user_pictures/3f13ab61-ec78-4fe6-8481-8682cb3b0ac2-channel_offline_image-1920x1080.png",
+              "created_at": "2016-12-14T20:32:28Z"
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            user.profile_image(70, 70),
+            "https://static-cdn.jtvnw.net/jtv_user_pictures/8a6381c7-d0c0-4576-b179-38bd5ce1d6af-profile_image-70x70.png"
+        );
+        assert_eq!(
+            user.offline_image(1280, 720),
+            "https://static-cdn.jtvnw.net/jtv_user_pictures/3f13ab61-ec78-4fe6-8481-8682cb3b0ac2-channel_offline_image-1280x720.png"
+        );
+
+        for url in &[
+            "",
+            "https://static-cdn.jtvnw.net/user-default-pictures-uv/ebe4cd89-b4f4-4cd9-adac-2f30151b4209-profile_image.png",
+            "https://static-cdn.jtvnw.net/jtv_user_pictures/profile_image-300xabc.png",
+            "https://example.com/a-300x300/avatar",
+        ] {
+            user.profile_image_url = url.to_string();
+            assert_eq!(user.profile_image(70, 70), *url);
+        }
+    }
+
+    #[test]
+    fn blocks() {
+        let server = mock::serve(vec![
+            mock::response(
+                200,
+                &[],
+                r#"{
+                  "data": [{
+                    "user_id": "135093069",
+                    "user_login": "bluelava",
+                    "display_name": "BlueLava"
+                  }],
+                  "pagination": {}
+                }"#,
+            ),
+            mock::response(204, &[], ""),
+            mock::response(204, &[], ""),
+            mock::response(204, &[], ""),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let page =
+            super::blocks(&c, "141981764", &PageOpts::default()).unwrap();
+        assert_eq!(page.data[0].user_login, "bluelava");
+        assert!(page.cursor.is_none());
+
+        super::block_user(
+            &c,
+            "135093069",
+            Some(SourceContext::Chat),
+            Some(BlockReason::Harassment),
+        )
+        .unwrap();
+        super::block_user(&c, "135093069", None, None).unwrap();
+        super::unblock_user(&c, "135093069").unwrap();
+
+        let requests = server.requests();
+        assert!(requests[0]
+            .starts_with("GET /users/blocks?broadcaster_id=141981764 "));
+        assert!(requests[1].starts_with(
+            "PUT /users/blocks?target_user_id=135093069&source_context=chat&reason=harassment "
+        ));
+        assert!(requests[2]
+            .starts_with("PUT /users/blocks?target_user_id=135093069 "));
+        assert!(requests[3]
+            .starts_with("DELETE /users/blocks?target_user_id=135093069 "));
+    }
+}
@@ -0,0 +1,517 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+use chrono::prelude::*;
+use serde::Deserialize;
+
+use super::{
+    scope::HelixScope,
+    Direction,
+    Endpoint,
+    HelixPage,
+    HelixResponse,
+    PageOpts,
+};
+
+use crate::{
+    response::{
+        ApiError,
+        TwitchResult,
+    },
+    TwitchClient,
+};
+
+pub const GET: Endpoint = Endpoint::new("/videos");
+
+/// Gets the videos of a specified user, newest first
+///
+/// Supports paging `Direction::Backward` with the cursor of any page.
+///
+/// #### Authentication: App or user access token
+pub fn by_user(
+    c: &TwitchClient,
+    user_id: &str,
+    opts: &PageOpts,
+) -> TwitchResult<HelixPage<HelixVideo>>
+{
+    list(c, &VideoOpts {
+        user_id: Some(user_id.to_owned()),
+        first: opts.first,
+        after: opts.after.clone(),
+        direction: opts.direction,
+        ..VideoOpts::default()
+    })
+}
+
+/// Gets the videos matching `opts`
+///
+/// Exactly one of `opts.ids`, `opts.user_id` and `opts.game_id` has to be
+/// set, the other filters and paging only apply to the latter two.
+///
+/// #### Authentication: App or user access token
+pub fn list(
+    c: &TwitchClient,
+    opts: &VideoOpts,
+) -> TwitchResult<HelixPage<HelixVideo>>
+{
+    let r =
+        c.helix_get::<HelixResponse<HelixVideo>, _>(&GET, &opts.params()?)?;
+    Ok(HelixPage::from(r))
+}
+
+pub const DELETE: Endpoint =
+    Endpoint::new("/videos").with_scopes(&[HelixScope::ChannelManageVideos]);
+
+/// Deletes up to 5 videos of the user of the access token
+///
+/// Returns the ids of the videos which were deleted, ids of videos which
+/// don't exist or belong to someone else are left out.
+///
+/// #### Authentication: `channel:manage:videos`
+pub fn delete(
+    c: &TwitchClient,
+    video_ids: &[&str],
+) -> TwitchResult<Vec<String>>
+{
+    if video_ids.is_empty() || video_ids.len() > 5 {
+        return Err(ApiError::invalid_input(
+            "between 1 and 5 videos can be deleted at once",
+        ));
+    }
+    let params: Vec<_> = video_ids.iter().map(|id| ("id", *id)).collect();
+    let r = c.helix_delete::<HelixResponse<String>, _>(&DELETE, &params)?;
+    Ok(r.data)
+}
+
+///////////////////////////////////////
+// VideoOpts
+///////////////////////////////////////
+/// Filters of `list`, e.g.
+/// `VideoOpts::default().user_id("141981764").video_type(VideoType::Archive)`
+#[derive(Debug, Default, Clone)]
+pub struct VideoOpts {
+    /// Up to 100 video ids
+    pub ids: Vec<String>,
+    pub user_id: Option<String>,
+    pub game_id: Option<String>,
+    /// ISO 639-1 code like `en`, or `other`
+    pub language: Option<String>,
+    pub period: Option<VideoPeriod>,
+    pub sort: Option<VideoSort>,
+    pub video_type: Option<VideoType>,
+    /// Maximum number of videos per page, at most 100
+    pub first: Option<u32>,
+    /// Cursor of the page to continue after, or before when paging
+    /// `Direction::Backward`
+    pub after: Option<String>,
+    pub direction: Direction,
+}
+
+impl VideoOpts {
+    pub fn id(
+        mut self,
+        id: &str,
+    ) -> VideoOpts
+    {
+        self.ids.push(id.to_owned());
+        self
+    }
+
+    pub fn user_id(
+        mut self,
+        user_id: &str,
+    ) -> VideoOpts
+    {
+        self.user_id = Some(user_id.to_owned());
+        self
+    }
+
+    pub fn game_id(
+        mut self,
+        game_id: &str,
+    ) -> VideoOpts
+    {
+        self.game_id = Some(game_id.to_owned());
+        self
+    }
+
+    pub fn language(
+        mut self,
+        language: &str,
+    ) -> VideoOpts
+    {
+        self.language = Some(language.to_owned());
+        self
+    }
+
+    pub fn period(
+        mut self,
+        period: VideoPeriod,
+    ) -> VideoOpts
+    {
+        self.period = Some(period);
+        self
+    }
+
+    pub fn sort(
+        mut self,
+        sort: VideoSort,
+    ) -> VideoOpts
+    {
+        self.sort = Some(sort);
+        self
+    }
+
+    pub fn video_type(
+        mut self,
+        video_type: VideoType,
+    ) -> VideoOpts
+    {
+        self.video_type = Some(video_type);
+        self
+    }
+
+    pub fn first(
+        mut self,
+        first: u32,
+    ) -> VideoOpts
+    {
+        self.first = Some(first);
+        self
+    }
+
+    pub fn after(
+        mut self,
+        cursor: &str,
+    ) -> VideoOpts
+    {
+        self.after = Some(cursor.to_owned());
+        self.direction = Direction::Forward;
+        self
+    }
+
+    pub fn before(
+        mut self,
+        cursor: &str,
+    ) -> VideoOpts
+    {
+        self.after = Some(cursor.to_owned());
+        self.direction = Direction::Backward;
+        self
+    }
+
+    fn params(&self) -> TwitchResult<Vec<(&'static str, String)>> {
+        let selectors = usize::from(!self.ids.is_empty())
+            + usize::from(self.user_id.is_some())
+            + usize::from(self.game_id.is_some());
+        if selectors != 1 {
+            return Err(ApiError::invalid_input(
+                "videos are selected by exactly one of ids, user_id and \
+                 game_id",
+            ));
+        }
+        if self.ids.len() > 100 {
+            return Err(ApiError::invalid_input(
+                "at most 100 videos can be looked up at once",
+            ));
+        }
+
+        let mut params: Vec<_> =
+            self.ids.iter().map(|id| ("id", id.clone())).collect();
+        if let Some(ref user_id) = self.user_id {
+            params.push(("user_id", user_id.clone()));
+        }
+        if let Some(ref game_id) = self.game_id {
+            params.push(("game_id", game_id.clone()));
+        }
+        if let Some(ref language) = self.language {
+            params.push(("language", language.clone()));
+        }
+        if let Some(period) = self.period {
+            params.push(("period", period.as_str().to_owned()));
+        }
+        if let Some(sort) = self.sort {
+            params.push(("sort", sort.as_str().to_owned()));
+        }
+        if let Some(video_type) = self.video_type {
+            params.push(("type", video_type.as_str().to_owned()));
+        }
+        if self.first.is_some_and(|first| first == 0 || first > 100) {
+            return Err(ApiError::invalid_input(
+                "first has to be between 1 and 100",
+            ));
+        }
+        params.extend(
+            PageOpts {
+                first: self.first,
+                after: self.after.clone(),
+                direction: self.direction,
+            }
+            .params(),
+        );
+        Ok(params)
+    }
+}
+
+/// How far back `VideoOpts::period` looks, from the video's publish date
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VideoPeriod {
+    All,
+    Day,
+    Week,
+    Month,
+}
+
+impl VideoPeriod {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            VideoPeriod::All => "all",
+            VideoPeriod::Day => "day",
+            VideoPeriod::Week => "week",
+            VideoPeriod::Month => "month",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VideoSort {
+    /// Newest first, the default
+    Time,
+    Trending,
+    /// Most viewed first
+    Views,
+}
+
+impl VideoSort {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            VideoSort::Time => "time",
+            VideoSort::Trending => "trending",
+            VideoSort::Views => "views",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VideoType {
+    All,
+    /// Past broadcasts
+    Archive,
+    Highlight,
+    Upload,
+}
+
+impl VideoType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            VideoType::All => "all",
+            VideoType::Archive => "archive",
+            VideoType::Highlight => "highlight",
+            VideoType::Upload => "upload",
+        }
+    }
+}
+
+///////////////////////////////////////
+// GetVideos
+///////////////////////////////////////
+#[derive(Deserialize, Debug, Clone)]
+pub struct HelixVideo {
+    pub id: String,
+    /// The stream the video was recorded from, `None` for uploads and
+    /// highlights
+    pub stream_id: Option<String>,
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+    pub title: String,
+    pub description: String,
+    pub created_at: DateTime<Utc>,
+    pub published_at: DateTime<Utc>,
+    pub url: String,
+    /// Contains `%{width}` and `%{height}` placeholders
+    pub thumbnail_url: String,
+    /// `public` or `private`
+    pub viewable: String,
+    pub view_count: i64,
+    pub language: String,
+    /// `archive`, `highlight` or `upload`
+    #[serde(rename = "type")]
+    pub _type: String,
+    /// Length like `3h8m33s`
+    pub duration: String,
+    /// Segments muted for copyrighted music, `None` if there are none
+    pub muted_segments: Option<Vec<MutedSegment>>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MutedSegment {
+    /// Length in seconds
+    pub duration: u32,
+    /// Seconds into the video the segment starts at
+    pub offset: u32,
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        helix::{
+            Direction,
+            PageOpts,
+        },
+        mock,
+        new,
+        response::ApiError,
+    };
+
+    fn page(
+        id: &str,
+        cursor: &str,
+    ) -> String
+    {
+        mock::response(
+            200,
+            &[],
+            &format!(
+                r#"{{
+                  "data": [{{
+                    "id": "{}",
+                    "stream_id": null,
+                    "user_id": "141981764",
+                    "user_login": "twitchdev",
+                    "user_name": "TwitchDev",
+                    "title": "Twitch Developers 101",
+                    "description": "Welcome to Twitch development!",
+                    "created_at": "2018-11-14T21:30:18Z",
+                    "published_at": "2018-11-14T22:04:30Z",
+                    "url": "https://www.twitch.tv/videos/335921245",
+                    "thumbnail_url": "https://static-cdn.jtvnw.net/cf_vods/d2nvs31859zcd8/twitchdev/335921245/ce0f3a7f-57a3-4152-bc06-0c6610189fb3/thumb/index-0000000000-%{{width}}x%{{height}}.jpg",
+                    "viewable": "public",
+                    "view_count": 1863062,
+                    "language": "en",
+                    "type": "upload",
+                    "duration": "3m21s",
+                    "muted_segments": [{{"duration": 30, "offset": 120}}]
+                  }}],
+                  "pagination": {{"cursor": "{}"}}
+                }}"#,
+                id, cursor
+            ),
+        )
+    }
+
+    #[test]
+    fn page_forward_and_backward() {
+        let server = mock::serve(vec![
+            page("1", "c1"),
+            page("2", "c2"),
+            page("1", "c1"),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let mut opts = PageOpts {
+            first: Some(1),
+            ..PageOpts::default()
+        };
+        let first = super::by_user(&c, "141981764", &opts).unwrap();
+        assert_eq!(
+            first.data[0].muted_segments.as_ref().unwrap()[0].offset,
+            120
+        );
+
+        opts.after = first.cursor;
+        let second = super::by_user(&c, "141981764", &opts).unwrap();
+        assert_eq!(second.data[0].id, "2");
+
+        opts.after = second.cursor;
+        opts.direction = Direction::Backward;
+        let back = super::by_user(&c, "141981764", &opts).unwrap();
+        assert_eq!(back.data[0].id, "1");
+
+        let requests = server.requests();
+        assert!(
+            requests[0].starts_with("GET /videos?user_id=141981764&first=1 ")
+        );
+        assert!(requests[1]
+            .starts_with("GET /videos?user_id=141981764&first=1&after=c1 "));
+        assert!(requests[2]
+            .starts_with("GET /videos?user_id=141981764&first=1&before=c2 "));
+    }
+
+    #[test]
+    fn delete() {
+        let server = mock::serve(vec![mock::response(
+            200,
+            &[],
+            r#"{"data": ["1234"]}"#,
+        )]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let deleted = super::delete(&c, &["1234", "9876"]).unwrap();
+        assert_eq!(deleted, vec!["1234"]);
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with("DELETE /videos?id=1234&id=9876 "));
+    }
+
+    #[test]
+    fn delete_limits() {
+        let c = new(String::from("<clientid>"));
+
+        for ids in &[&[][..], &["1", "2", "3", "4", "5", "6"][..]] {
+            match super::delete(&c, ids) {
+                Err(ApiError::InvalidInput(_)) => (),
+                r => panic!("{:?}", r),
+            }
+        }
+    }
+
+    #[test]
+    fn video_opts() {
+        use super::{
+            VideoOpts,
+            VideoPeriod,
+            VideoSort,
+            VideoType,
+        };
+
+        let server = mock::serve(vec![page("1", "c1"), page("1", "c1")]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let opts = VideoOpts::default()
+            .game_id("509670")
+            .period(VideoPeriod::Week)
+            .sort(VideoSort::Views)
+            .video_type(VideoType::Highlight)
+            .first(20);
+        let page = super::list(&c, &opts).unwrap();
+        let opts = opts.before(&page.cursor.unwrap());
+        super::list(&c, &opts).unwrap();
+
+        for opts in &[
+            VideoOpts::default(),
+            VideoOpts::default().id("335921245").user_id("141981764"),
+            VideoOpts::default().user_id("141981764").first(0),
+            VideoOpts::default().user_id("141981764").first(101),
+        ] {
+            match super::list(&c, opts) {
+                Err(ApiError::InvalidInput(_)) => (),
+                r => panic!("{:?}", r),
+            }
+        }
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with(
+            "GET /videos?game_id=509670&period=week&sort=views&type=highlight&first=20 "
+        ));
+        assert!(requests[1].starts_with(
+            "GET /videos?game_id=509670&period=week&sort=views&type=highlight&first=20&before=c1 "
+        ));
+    }
+}
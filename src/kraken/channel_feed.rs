@@ -297,8 +297,10 @@ pub struct FeedPostCommentPermissions {
 pub struct FeedPostEmotes {
     pub start: i32,
     pub end: i32,
-    pub id: i64,
-    pub set: i64,
+    #[serde(deserialize_with = "crate::kraken::id")]
+    pub id: String,
+    #[serde(deserialize_with = "crate::kraken::id")]
+    pub set: String,
 }
 
 #[derive(Serialize, Debug)]
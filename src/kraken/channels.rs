@@ -297,13 +297,13 @@ pub fn reset_stream_key(
 ///////////////////////////////////////
 #[derive(Deserialize, Debug)]
 pub struct Channel {
-    #[serde(rename = "_id")]
-    pub id: i64,
+    #[serde(rename = "_id", deserialize_with = "crate::kraken::id")]
+    pub id: String,
     pub broadcaster_language: String,
     pub created_at: DateTime<Utc>,
     pub display_name: String,
     pub email: Option<String>,
-    pub followers: i32,
+    pub followers: i64,
     pub game: String,
     pub language: String,
     pub logo: String,
@@ -317,7 +317,7 @@ pub struct Channel {
     pub updated_at: DateTime<Utc>,
     pub url: String,
     pub video_banner: Option<String>,
-    pub views: i32,
+    pub views: i64,
 }
 
 ///////////////////////////////////////
@@ -330,7 +330,8 @@ pub struct ChannelEditors {
 
 #[derive(Deserialize, Debug)]
 pub struct ChannelEditor {
-    pub _id: i64,
+    #[serde(deserialize_with = "crate::kraken::id")]
+    pub _id: String,
     pub bio: Option<String>,
     pub created_at: DateTime<Utc>,
     pub display_name: String,
@@ -366,7 +367,8 @@ pub struct ChannelTeams {
 
 #[derive(Deserialize, Debug)]
 pub struct ChannelTeam {
-    pub _id: i64,
+    #[serde(deserialize_with = "crate::kraken::id")]
+    pub _id: String,
     pub background: Option<String>,
     pub banner: String,
     pub created_at: DateTime<Utc>,
@@ -464,7 +466,7 @@ mod tests {
         c.set_oauth_token(TOKEN);
 
         match super::get(&c) {
-            Ok(r) => assert_eq!(&r.id.to_string(), CHANID),
+            Ok(r) => assert_eq!(r.id, CHANID),
             Err(r) => {
                 println!("{:?}", r);
                 assert!(false);
@@ -477,7 +479,7 @@ mod tests {
         let c = new(String::from(CLIENTID));
 
         match super::get_by_id(&c, CHANID) {
-            Ok(r) => assert_eq!(&r.id.to_string(), CHANID),
+            Ok(r) => assert_eq!(r.id, CHANID),
             Err(r) => {
                 println!("{:?}", r);
                 assert!(false);
@@ -89,7 +89,8 @@ pub struct EmotesBySet {
 
 #[derive(Deserialize, Debug)]
 pub struct EmoteSet {
-    pub id: i64,
+    #[serde(deserialize_with = "crate::kraken::id")]
+    pub id: String,
     pub code: String,
 }
 
@@ -52,17 +52,18 @@ pub struct TopGames<'c> {
 #[derive(Deserialize, Debug)]
 pub struct TopGame {
     pub channels: i32,
-    pub viewers: i32,
+    pub viewers: i64,
     pub game: Game,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct Game {
-    #[serde(rename = "_id")]
-    pub id: i64,
+    #[serde(rename = "_id", deserialize_with = "crate::kraken::id")]
+    pub id: String,
     #[serde(rename = "box")]
     pub _box: HashMap<String, String>,
-    pub giantbomb_id: i64,
+    #[serde(deserialize_with = "crate::kraken::id")]
+    pub giantbomb_id: String,
     pub logo: HashMap<String, String>,
     pub name: String,
     #[serde(default)]
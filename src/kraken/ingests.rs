@@ -45,7 +45,8 @@ pub struct IngestServerList {
 
 #[derive(Deserialize, Debug)]
 pub struct IngestServer {
-    pub _id: i64,
+    #[serde(deserialize_with = "crate::kraken::id")]
+    pub _id: String,
     pub availability: f32,
     pub default: bool,
     pub name: String,
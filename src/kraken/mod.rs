@@ -10,3 +10,58 @@ pub mod streams;
 pub mod teams;
 pub mod users;
 pub mod videos;
+
+use serde::{
+    Deserialize,
+    Deserializer,
+};
+
+/// Kraken sends ids as numbers by some endpoints and as numeric strings by
+/// others, both are kept as `String` so they are neither truncated nor
+/// compared differently depending on the endpoint
+pub(crate) fn id<'de, D>(d: D) -> Result<String, D::Error>
+where D: Deserializer<'de> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Id {
+        Number(u64),
+        String(String),
+    }
+
+    Ok(match Id::deserialize(d)? {
+        Id::Number(id) => id.to_string(),
+        Id::String(id) => id,
+    })
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod id_tests {
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    struct Ids {
+        #[serde(deserialize_with = "super::id")]
+        number: String,
+        #[serde(deserialize_with = "super::id")]
+        string: String,
+    }
+
+    #[test]
+    fn id() {
+        let ids: Ids = serde_json::from_str(
+            r#"{"number": 18446744073709551615, "string": "18446744073709551615"}"#,
+        )
+        .unwrap();
+        assert_eq!(ids.number, "18446744073709551615");
+        assert_eq!(ids.string, ids.number);
+
+        assert!(serde_json::from_str::<Ids>(
+            r#"{"number": -1, "string": "1"}"#
+        )
+        .is_err());
+    }
+}
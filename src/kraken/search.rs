@@ -210,7 +210,7 @@ mod tests {
         let c = new(String::from(CLIENTID));
 
         match super::channels(&c, "twitch") {
-            Ok(mut r) => assert_ne!(r.next().unwrap().id, 0),
+            Ok(mut r) => assert!(!r.next().unwrap().id.is_empty()),
             Err(r) => {
                 println!("{:?}", r);
                 assert!(false);
@@ -223,7 +223,7 @@ mod tests {
         let c = new(String::from(CLIENTID));
 
         match super::games(&c, "league", false) {
-            Ok(mut r) => assert_ne!(r.next().unwrap().id, 0),
+            Ok(mut r) => assert!(!r.next().unwrap().id.is_empty()),
             Err(r) => {
                 println!("{:?}", r);
                 assert!(false);
@@ -236,7 +236,7 @@ mod tests {
         let c = new(String::from(CLIENTID));
 
         match super::streams(&c, "twitch", None) {
-            Ok(mut r) => assert_ne!(r.next().unwrap().id, 0),
+            Ok(mut r) => assert!(!r.next().unwrap().id.is_empty()),
             Err(r) => {
                 println!("{:?}", r);
                 assert!(false);
@@ -27,6 +27,7 @@ use serde::Deserialize;
 use super::channels::Channel;
 
 use crate::{
+    helix::streams::StreamType,
     response::TwitchResult,
     TwitchClient,
 };
@@ -127,15 +128,17 @@ pub struct StreamByUser {
 
 #[derive(Deserialize, Debug)]
 pub struct Stream {
-    #[serde(rename = "_id")]
-    pub id: i64,
+    #[serde(rename = "_id", deserialize_with = "crate::kraken::id")]
+    pub id: String,
     pub game: String,
-    pub viewers: i32,
+    pub viewers: i64,
     pub video_height: i32,
     pub average_fps: i32,
     pub delay: i32,
     pub created_at: DateTime<Utc>,
     pub is_playlist: bool,
+    #[serde(default)]
+    pub stream_type: StreamType,
     pub preview: HashMap<String, String>,
     pub channel: Channel,
 }
@@ -291,7 +294,7 @@ mod tests {
         let c = new(String::from(CLIENTID));
 
         match super::live(&c, None, None, None) {
-            Ok(mut r) => assert_ne!(r.next().unwrap().id, 0),
+            Ok(mut r) => assert!(!r.next().unwrap().id.is_empty()),
             Err(r) => {
                 println!("{:?}", r);
                 assert!(false);
@@ -308,11 +311,11 @@ mod tests {
                 }
             }
         {
-            assert_ne!(chan.id, 0);
+            assert!(!chan.id.is_empty());
 
             match super::live(&c, Some(&[&chan.id.to_string()]), None, None) {
                 Ok(mut r) => match r.next() {
-                    Some(st) => assert_ne!(st.id, 0),
+                    Some(st) => assert!(!st.id.is_empty()),
                     None => {
                         println!("{:?}", chan);
                         assert!(false);
@@ -326,7 +329,7 @@ mod tests {
         }
 
         match super::live(&c, None, None, Some("en".to_owned())) {
-            Ok(mut r) => assert_ne!(r.next().unwrap().id, 0),
+            Ok(mut r) => assert!(!r.next().unwrap().id.is_empty()),
             Err(r) => {
                 println!("{:?}", r);
                 assert!(false);
@@ -366,7 +369,7 @@ mod tests {
 
         match super::featured(&c) {
             Ok(mut r) => match r.next() {
-                Some(st) => assert_ne!(st.stream.id, 0),
+                Some(st) => assert!(!st.stream.id.is_empty()),
                 None => assert!(false),
             },
             Err(r) => {
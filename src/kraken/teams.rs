@@ -65,8 +65,8 @@ pub struct TeamIterator<'c> {
 
 #[derive(Deserialize, Debug)]
 pub struct Team {
-    #[serde(rename = "_id")]
-    pub id: i64,
+    #[serde(rename = "_id", deserialize_with = "crate::kraken::id")]
+    pub id: String,
     pub background: Option<String>,
     pub banner: String,
     pub created_at: DateTime<Utc>,
@@ -108,7 +108,7 @@ mod tests {
         let c = new(String::from(CLIENTID));
         match super::get_all(&c) {
             Ok(mut r) => match r.next() {
-                Some(team) => assert_ne!(team.id, 0),
+                Some(team) => assert!(!team.id.is_empty()),
                 None => assert!(false),
             },
             Err(r) => {
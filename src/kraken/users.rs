@@ -248,8 +248,8 @@ pub fn unblock(
 ///////////////////////////////////////
 #[derive(Deserialize, Debug)]
 pub struct User {
-    #[serde(rename = "_id")]
-    pub id: i64,
+    #[serde(rename = "_id", deserialize_with = "crate::kraken::id")]
+    pub id: String,
     pub bio: String,
     pub created_at: DateTime<Utc>,
     pub display_name: String,
@@ -391,7 +391,7 @@ mod tests {
                 },
             }
             // follow
-            match super::follow(&c, &user_id, &TESTCH.to_string(), false) {
+            match super::follow(&c, &user_id, TESTCH, false) {
                 Ok(r) => assert_eq!(r.channel.id, TESTCH),
                 Err(r) => {
                     println!("{:?}", r);
@@ -405,21 +405,21 @@ mod tests {
                     assert!(false);
                 }
             }
-            match super::is_following(&c, &user_id, &TESTCH.to_string()) {
+            match super::is_following(&c, &user_id, TESTCH) {
                 Ok(_r) => (),
                 Err(r) => {
                     println!("{:?}", r);
                     assert!(false);
                 }
             }
-            match super::unfollow(&c, &user_id, &TESTCH.to_string()) {
+            match super::unfollow(&c, &user_id, TESTCH) {
                 Ok(_r) => (),
                 Err(r) => {
                     println!("{:?}", r);
                     assert!(false);
                 }
             }
-            match super::is_following(&c, &user_id, &TESTCH.to_string()) {
+            match super::is_following(&c, &user_id, TESTCH) {
                 Ok(r) => assert!(r.is_none()),
                 Err(r) => {
                     println!("{:?}", r);
@@ -428,14 +428,14 @@ mod tests {
             }
             // block
             match super::block(&c, &user_id, "1") {
-                Ok(r) => assert_eq!(r.user.id, 1),
+                Ok(r) => assert_eq!(r.user.id, "1"),
                 Err(r) => {
                     println!("{:?}", r);
                     assert!(false);
                 }
             }
             match super::blocking(&c, &user_id) {
-                Ok(mut r) => assert_eq!(r.next().unwrap().user.id, 1),
+                Ok(mut r) => assert_eq!(r.next().unwrap().user.id, "1"),
                 Err(r) => {
                     println!("{:?}", r);
                     assert!(false);
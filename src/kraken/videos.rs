@@ -86,7 +86,8 @@ pub fn followed(c: &TwitchClient) -> TwitchResult<FollowedVideoIterator> {
 pub struct Video {
     #[serde(rename = "_id")]
     pub id: String,
-    pub broadcast_id: i64,
+    #[serde(deserialize_with = "crate::kraken::id")]
+    pub broadcast_id: String,
     pub broadcast_type: String,
     pub channel: HashMap<String, String>,
     pub created_at: DateTime<Utc>,
@@ -107,7 +108,7 @@ pub struct Video {
     pub url: String,
     pub viewable: String,
     pub viewable_at: Option<DateTime<Utc>>,
-    pub views: i32,
+    pub views: i64,
 }
 
 ///////////////////////////////////////
@@ -39,7 +39,18 @@
 
 #[macro_use]
 pub mod response;
+pub mod chat;
+pub mod circuit;
+pub mod embed;
+pub mod helix;
 pub mod kraken;
+pub mod ratelimit;
+pub mod retry;
+pub mod token;
+pub mod util;
+
+#[cfg(test)]
+mod mock;
 
 use serde::{
     de::DeserializeOwned,
@@ -47,15 +58,41 @@ use serde::{
     Serialize,
 };
 
+use circuit::{
+    Circuit,
+    CircuitBreaker,
+    CircuitState,
+};
+use helix::{
+    games::HelixGame,
+    scope::HelixScope,
+    Endpoint,
+    HelixPage,
+    HelixResponse,
+    PageOpts,
+};
+use ratelimit::{
+    RateLimitCallback,
+    RateLimitHook,
+    RateLimitStatus,
+};
 use response::{
     ApiError,
+    ErrorResponse,
+    SkippedItem,
     TwitchResult,
 };
+use retry::RetryPolicy;
+use token::{
+    Token,
+    TokenStore,
+};
 
 use reqwest::{
     blocking::{
         Client,
         RequestBuilder,
+        Response,
     },
     header::{
         HeaderMap,
@@ -67,7 +104,20 @@ use reqwest::{
     },
 };
 
-use std::fs;
+use std::{
+    collections::HashMap,
+    fs,
+    io::{
+        self,
+        Read,
+    },
+    sync::Mutex,
+    thread,
+    time::{
+        Duration,
+        Instant,
+    },
+};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Credentials {
@@ -102,6 +152,15 @@ impl Credentials {
         }
     }
 
+    /// Reads credentials from a TOML file, like `set_from_file`, but fails
+    /// instead of panicking
+    pub fn from_file(file: &str) -> TwitchResult<Credentials> {
+        let content = fs::read_to_string(file)?;
+        toml::from_str(&content).map_err(|e| {
+            ApiError::from(io::Error::new(io::ErrorKind::InvalidData, e))
+        })
+    }
+
     pub fn write_to_file(
         &self,
         file: String,
@@ -116,16 +175,329 @@ impl Credentials {
 pub struct TwitchClient {
     client: Client,
     cred: Credentials,
+    http: HttpConfig,
+    helix_url: String,
+    id_url: String,
+    client_secret: Option<String>,
+    token: Option<Token>,
+    token_store: Option<Box<dyn TokenStore>>,
+    rate_limit: Mutex<Option<RateLimitStatus>>,
+    on_rate_limit: Option<RateLimitHook>,
+    costs: HashMap<&'static str, u32>,
+    max_response_bytes: usize,
+    retry: Option<RetryPolicy>,
+    circuit: Option<Circuit>,
+    lenient_items: bool,
+    skipped_items: Mutex<Vec<SkippedItem>>,
+    /// Games resolved by `resolve_game`, keyed by lowercase name
+    games: Mutex<HashMap<String, HelixGame>>,
+    /// The token `can_call` last validated and its scopes
+    granted_scopes: Mutex<Option<(String, Vec<HelixScope>)>>,
+}
+
+/// Default for `TwitchClient::with_max_response_bytes`, 16 MiB
+pub const DEFAULT_MAX_RESPONSE_BYTES: usize = 16 * 1024 * 1024;
+
+/// Settings the inner HTTP client gets built with
+#[derive(Debug, Default, Clone)]
+struct HttpConfig {
+    tcp_keepalive: Option<Duration>,
+    pool_idle_timeout: Option<Duration>,
+    #[cfg(feature = "dangerous-testing")]
+    accept_invalid_certs: bool,
+}
+
+impl HttpConfig {
+    fn build(&self) -> Client {
+        let mut builder = Client::builder();
+        if let Some(keepalive) = self.tcp_keepalive {
+            builder = builder.tcp_keepalive(keepalive);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        #[cfg(feature = "dangerous-testing")]
+        {
+            builder =
+                builder.danger_accept_invalid_certs(self.accept_invalid_certs);
+        }
+        builder.build().expect("Error building the HTTP client")
+    }
 }
 
 pub fn new(clientid: String) -> TwitchClient {
+    with_inner_client(clientid, Client::new())
+}
+
+/// Like `new`, but sends the requests with `client` instead of building an
+/// HTTP client of its own
+fn with_inner_client(
+    clientid: String,
+    client: Client,
+) -> TwitchClient
+{
     TwitchClient {
-        client: Client::new(),
+        client,
         cred: Credentials::new(clientid),
+        http: HttpConfig::default(),
+        helix_url: String::from("https://api.twitch.tv/helix"),
+        id_url: String::from("https://id.twitch.tv"),
+        client_secret: None,
+        token: None,
+        token_store: None,
+        rate_limit: Mutex::new(None),
+        on_rate_limit: None,
+        costs: HashMap::new(),
+        max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+        retry: None,
+        circuit: None,
+        lenient_items: false,
+        skipped_items: Mutex::new(Vec::new()),
+        games: Mutex::new(HashMap::new()),
+        granted_scopes: Mutex::new(None),
+    }
+}
+
+/// Creates clients for many users which share one connection pool
+///
+/// Every client keeps its own credentials, token and rate limit status.
+/// That matches how Twitch counts requests with user access tokens, which
+/// get a bucket per user and client id, while requests with app access
+/// tokens of the same client id draw from one bucket no client can see
+/// the others use. Calling `with_tcp_keepalive` or `with_pool_idle_timeout`
+/// on a created client gives it a pool of its own.
+#[derive(Debug, Clone)]
+pub struct TwitchClientFactory {
+    client: Client,
+}
+
+impl TwitchClientFactory {
+    pub fn new(shared_client: Client) -> TwitchClientFactory {
+        TwitchClientFactory {
+            client: shared_client,
+        }
+    }
+
+    /// Creates a client acting with `cred`, sharing the connection pool of
+    /// the factory
+    pub fn for_credentials(
+        &self,
+        cred: Credentials,
+    ) -> TwitchClient
+    {
+        let mut c = with_inner_client(cred.client_id, self.client.clone());
+        c.set_oauth_token(&cred.token);
+        c
+    }
+}
+
+/// Path `TwitchClient::new_validated` reads credentials from by default
+pub const CREDENTIALS_FILE: &str = "credentials.toml";
+
+/// Timings reported by `TwitchClient::probe_connection_reuse`
+#[derive(Debug, Clone, Copy)]
+pub struct ConnectionProbe {
+    pub first: Duration,
+    pub second: Duration,
+}
+
+impl ConnectionProbe {
+    /// Whether the second request was fast enough to have skipped the
+    /// TCP and TLS handshakes
+    ///
+    /// This is a heuristic: a reused connection usually answers in well
+    /// under half the time a fresh one needs.
+    pub fn likely_reused(&self) -> bool {
+        self.second * 2 < self.first
     }
 }
 
 impl TwitchClient {
+    /// Creates a client from the credentials in `file` and checks them
+    ///
+    /// Reads `CREDENTIALS_FILE` if `file` is `None`. Fails if the file
+    /// can't be read, the client id or token is empty, or Twitch reports
+    /// the token as invalid or issued to another client id. Use `new` to
+    /// create a client without any checks.
+    pub fn new_validated(file: Option<String>) -> TwitchResult<TwitchClient> {
+        let cred = Credentials::from_file(
+            file.as_deref().unwrap_or(CREDENTIALS_FILE),
+        )?;
+        let mut c = new(cred.client_id);
+        c.set_oauth_token(&cred.token);
+        c.check_credentials()?;
+        Ok(c)
+    }
+
+    fn check_credentials(&self) -> TwitchResult<()> {
+        if self.cred.client_id.is_empty() {
+            return Err(ApiError::invalid_input("the client id is empty"));
+        }
+        if self.cred.token.is_empty() {
+            return Err(ApiError::invalid_input("the token is empty"));
+        }
+        let info = self.validate_token()?;
+        if info.client_id != self.cred.client_id {
+            return Err(ApiError::invalid_input(
+                "the token was issued to another client id",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Enables TCP keepalive probes on the connections to Twitch
+    ///
+    /// Bots making steady requests should keep this at around a minute so
+    /// idle connections aren't silently dropped by middleboxes in between.
+    /// Disabled by default.
+    pub fn with_tcp_keepalive(
+        mut self,
+        keepalive: Duration,
+    ) -> TwitchClient
+    {
+        self.http.tcp_keepalive = Some(keepalive);
+        self.client = self.http.build();
+        self
+    }
+
+    /// Sets how long idle connections are kept in the pool for reuse
+    ///
+    /// Pick a value longer than your polling interval, otherwise every poll
+    /// pays for a new TLS handshake. Defaults to 90 seconds.
+    pub fn with_pool_idle_timeout(
+        mut self,
+        timeout: Duration,
+    ) -> TwitchClient
+    {
+        self.http.pool_idle_timeout = Some(timeout);
+        self.client = self.http.build();
+        self
+    }
+
+    /// Disables TLS certificate verification
+    ///
+    /// **DANGER: never use this against the real Twitch API.** Any server
+    /// can impersonate Twitch and read your tokens with this turned on. It
+    /// only exists to test against local mock servers with self-signed
+    /// certificates and is only available with the `dangerous-testing`
+    /// feature, which must not be enabled in production builds.
+    #[cfg(feature = "dangerous-testing")]
+    pub fn danger_accept_invalid_certs(
+        mut self,
+        accept: bool,
+    ) -> TwitchClient
+    {
+        self.http.accept_invalid_certs = accept;
+        self.client = self.http.build();
+        self
+    }
+
+    /// Sends requests with `client` instead of a client of its own
+    ///
+    /// Clones of a `Client` share their connection pool, see
+    /// `TwitchClientFactory`.
+    pub fn with_client(
+        mut self,
+        client: Client,
+    ) -> TwitchClient
+    {
+        self.client = client;
+        self
+    }
+
+    /// Retries Helix requests which failed for transient reasons
+    ///
+    /// See `RetryPolicy::should_retry` for which errors are retried. Retrying
+    /// is disabled by default.
+    pub fn with_retry(
+        mut self,
+        policy: RetryPolicy,
+    ) -> TwitchClient
+    {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Fails Helix requests fast while Twitch seems to be down
+    ///
+    /// See `CircuitBreaker` for when the circuit opens. Retries count as
+    /// requests of their own, once the circuit opened they fail with
+    /// `ApiError::CircuitOpen` instead of being sent. Disabled by default.
+    pub fn with_circuit_breaker(
+        mut self,
+        breaker: CircuitBreaker,
+    ) -> TwitchClient
+    {
+        self.circuit = Some(Circuit::new(breaker));
+        self
+    }
+
+    /// The state of the circuit breaker, `None` if there is none
+    pub fn circuit_state(&self) -> Option<CircuitState> {
+        self.circuit.as_ref().map(Circuit::state)
+    }
+
+    /// Leaves out elements of a Helix response's `data` which fail to
+    /// parse instead of failing the whole response
+    ///
+    /// Useful when Twitch changes a field before this crate catches up,
+    /// e.g. to show 99 of 100 streams. The left out elements are collected
+    /// until `take_skipped_items` is called. Disabled by default.
+    pub fn with_lenient_items(
+        mut self,
+        lenient: bool,
+    ) -> TwitchClient
+    {
+        self.lenient_items = lenient;
+        self
+    }
+
+    /// Returns and forgets the elements left out since the last call, see
+    /// `with_lenient_items`
+    ///
+    /// The elements of all threads sharing the client are collected
+    /// together.
+    pub fn take_skipped_items(&self) -> Vec<SkippedItem> {
+        std::mem::take(&mut *self.skipped_items.lock().unwrap())
+    }
+
+    /// Limits how many bytes of a response body are read
+    ///
+    /// Reading stops as soon as the limit is exceeded and the request fails
+    /// with `ApiError::ResponseTooLarge`, so a misbehaving server or proxy
+    /// can't make the client buffer an arbitrarily large body. Defaults to
+    /// `DEFAULT_MAX_RESPONSE_BYTES`.
+    pub fn with_max_response_bytes(
+        mut self,
+        max: usize,
+    ) -> TwitchClient
+    {
+        self.max_response_bytes = max;
+        self
+    }
+
+    /// Debug helper to check whether connections are being reused
+    ///
+    /// Issues two consecutive requests to `path` and reports how long each
+    /// one took. The first request has to establish the connection, so if
+    /// the second one isn't noticeably faster, the connection most likely
+    /// isn't kept alive between calls.
+    pub fn probe_connection_reuse(
+        &self,
+        path: &str,
+    ) -> TwitchResult<ConnectionProbe>
+    {
+        let start = Instant::now();
+        self.get::<serde_json::Value>(path)?;
+        let first = start.elapsed();
+
+        let start = Instant::now();
+        self.get::<serde_json::Value>(path)?;
+        let second = start.elapsed();
+
+        Ok(ConnectionProbe { first, second })
+    }
+
     fn build_request<F>(
         &self,
         path: &str,
@@ -135,7 +507,6 @@ impl TwitchClient {
         F: Fn(&str) -> RequestBuilder,
     {
         let url = String::from("https://api.twitch.tv/kraken") + path;
-        let oauth = format!("OAuth {}", self.cred.token);
         let cid = format!("{:#?}", self.cred.client_id);
 
         let mut headers = HeaderMap::new();
@@ -152,7 +523,11 @@ impl TwitchClient {
             ACCEPT,
             HeaderValue::from_static("application/vnd.twitchtv.v5+json"),
         );
-        headers.insert(AUTHORIZATION, HeaderValue::from_str(&oauth).unwrap());
+        if !self.cred.token.is_empty() {
+            let oauth = format!("OAuth {}", self.cred.token);
+            headers
+                .insert(AUTHORIZATION, HeaderValue::from_str(&oauth).unwrap());
+        }
 
         build(&url).headers(headers)
     }
@@ -177,10 +552,7 @@ impl TwitchClient {
 
         match r {
             Err(err) => Err(ApiError::from(err)),
-            Ok(x) => match x.json() {
-                Err(err) => Err(ApiError::from(err)),
-                Ok(x) => Ok(x),
-            },
+            Ok(x) => self.read_json(x),
         }
     }
 
@@ -201,10 +573,38 @@ impl TwitchClient {
 
         match r {
             Err(err) => Err(ApiError::from(err)),
-            Ok(x) => match x.json() {
-                Err(err) => Err(ApiError::from(err)),
-                Ok(x) => Ok(x),
-            },
+            Ok(x) => self.read_json(x),
+        }
+    }
+
+    fn build_form_request(
+        &self,
+        url: &str,
+        params: &[(&str, &str)],
+    ) -> RequestBuilder
+    {
+        self.client.post(url).form(params)
+    }
+
+    /// Sends `params` as an `application/x-www-form-urlencoded` body
+    ///
+    /// Unlike the other helpers this takes a full URL and doesn't add any
+    /// Twitch headers, as it is meant for the OAuth endpoints on
+    /// `id.twitch.tv`, which expect the client id and secret in the body.
+    pub fn post_form<R: DeserializeOwned>(
+        &self,
+        url: &str,
+        params: &[(&str, &str)],
+    ) -> TwitchResult<R>
+    {
+        let r = self
+            .build_form_request(url, params)
+            .send()?
+            .error_for_status();
+
+        match r {
+            Err(err) => Err(ApiError::from(err)),
+            Ok(x) => self.read_json(x),
         }
     }
 
@@ -225,10 +625,7 @@ impl TwitchClient {
 
         match r {
             Err(err) => Err(ApiError::from(err)),
-            Ok(x) => match x.json() {
-                Err(err) => Err(ApiError::from(err)),
-                Ok(x) => Ok(x),
-            },
+            Ok(x) => self.read_json(x),
         }
     }
 
@@ -244,20 +641,508 @@ impl TwitchClient {
 
         match r {
             Err(err) => Err(ApiError::from(err)),
-            Ok(x) => match x.json() {
-                Err(err) => Err(ApiError::from(err)),
-                Ok(x) => Ok(x),
+            Ok(x) => self.read_json(x),
+        }
+    }
+
+    /// Reads the body of `r`, up to `max_response_bytes`
+    fn read_body(
+        &self,
+        r: Response,
+    ) -> TwitchResult<Vec<u8>>
+    {
+        let max = self.max_response_bytes;
+        if r.content_length().is_some_and(|len| len > max as u64) {
+            return Err(ApiError::ResponseTooLarge(max));
+        }
+
+        // The length header is optional, so count while reading as well
+        let mut body = Vec::new();
+        r.take(max as u64 + 1).read_to_end(&mut body)?;
+        if body.len() > max {
+            return Err(ApiError::ResponseTooLarge(max));
+        }
+        Ok(body)
+    }
+
+    /// Parses the body of a successful response, empty bodies like those of
+    /// `204 No Content` are treated like `null`
+    fn read_json<T: DeserializeOwned>(
+        &self,
+        r: Response,
+    ) -> TwitchResult<T>
+    {
+        let body = self.read_body(r)?;
+        if body.is_empty() {
+            return Ok(serde_json::from_str("null")?);
+        }
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    fn build_helix_request<F>(
+        &self,
+        path: &str,
+        build: F,
+    ) -> RequestBuilder
+    where
+        F: Fn(&str) -> RequestBuilder,
+    {
+        let url = self.helix_url.clone() + path;
+
+        let mut headers = HeaderMap::new();
+
+        // public endpoints work with only a client id, so both headers are
+        // left out instead of sent empty if they aren't set
+        if !self.cred.client_id.is_empty() {
+            headers.insert(
+                HeaderName::from_lowercase(b"client-id").unwrap(),
+                HeaderValue::from_str(&self.cred.client_id).unwrap(),
+            );
+        }
+        if !self.cred.token.is_empty() {
+            let bearer = format!("Bearer {}", self.cred.token);
+            headers
+                .insert(AUTHORIZATION, HeaderValue::from_str(&bearer).unwrap());
+        }
+
+        build(&url).headers(headers)
+    }
+
+    /// Turns a Helix response into either `T` or the error Twitch sent
+    ///
+    /// Helix reports failures as a JSON body, which is kept as
+    /// `ApiError::TwitchError` instead of a bare HTTP status error, or as
+    /// `ApiError::Forbidden` for `403 Forbidden`. Empty
+    /// bodies (`204 No Content`) are treated like `null`, so `()` can be
+    /// used as `T` for endpoints without a response body.
+    fn helix_response<T: DeserializeOwned>(
+        &self,
+        r: Response,
+    ) -> TwitchResult<T>
+    {
+        let body = self.helix_body(r)?;
+        self.parse_helix(&body)
+    }
+
+    /// Reads the body of a Helix response, or the error Twitch sent instead
+    fn helix_body(
+        &self,
+        r: Response,
+    ) -> TwitchResult<Vec<u8>>
+    {
+        if let Some(status) = RateLimitStatus::from_headers(r.headers()) {
+            *self.rate_limit.lock().unwrap() = Some(status);
+            if let Some(RateLimitHook(ref callback)) = self.on_rate_limit {
+                callback(&status);
+            }
+        }
+
+        let status = r.status();
+        let body = self.read_body(r)?;
+
+        if !status.is_success() {
+            let err = serde_json::from_slice::<ErrorResponse>(&body)
+                .unwrap_or_else(|_| ErrorResponse {
+                    error: status
+                        .canonical_reason()
+                        .unwrap_or_default()
+                        .to_owned(),
+                    status: i32::from(status.as_u16()),
+                    message: String::from_utf8_lossy(&body).into_owned(),
+                    cause: None,
+                });
+            return Err(ApiError::from_twitch(err));
+        }
+        Ok(body)
+    }
+
+    fn parse_helix<T: DeserializeOwned>(
+        &self,
+        body: &[u8],
+    ) -> TwitchResult<T>
+    {
+        if body.is_empty() {
+            Ok(serde_json::from_str("null")?)
+        }
+        else if self.lenient_items {
+            let (t, skipped) = response::parse_lenient(body)?;
+            self.skipped_items.lock().unwrap().extend(skipped);
+            Ok(t)
+        }
+        else {
+            Ok(serde_json::from_slice(body)?)
+        }
+    }
+
+    /// Paces and sends a request to `endpoint`, retrying it according to
+    /// the retry policy as long as the circuit breaker lets it through
+    fn send_helix<T: DeserializeOwned>(
+        &self,
+        endpoint: &Endpoint,
+        req: RequestBuilder,
+    ) -> TwitchResult<T>
+    {
+        let body = self.send_helix_raw(endpoint, req)?;
+        self.parse_helix(&body)
+    }
+
+    /// Like `send_helix`, but returns the body as is instead of parsing it
+    /// as JSON
+    fn send_helix_raw(
+        &self,
+        endpoint: &Endpoint,
+        mut req: RequestBuilder,
+    ) -> TwitchResult<Vec<u8>>
+    {
+        let mut retry = 0;
+        loop {
+            if let Some(ref circuit) = self.circuit {
+                circuit.acquire()?;
+            }
+            self.pace(self.cost(endpoint));
+            let next = match self.retry {
+                Some(ref policy) if retry < policy.max_retries => {
+                    req.try_clone().map(|next| (policy, next))
+                }
+                _ => None,
+            };
+
+            let r = req
+                .send()
+                .map_err(ApiError::from)
+                .and_then(|r| self.helix_body(r));
+            if let Some(ref circuit) = self.circuit {
+                circuit.record(&r);
+            }
+            match (r, next) {
+                (Err(ref e), Some((policy, next)))
+                    if policy.should_retry(e) =>
+                {
+                    thread::sleep(policy.delay_for(
+                        e,
+                        retry,
+                        self.rate_limit_status(),
+                    ));
+                    retry += 1;
+                    req = next;
+                }
+                (r, _) => {
+                    return r
+                        .map_err(|e| e.with_expected_scopes(endpoint.scopes()))
+                }
+            }
+        }
+    }
+
+    /// Sends a GET request to the Helix `endpoint`, encoding `params` as
+    /// query string
+    pub fn helix_get<T, Q>(
+        &self,
+        endpoint: &Endpoint,
+        params: &Q,
+    ) -> TwitchResult<T>
+    where
+        T: DeserializeOwned,
+        Q: Serialize + ?Sized,
+    {
+        let req = self
+            .build_helix_request(endpoint.path, |url| self.client.get(url))
+            .query(params);
+        self.send_helix(endpoint, req)
+    }
+
+    /// Sends a GET request to the Helix `endpoint` and returns the body as
+    /// text, for the few endpoints which don't respond with JSON
+    pub fn helix_get_text<Q>(
+        &self,
+        endpoint: &Endpoint,
+        params: &Q,
+    ) -> TwitchResult<String>
+    where
+        Q: Serialize + ?Sized,
+    {
+        let req = self
+            .build_helix_request(endpoint.path, |url| self.client.get(url))
+            .query(params);
+        let body = self.send_helix_raw(endpoint, req)?;
+        String::from_utf8(body).map_err(|e| {
+            ApiError::from(io::Error::new(io::ErrorKind::InvalidData, e))
+        })
+    }
+
+    /// Overrides the rate limit points requests to the path of `endpoint`
+    /// are assumed to cost
+    ///
+    /// Useful if Twitch changes the cost of an endpoint before this crate
+    /// catches up.
+    pub fn override_cost(
+        &mut self,
+        endpoint: &Endpoint,
+        cost: u32,
+    )
+    {
+        self.costs.insert(endpoint.path, cost);
+    }
+
+    /// The rate limit points a request to `endpoint` costs
+    pub fn cost(
+        &self,
+        endpoint: &Endpoint,
+    ) -> u32
+    {
+        match self.costs.get(endpoint.path) {
+            Some(cost) => *cost,
+            None => endpoint.cost(),
+        }
+    }
+
+    /// The rate limit as reported by the last Helix response
+    pub fn rate_limit_status(&self) -> Option<RateLimitStatus> {
+        *self.rate_limit.lock().unwrap()
+    }
+
+    /// Calls `callback` with the bucket reported by every Helix response
+    /// carrying the rate limit headers, e.g. to log or alert when
+    /// `remaining` gets low
+    ///
+    /// The callback runs on the thread sending the request, keep it short.
+    pub fn on_rate_limit(
+        &mut self,
+        callback: RateLimitCallback,
+    )
+    {
+        self.on_rate_limit = Some(RateLimitHook(callback));
+    }
+
+    /// Sleeps until the rate limit bucket refills if it doesn't hold `cost`
+    /// points anymore, then deducts them
+    ///
+    /// The deduction only lasts until the next response reports the actual
+    /// bucket, but keeps a burst of requests from overdrawing it. The lock
+    /// isn't held while sleeping, so other threads sharing the client keep
+    /// going.
+    fn pace(
+        &self,
+        cost: u32,
+    )
+    {
+        let wait = match *self.rate_limit.lock().unwrap() {
+            Some(status) => {
+                let now = chrono::Utc::now().timestamp();
+                if status.remaining < cost && status.reset > now {
+                    Some(Duration::from_secs((status.reset - now) as u64))
+                }
+                else {
+                    None
+                }
+            }
+            None => return,
+        };
+        if let Some(wait) = wait {
+            thread::sleep(wait);
+        }
+
+        let mut rate_limit = self.rate_limit.lock().unwrap();
+        if let Some(ref mut status) = *rate_limit {
+            // A response received meanwhile may already report a new bucket
+            if wait.is_some() && status.reset <= chrono::Utc::now().timestamp()
+            {
+                status.remaining = status.limit;
+            }
+            status.remaining = status.remaining.saturating_sub(cost);
+        }
+    }
+
+    /// Folds over the items of every page of a paginated Helix endpoint
+    ///
+    /// Pages are fetched one at a time and dropped after folding, so huge
+    /// result sets can be counted or summed up with bounded memory. Add
+    /// `first` to `params` to fetch fewer, larger pages. Requests are paced
+    /// to wait for the rate limit bucket to refill once it's exhausted.
+    pub fn aggregate_pages<T, A, Q, F>(
+        &self,
+        endpoint: &Endpoint,
+        params: &Q,
+        init: A,
+        mut fold: F,
+    ) -> TwitchResult<A>
+    where
+        T: DeserializeOwned,
+        Q: Serialize + ?Sized,
+        F: FnMut(A, &T) -> A,
+    {
+        let (acc, _, _) = self.fold_pages(
+            endpoint,
+            params,
+            None,
+            usize::MAX,
+            init,
+            |acc, data: Vec<T>| data.iter().fold(acc, &mut fold),
+        )?;
+        Ok(acc)
+    }
+
+    /// Collects the items of a paginated Helix endpoint, sending at most
+    /// `max_requests` requests
+    ///
+    /// Starts after the cursor `after`, or at the first page if it's
+    /// `None`. If the budget runs out before the last page, `cursor` of the
+    /// result is set and can be passed as `after` to resume later. `total`
+    /// is the one reported by the last page.
+    pub fn collect_pages<T, Q>(
+        &self,
+        endpoint: &Endpoint,
+        params: &Q,
+        after: Option<&str>,
+        max_requests: usize,
+    ) -> TwitchResult<HelixPage<T>>
+    where
+        T: DeserializeOwned,
+        Q: Serialize + ?Sized,
+    {
+        let (data, cursor, total) = self.fold_pages(
+            endpoint,
+            params,
+            after.map(String::from),
+            max_requests,
+            Vec::new(),
+            |mut acc, data| {
+                acc.extend(data);
+                acc
             },
+        )?;
+        Ok(HelixPage {
+            data,
+            cursor,
+            total,
+        })
+    }
+
+    /// Folds the `data` of up to `max_requests` pages starting after
+    /// `cursor`, returns the cursor of the next page if any is left
+    fn fold_pages<T, A, Q, F>(
+        &self,
+        endpoint: &Endpoint,
+        params: &Q,
+        mut cursor: Option<String>,
+        max_requests: usize,
+        init: A,
+        mut fold: F,
+    ) -> TwitchResult<(A, Option<String>, Option<i64>)>
+    where
+        T: DeserializeOwned,
+        Q: Serialize + ?Sized,
+        F: FnMut(A, Vec<T>) -> A,
+    {
+        let mut acc = init;
+        let mut total = None;
+        for _ in 0..max_requests {
+            // Following the cursors Twitch returns always pages forward
+            let page = PageOpts {
+                after: cursor.clone(),
+                ..PageOpts::default()
+            };
+            let req = self
+                .build_helix_request(endpoint.path, |url| self.client.get(url))
+                .query(params)
+                .query(&page.params());
+            let r: HelixResponse<T> = self.send_helix(endpoint, req)?;
+            total = r.total;
+            cursor = match r.cursor() {
+                Some(next) if !r.data.is_empty() => Some(next.to_owned()),
+                _ => None,
+            };
+            acc = fold(acc, r.data);
+            if cursor.is_none() {
+                break;
+            }
         }
+        Ok((acc, cursor, total))
+    }
+
+    /// Sends a POST request with a JSON body to the Helix `endpoint`
+    pub fn helix_post<T, R, Q>(
+        &self,
+        endpoint: &Endpoint,
+        params: &Q,
+        data: &T,
+    ) -> TwitchResult<R>
+    where
+        T: Serialize + ?Sized,
+        R: DeserializeOwned,
+        Q: Serialize + ?Sized,
+    {
+        let req = self
+            .build_helix_request(endpoint.path, |url| self.client.post(url))
+            .query(params)
+            .json(data);
+        self.send_helix(endpoint, req)
+    }
+
+    /// Sends `data` as JSON body of a PATCH request to the Helix `endpoint`
+    pub fn helix_patch<T, R, Q>(
+        &self,
+        endpoint: &Endpoint,
+        params: &Q,
+        data: &T,
+    ) -> TwitchResult<R>
+    where
+        T: Serialize + ?Sized,
+        R: DeserializeOwned,
+        Q: Serialize + ?Sized,
+    {
+        let req = self
+            .build_helix_request(endpoint.path, |url| self.client.patch(url))
+            .query(params)
+            .json(data);
+        self.send_helix(endpoint, req)
+    }
+
+    /// Sends `data` as JSON body of a PUT request to the Helix `endpoint`
+    pub fn helix_put<T, R, Q>(
+        &self,
+        endpoint: &Endpoint,
+        params: &Q,
+        data: &T,
+    ) -> TwitchResult<R>
+    where
+        T: Serialize + ?Sized,
+        R: DeserializeOwned,
+        Q: Serialize + ?Sized,
+    {
+        let req = self
+            .build_helix_request(endpoint.path, |url| self.client.put(url))
+            .query(params)
+            .json(data);
+        self.send_helix(endpoint, req)
+    }
+
+    /// Sends a DELETE request to the Helix `endpoint`
+    pub fn helix_delete<T, Q>(
+        &self,
+        endpoint: &Endpoint,
+        params: &Q,
+    ) -> TwitchResult<T>
+    where
+        T: DeserializeOwned,
+        Q: Serialize + ?Sized,
+    {
+        let req = self
+            .build_helix_request(endpoint.path, |url| self.client.delete(url))
+            .query(params);
+        self.send_helix(endpoint, req)
     }
 }
 
 pub mod auth {
     use std::fmt;
 
+    use reqwest::Url;
+
     use super::TwitchClient;
+    use crate::helix::scope::HelixScope;
 
-    #[derive(Debug)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     #[allow(non_camel_case_types)]
     pub enum Scope {
         channel_check_subscription,
@@ -329,6 +1214,60 @@ pub mod auth {
         gen_auth_url(c, "code", redirect_url, scope, state)
     }
 
+    /// Builds the URL to send a user to for authorizing `scopes`, using the
+    /// authorization code flow
+    ///
+    /// `state` is sent back to `redirect_url` unchanged and should be a
+    /// random value the redirect handler checks against CSRF. With
+    /// `force_verify` the user is asked again even if they authorized the
+    /// client before.
+    pub fn helix_auth_url(
+        c: &TwitchClient,
+        redirect_url: &str,
+        scopes: &[HelixScope],
+        state: &str,
+        force_verify: bool,
+    ) -> String
+    {
+        let scope = scopes
+            .iter()
+            .map(|s| s.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut url = Url::parse(&c.id_url)
+            .and_then(|u| u.join("/oauth2/authorize"))
+            .expect("id_url is a valid URL");
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &c.cred.client_id)
+            .append_pair("redirect_uri", redirect_url)
+            .append_pair("scope", &scope)
+            .append_pair("state", state);
+        if force_verify {
+            url.query_pairs_mut().append_pair("force_verify", "true");
+        }
+        url.into()
+    }
+
+    /// Returns the scopes which were requested but not granted
+    ///
+    /// Scopes granted on top of the requested ones are ignored. Works with
+    /// both `Scope` and `helix::scope::HelixScope`, for the latter use
+    /// `TokenInfo::granted_scopes` to get the granted scopes of a token.
+    pub fn scope_diff<S: PartialEq + Clone>(
+        requested: &[S],
+        granted: &[S],
+    ) -> Vec<S>
+    {
+        let mut missing: Vec<S> = Vec::new();
+        for scope in requested {
+            if !granted.contains(scope) && !missing.contains(scope) {
+                missing.push(scope.clone());
+            }
+        }
+        missing
+    }
+
     pub fn imp_grant_flow(
         c: &TwitchClient,
         redirect_url: &str,
@@ -338,13 +1277,795 @@ pub mod auth {
     {
         gen_auth_url(c, "token", redirect_url, scope, state)
     }
+
+    /// Waits for the redirect of the authorization code flow on
+    /// `http://127.0.0.1:port` and returns the code
+    ///
+    /// For desktop apps, which pass that address as `redirect_url` of
+    /// `helix_auth_url`. The browser is shown a page saying the window can be
+    /// closed. Other requests, e.g. for the favicon, are answered with `404`,
+    /// connections failing or never sending a request are skipped.
+    /// Fails with `ApiError::InvalidInput` if the state doesn't match
+    /// `expected_state` or the user denied the authorization, and with an
+    /// `ApiError::IoError` of kind `TimedOut` once `timeout` passed.
+    #[cfg(feature = "local-callback")]
+    pub fn local_callback(
+        port: u16,
+        expected_state: &str,
+        timeout: std::time::Duration,
+    ) -> crate::response::TwitchResult<String>
+    {
+        use std::{
+            io::{
+                self,
+                BufRead,
+                BufReader,
+                Write,
+            },
+            net::TcpListener,
+            thread,
+            time::{
+                Duration,
+                Instant,
+            },
+        };
+
+        use crate::response::ApiError;
+
+        const PAGE: &str = "<!DOCTYPE html><html><body><p>You can close \
+                            this window now.</p></body></html>";
+
+        let listener = TcpListener::bind(("127.0.0.1", port))?;
+        listener.set_nonblocking(true)?;
+        let deadline = Instant::now() + timeout;
+        loop {
+            let mut stream = match listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    if Instant::now() >= deadline {
+                        return Err(ApiError::from(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            "the authorization wasn't completed in time",
+                        )));
+                    }
+                    thread::sleep(Duration::from_millis(50));
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            // Browsers preconnect without sending anything, so reading may
+            // not block past the deadline. Stray connections failing don't
+            // end the flow.
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let read_timeout = remaining.max(Duration::from_millis(1));
+            if stream.set_nonblocking(false).is_err()
+                || stream.set_read_timeout(Some(read_timeout)).is_err()
+            {
+                continue;
+            }
+            let mut line = String::new();
+            if BufReader::new(&stream).read_line(&mut line).is_err() {
+                continue;
+            }
+            let target = line.split(' ').nth(1).unwrap_or_default();
+            let url = Url::parse("http://127.0.0.1")
+                .and_then(|base| base.join(target))
+                .ok();
+            let param = |name: &str| {
+                url.as_ref().and_then(|u| {
+                    u.query_pairs()
+                        .find(|(k, _)| k == name)
+                        .map(|(_, v)| v.into_owned())
+                })
+            };
+
+            let (code, state, error) =
+                (param("code"), param("state"), param("error"));
+            if code.is_none() && error.is_none() {
+                let _ = stream.write_all(
+                    b"HTTP/1.1 404 Not Found\r\nConnection: close\r\n\
+                      Content-Length: 0\r\n\r\n",
+                );
+                continue;
+            }
+            // The browser closing early doesn't make the code invalid
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Type: \
+                 text/html\r\nContent-Length: {}\r\n\r\n{}",
+                PAGE.len(),
+                PAGE
+            );
+
+            if state.as_deref() != Some(expected_state) {
+                return Err(ApiError::invalid_input(
+                    "the state of the redirect doesn't match",
+                ));
+            }
+            return match (code, error) {
+                (Some(code), None) => Ok(code),
+                _ => Err(ApiError::invalid_input(
+                    "the user denied the authorization",
+                )),
+            };
+        }
+    }
 }
 
 #[cfg(test)]
 #[macro_use]
 extern crate pretty_assertions;
 
-// #[allow(dead_code)]
-// mod tests {
-//     include!("../credentials.rs");
-// }
+#[cfg(test)]
+mod client_tests {
+    use std::{
+        thread,
+        time::{
+            Duration,
+            Instant,
+        },
+    };
+
+    use reqwest::header::CONTENT_TYPE;
+
+    use crate::{
+        helix::{
+            scope::HelixScope,
+            Endpoint,
+            HelixPage,
+        },
+        mock,
+        new,
+        ratelimit::RateLimitStatus,
+        response::ApiError,
+        ConnectionProbe,
+    };
+
+    #[test]
+    fn scope_diff() {
+        use crate::auth::{
+            scope_diff,
+            Scope,
+        };
+
+        let requested =
+            [Scope::user_read, Scope::channel_read, Scope::user_read];
+        assert_eq!(scope_diff(&requested, &[Scope::user_read]), vec![
+            Scope::channel_read
+        ]);
+        assert!(scope_diff(&requested, &[
+            Scope::channel_read,
+            Scope::user_read,
+            Scope::chat_login,
+        ])
+        .is_empty());
+
+        let requested =
+            [HelixScope::UserReadEmail, HelixScope::ChannelReadGoals];
+        assert_eq!(scope_diff(&requested, &[HelixScope::BitsRead]), requested);
+    }
+
+    #[test]
+    fn helix_auth_url() {
+        use reqwest::Url;
+
+        use crate::auth::helix_auth_url;
+
+        let c = new(String::from("<clientid>"));
+        let url = helix_auth_url(
+            &c,
+            "http://localhost:3000/callback?x=1",
+            &[HelixScope::ChannelReadGoals, HelixScope::UserReadEmail],
+            "c3ab8aa609ea11e793ae92361f002671",
+            true,
+        );
+        assert!(url.starts_with("https://id.twitch.tv/oauth2/authorize?"));
+        assert!(
+            url.contains("scope=channel%3Aread%3Agoals+user%3Aread%3Aemail")
+        );
+
+        let url = Url::parse(&url).unwrap();
+        let query: Vec<_> = url.query_pairs().into_owned().collect();
+        assert_eq!(query, vec![
+            ("response_type".to_owned(), "code".to_owned()),
+            ("client_id".to_owned(), "<clientid>".to_owned()),
+            (
+                "redirect_uri".to_owned(),
+                "http://localhost:3000/callback?x=1".to_owned()
+            ),
+            (
+                "scope".to_owned(),
+                "channel:read:goals user:read:email".to_owned()
+            ),
+            (
+                "state".to_owned(),
+                "c3ab8aa609ea11e793ae92361f002671".to_owned()
+            ),
+            ("force_verify".to_owned(), "true".to_owned()),
+        ]);
+
+        let url = helix_auth_url(&c, "http://localhost", &[], "s", false);
+        assert!(!url.contains("force_verify"));
+    }
+
+    #[cfg(feature = "local-callback")]
+    #[test]
+    fn local_callback() {
+        use std::{
+            io::{
+                Read,
+                Write,
+            },
+            net::{
+                TcpListener,
+                TcpStream,
+            },
+            thread,
+        };
+
+        use crate::auth::local_callback;
+
+        fn redirect(
+            port: u16,
+            target: &'static str,
+        ) -> thread::JoinHandle<String>
+        {
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(100));
+                let mut s = TcpStream::connect(("127.0.0.1", port)).unwrap();
+                write!(s, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", target)
+                    .unwrap();
+                let mut response = String::new();
+                s.read_to_string(&mut response).unwrap();
+                response
+            })
+        }
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let browser = redirect(port, "/?code=gulfwdmys5lsm6qyz4xiz9q32l10&scope=bits%3Aread&state=c3ab8aa6");
+        let code =
+            local_callback(port, "c3ab8aa6", Duration::from_secs(5)).unwrap();
+        assert_eq!(code, "gulfwdmys5lsm6qyz4xiz9q32l10");
+        let page = browser.join().unwrap();
+        assert!(page.starts_with("HTTP/1.1 200 OK"));
+        assert!(page.contains("You can close this window now."));
+
+        let favicon = redirect(port, "/favicon.ico");
+        let browser = thread::spawn(move || {
+            favicon.join().unwrap();
+            redirect(port, "/?code=abc&state=forged").join().unwrap()
+        });
+        match local_callback(port, "c3ab8aa6", Duration::from_secs(5)) {
+            Err(ApiError::InvalidInput(_)) => (),
+            r => panic!("{:?}", r),
+        }
+        browser.join().unwrap();
+
+        let browser = redirect(port, "/?error=access_denied&state=c3ab8aa6");
+        match local_callback(port, "c3ab8aa6", Duration::from_secs(5)) {
+            Err(ApiError::InvalidInput(_)) => (),
+            r => panic!("{:?}", r),
+        }
+        browser.join().unwrap();
+
+        match local_callback(port, "c3ab8aa6", Duration::from_millis(100)) {
+            Err(ApiError::IoError(e)) => {
+                assert_eq!(e.kind(), std::io::ErrorKind::TimedOut)
+            }
+            r => panic!("{:?}", r),
+        }
+
+        // A connection closed without a request is skipped
+        let browser = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            drop(TcpStream::connect(("127.0.0.1", port)).unwrap());
+            redirect(port, "/?code=abc&state=c3ab8aa6").join().unwrap()
+        });
+        let code =
+            local_callback(port, "c3ab8aa6", Duration::from_secs(5)).unwrap();
+        assert_eq!(code, "abc");
+        browser.join().unwrap();
+
+        // A preconnect which never sends a request doesn't outlast the
+        // timeout
+        let preconnect = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            let s = TcpStream::connect(("127.0.0.1", port)).unwrap();
+            thread::sleep(Duration::from_secs(2));
+            drop(s);
+        });
+        let started = std::time::Instant::now();
+        match local_callback(port, "c3ab8aa6", Duration::from_millis(500)) {
+            Err(ApiError::IoError(e)) => {
+                assert_eq!(e.kind(), std::io::ErrorKind::TimedOut)
+            }
+            r => panic!("{:?}", r),
+        }
+        assert!(started.elapsed() < Duration::from_secs(2));
+        preconnect.join().unwrap();
+    }
+
+    #[test]
+    fn any_2xx_is_success() {
+        const ENDPOINT: Endpoint = Endpoint::new("/eventsub/subscriptions");
+        for status in &[200, 201, 202] {
+            let server = mock::serve(vec![
+                mock::response(*status, &[], r#"{"data": [{"id": "1"}]}"#),
+                mock::response(*status, &[], r#"{"access_token": "abc"}"#),
+            ]);
+            let mut c = new(String::from("<clientid>"));
+            c.helix_url = server.url.clone();
+
+            let r: serde_json::Value =
+                c.helix_post(&ENDPOINT, &(), &()).unwrap();
+            assert_eq!(r["data"][0]["id"], "1", "{}", status);
+            let url = format!("{}/oauth2/token", server.url);
+            let r: serde_json::Value = c.post_form(&url, &[]).unwrap();
+            assert_eq!(r["access_token"], "abc", "{}", status);
+            server.requests();
+        }
+
+        let server = mock::serve(vec![
+            mock::response(204, &[], ""),
+            mock::response(204, &[], ""),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+        c.helix_delete::<(), _>(&ENDPOINT, &[("id", "1")]).unwrap();
+        let url = format!("{}/oauth2/revoke", server.url);
+        c.post_form::<()>(&url, &[]).unwrap();
+        server.requests();
+    }
+
+    #[test]
+    fn post_form_content_type() {
+        let c = new(String::from("<clientid>"));
+        let r = c
+            .build_form_request("https://id.twitch.tv/oauth2/token", &[
+                ("client_id", "<clientid>"),
+                ("grant_type", "refresh_token"),
+            ])
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            r.headers()[CONTENT_TYPE],
+            "application/x-www-form-urlencoded"
+        );
+        assert_eq!(
+            r.body().and_then(|b| b.as_bytes()),
+            Some(&b"client_id=%3Cclientid%3E&grant_type=refresh_token"[..])
+        );
+    }
+
+    #[test]
+    fn http_config() {
+        let c = new(String::from("<clientid>"))
+            .with_tcp_keepalive(Duration::from_secs(60))
+            .with_pool_idle_timeout(Duration::from_secs(120));
+
+        assert_eq!(c.http.tcp_keepalive, Some(Duration::from_secs(60)));
+        assert_eq!(c.http.pool_idle_timeout, Some(Duration::from_secs(120)));
+    }
+
+    #[cfg(feature = "dangerous-testing")]
+    #[test]
+    fn danger_accept_invalid_certs() {
+        let c =
+            new(String::from("<clientid>")).danger_accept_invalid_certs(true);
+        assert!(c.http.accept_invalid_certs);
+    }
+
+    #[test]
+    fn max_response_bytes() {
+        const ENDPOINT: Endpoint = Endpoint::new("/test");
+        let body = r#"{"data":[{"id":"1"},{"id":"2"}]}"#;
+
+        let server = mock::serve(vec![
+            mock::response(200, &[], body),
+            mock::response(200, &[], body),
+            // Without a length header the limit is hit while reading
+            format!("HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n{}", body),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let c = c.with_max_response_bytes(body.len());
+        let r: serde_json::Value =
+            c.helix_get(&ENDPOINT, &[("id", "1")]).unwrap();
+        assert_eq!(r["data"][1]["id"], "2");
+
+        let c = c.with_max_response_bytes(16);
+        for _ in 0..2 {
+            match c.helix_get::<serde_json::Value, _>(&ENDPOINT, &[("id", "1")])
+            {
+                Err(ApiError::ResponseTooLarge(16)) => (),
+                r => panic!("{:?}", r),
+            }
+        }
+        server.requests();
+    }
+
+    #[test]
+    fn lenient_items() {
+        use crate::helix::{
+            streams::HelixStream,
+            HelixResponse,
+        };
+
+        const ENDPOINT: Endpoint = Endpoint::new("/streams");
+        let body = r#"{
+          "data": [
+            {"id": "1", "user_id": "1", "user_login": "a", "user_name": "A", "game_id": "", "game_name": "", "type": "live", "title": "", "viewer_count": 5, "started_at": "2021-03-10T15:04:21Z", "language": "en", "thumbnail_url": "", "is_mature": false},
+            {"id": "2", "viewer_count": "many"},
+            {"id": "3", "user_id": "3", "user_login": "c", "user_name": "C", "game_id": "", "game_name": "", "type": "live", "title": "", "viewer_count": 7, "started_at": "2021-03-10T15:04:21Z", "language": "en", "thumbnail_url": "", "is_mature": false}
+          ],
+          "pagination": {"cursor": "abc"}
+        }"#;
+        let server = mock::serve(vec![
+            mock::response(200, &[], body),
+            mock::response(200, &[], body),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        match c.helix_get::<HelixResponse<HelixStream>, _>(&ENDPOINT, &()) {
+            Err(ApiError::ParseError(_)) => (),
+            r => panic!("{:?}", r),
+        }
+
+        let c = c.with_lenient_items(true);
+        let r: HelixResponse<HelixStream> =
+            c.helix_get(&ENDPOINT, &()).unwrap();
+        let ids: Vec<_> = r.data.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["1", "3"]);
+        assert_eq!(r.cursor(), Some("abc"));
+
+        let skipped = c.take_skipped_items();
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].index, 1);
+        assert_eq!(skipped[0].item["id"], "2");
+        assert!(c.take_skipped_items().is_empty());
+        server.requests();
+    }
+
+    #[test]
+    fn forbidden() {
+        const ENDPOINT: Endpoint = Endpoint::new("/subscriptions")
+            .with_scopes(&[HelixScope::ChannelReadSubscriptions]);
+        let server = mock::serve(vec![mock::response(
+            403,
+            &[],
+            r#"{"error":"Forbidden","status":403,"message":"Missing scope: channel:read:subscriptions"}"#,
+        )]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        match c.helix_get::<serde_json::Value, _>(&ENDPOINT, &[("id", "1")]) {
+            Err(ApiError::Forbidden {
+                missing_scope: Some(HelixScope::ChannelReadSubscriptions),
+                expected,
+                response,
+            }) => {
+                assert_eq!(expected, vec![
+                    HelixScope::ChannelReadSubscriptions
+                ]);
+                assert_eq!(response.status, 403);
+            }
+            r => panic!("{:?}", r),
+        }
+        server.requests();
+    }
+
+    #[test]
+    fn check_credentials() {
+        let server = mock::serve(vec![
+            mock::response(
+                200,
+                &[],
+                r#"{
+                  "client_id": "wbmytr93xzw8zbg0p1izqyzzc5mbiz",
+                  "login": "twitchdev",
+                  "scopes": ["channel:read:subscriptions"],
+                  "user_id": "141981764",
+                  "expires_in": 5520838
+                }"#,
+            ),
+            mock::response(
+                401,
+                &[],
+                r#"{"status": 401, "message": "invalid access token"}"#,
+            ),
+        ]);
+        let mut c = new(String::from("wbmytr93xzw8zbg0p1izqyzzc5mbiz"));
+        c.id_url = server.url.clone();
+
+        match c.check_credentials() {
+            Err(ApiError::InvalidInput(_)) => (),
+            r => panic!("{:?}", r),
+        }
+
+        c.set_oauth_token("1ssjqsqfy6bads1ws7m03gras79zfr");
+        c.check_credentials().unwrap();
+        match c.check_credentials() {
+            Err(ApiError::TwitchError(e)) => assert_eq!(e.status, 401),
+            r => panic!("{:?}", r),
+        }
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with("GET /oauth2/validate "));
+        assert!(requests[0]
+            .to_ascii_lowercase()
+            .contains("authorization: oauth 1ssjqsqfy6bads1ws7m03gras79zfr"));
+    }
+
+    #[test]
+    fn connection_probe() {
+        let probe = ConnectionProbe {
+            first: Duration::from_millis(300),
+            second: Duration::from_millis(40),
+        };
+        assert!(probe.likely_reused());
+
+        let probe = ConnectionProbe {
+            first: Duration::from_millis(300),
+            second: Duration::from_millis(280),
+        };
+        assert!(!probe.likely_reused());
+    }
+
+    #[test]
+    fn cost() {
+        const ENDPOINT: Endpoint = Endpoint::new("/streams").with_cost(3);
+
+        let mut c = new(String::from("<clientid>"));
+        assert_eq!(c.cost(&Endpoint::new("/streams")), 1);
+        assert_eq!(c.cost(&ENDPOINT), 3);
+
+        c.override_cost(&ENDPOINT, 5);
+        assert_eq!(c.cost(&ENDPOINT), 5);
+    }
+
+    #[test]
+    fn pace_deducts_cost() {
+        let c = new(String::from("<clientid>"));
+        *c.rate_limit.lock().unwrap() = Some(RateLimitStatus {
+            limit: 800,
+            remaining: 10,
+            reset: 0,
+        });
+
+        c.pace(3);
+        assert_eq!(c.rate_limit_status().unwrap().remaining, 7);
+        // the reset lies in the past, so there is nothing to wait for
+        c.pace(10);
+        assert_eq!(c.rate_limit_status().unwrap().remaining, 0);
+    }
+
+    #[test]
+    fn pace_doesnt_block_other_threads() {
+        let c = new(String::from("<clientid>"));
+        *c.rate_limit.lock().unwrap() = Some(RateLimitStatus {
+            limit: 800,
+            remaining: 0,
+            reset: chrono::Utc::now().timestamp() + 2,
+        });
+
+        thread::scope(|s| {
+            let paced = s.spawn(|| c.pace(1));
+            thread::sleep(Duration::from_millis(100));
+            let start = Instant::now();
+            assert_eq!(c.rate_limit_status().unwrap().remaining, 0);
+            assert!(start.elapsed() < Duration::from_millis(100));
+            assert!(!paced.is_finished());
+            paced.join().unwrap();
+        });
+        assert_eq!(c.rate_limit_status().unwrap().remaining, 799);
+    }
+
+    #[test]
+    fn aggregate_pages() {
+        let server = mock::serve(vec![
+            mock::response(
+                200,
+                &[
+                    ("Ratelimit-Limit", "800"),
+                    ("Ratelimit-Remaining", "799"),
+                    ("Ratelimit-Reset", "1603307977"),
+                ],
+                r#"{"data":[{"viewer_count":10},{"viewer_count":5}],
+                    "pagination":{"cursor":"page2"}}"#,
+            ),
+            mock::response(
+                200,
+                &[],
+                r#"{"data":[{"viewer_count":1}],"pagination":{}}"#,
+            ),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let viewers = c
+            .aggregate_pages(
+                &Endpoint::new("/streams"),
+                &[("first", "2")],
+                0,
+                |acc, s: &serde_json::Value| {
+                    acc + s["viewer_count"].as_i64().unwrap()
+                },
+            )
+            .unwrap();
+        assert_eq!(viewers, 16);
+        // the second response carried no headers, so the first request's
+        // status minus the deducted point is left
+        assert_eq!(c.rate_limit_status().unwrap().remaining, 798);
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with("GET /streams?first=2 "));
+        assert!(requests[1].starts_with("GET /streams?first=2&after=page2 "));
+    }
+
+    #[test]
+    fn collect_pages() {
+        let page = |id: &str, cursor: &str| {
+            mock::response(
+                200,
+                &[],
+                &format!(
+                    r#"{{"data":[{{"id":"{}"}}],"pagination":{{"cursor":"{}"}},"total":3}}"#,
+                    id, cursor
+                ),
+            )
+        };
+        let server =
+            mock::serve(vec![page("1", "c1"), page("2", "c2"), page("3", "")]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let endpoint = Endpoint::new("/streams");
+        let first: HelixPage<serde_json::Value> = c
+            .collect_pages(&endpoint, &[("first", "1")], None, 2)
+            .unwrap();
+        assert_eq!(first.data.len(), 2);
+        assert_eq!(first.cursor.as_deref(), Some("c2"));
+        assert_eq!(first.total, Some(3));
+
+        let rest: HelixPage<serde_json::Value> = c
+            .collect_pages(
+                &endpoint,
+                &[("first", "1")],
+                first.cursor.as_deref(),
+                2,
+            )
+            .unwrap();
+        assert_eq!(rest.data[0]["id"], "3");
+        assert_eq!(rest.cursor, None);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        assert!(requests[1].starts_with("GET /streams?first=1&after=c1 "));
+        assert!(requests[2].starts_with("GET /streams?first=1&after=c2 "));
+    }
+
+    #[test]
+    fn client_factory() {
+        use crate::{
+            Credentials,
+            TwitchClientFactory,
+        };
+
+        let server = mock::serve(vec![
+            mock::response(200, &[], r#"{"data":[]}"#),
+            mock::response(200, &[], r#"{"data":[]}"#),
+        ]);
+        let factory =
+            TwitchClientFactory::new(reqwest::blocking::Client::new());
+        let endpoint = Endpoint::new("/streams");
+
+        for token in &["<token1>", "<token2>"] {
+            let mut cred = Credentials::new(String::from("<clientid>"));
+            cred.token = token.to_string();
+            let mut c = factory.for_credentials(cred);
+            c.helix_url = server.url.clone();
+            c.helix_get::<serde_json::Value, _>(&endpoint, &()).unwrap();
+        }
+
+        let requests: Vec<_> = server
+            .requests()
+            .iter()
+            .map(|r| r.to_ascii_lowercase())
+            .collect();
+        assert!(requests[0].contains("authorization: bearer <token1>"));
+        assert!(requests[1].contains("authorization: bearer <token2>"));
+        assert!(requests[1].contains("client-id: <clientid>"));
+    }
+
+    #[test]
+    fn client_id_only() {
+        let server = mock::serve(vec![
+            mock::response(200, &[], r#"{"data":[]}"#),
+            mock::response(200, &[], r#"{"data":[]}"#),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let endpoint = Endpoint::new("/streams");
+        c.helix_get::<serde_json::Value, _>(&endpoint, &()).unwrap();
+        c.set_oauth_token("<token>");
+        c.helix_get::<serde_json::Value, _>(&endpoint, &()).unwrap();
+
+        let requests: Vec<_> = server
+            .requests()
+            .into_iter()
+            .map(|r| r.to_ascii_lowercase())
+            .collect();
+        assert!(requests[0].contains("client-id: <clientid>\r\n"));
+        assert!(!requests[0].contains("authorization:"));
+        assert!(requests[1].contains("authorization: bearer <token>\r\n"));
+    }
+
+    #[test]
+    fn on_rate_limit() {
+        use std::sync::{
+            Arc,
+            Mutex,
+        };
+
+        let server = mock::serve(vec![
+            mock::response(
+                200,
+                &[
+                    ("Ratelimit-Limit", "800"),
+                    ("Ratelimit-Remaining", "3"),
+                    ("Ratelimit-Reset", "1603307977"),
+                ],
+                r#"{"data":[]}"#,
+            ),
+            mock::response(200, &[], r#"{"data":[]}"#),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        c.on_rate_limit(Box::new(move |status| {
+            sink.lock().unwrap().push(status.remaining)
+        }));
+
+        let endpoint = Endpoint::new("/streams");
+        c.helix_get::<serde_json::Value, _>(&endpoint, &()).unwrap();
+        c.helix_get::<serde_json::Value, _>(&endpoint, &()).unwrap();
+        server.requests();
+
+        // only the response carrying the headers is reported
+        assert_eq!(*seen.lock().unwrap(), vec![3]);
+    }
+}
+
+/// Credentials for the tests against the live API
+///
+/// They are read from the environment at compile time, so the tests build
+/// without any secrets and only the live ones fail if they are unset, e.g.
+/// `TWITCH_CLIENT_ID=... TWITCH_TOKEN=... cargo test`.
+#[cfg(test)]
+#[allow(dead_code)]
+mod tests {
+    macro_rules! env_or_empty {
+        ($name:expr) => {
+            match option_env!($name) {
+                Some(value) => value,
+                None => "",
+            }
+        };
+    }
+
+    pub const CLIENTID: &str = env_or_empty!("TWITCH_CLIENT_ID");
+    pub const TOKEN: &str = env_or_empty!("TWITCH_TOKEN");
+    pub const CHANID: &str = env_or_empty!("TWITCH_CHANNEL_ID");
+    pub const TESTCH: &str = "12826";
+}
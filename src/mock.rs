@@ -0,0 +1,120 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+//! A tiny HTTP server replaying canned responses, for tests which need to
+//! look at what goes over the wire
+
+use std::{
+    io::{
+        BufRead,
+        BufReader,
+        Read,
+        Write,
+    },
+    net::TcpListener,
+    thread::{
+        self,
+        JoinHandle,
+    },
+};
+
+use crate::response::{
+    ApiError,
+    ErrorResponse,
+};
+
+pub struct MockServer {
+    pub url: String,
+    handle: JoinHandle<Vec<String>>,
+}
+
+impl MockServer {
+    /// Waits until all responses were served and returns the received
+    /// requests (request line, headers and body)
+    pub fn requests(self) -> Vec<String> {
+        self.handle.join().unwrap()
+    }
+}
+
+/// Serves each of `responses` to one connection, in order
+pub fn serve(responses: Vec<String>) -> MockServer {
+    serve_routes(responses.into_iter().map(|r| ("", r)).collect())
+}
+
+/// Serves each response to one connection whose request path starts with
+/// the response's prefix, for requests which are sent concurrently
+pub fn serve_routes(routes: Vec<(&'static str, String)>) -> MockServer {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    let handle = thread::spawn(move || {
+        let mut routes = routes;
+        let mut requests = Vec::new();
+        while !routes.is_empty() {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+
+            let mut request = String::new();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let lower = line.to_ascii_lowercase();
+                if let Some(len) = lower.strip_prefix("content-length:") {
+                    content_length = len.trim().parse().unwrap();
+                }
+                request.push_str(&line);
+                if line == "\r\n" || line.is_empty() {
+                    break;
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            request.push_str(&String::from_utf8(body).unwrap());
+
+            let path = request.split(' ').nth(1).unwrap_or_default();
+            let idx = routes
+                .iter()
+                .position(|(prefix, _)| path.starts_with(prefix))
+                .unwrap_or_else(|| panic!("no response for {}", path));
+            let (_, response) = routes.remove(idx);
+            requests.push(request);
+
+            let mut stream = reader.into_inner();
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+        requests
+    });
+
+    MockServer { url, handle }
+}
+
+/// Builds a raw HTTP response
+pub fn response(
+    status: u16,
+    headers: &[(&str, &str)],
+    body: &str,
+) -> String
+{
+    let mut r = format!(
+        "HTTP/1.1 {} Mock\r\nConnection: close\r\nContent-Length: {}\r\n",
+        status,
+        body.len()
+    );
+    for (name, value) in headers {
+        r.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    r.push_str("\r\n");
+    r.push_str(body);
+    r
+}
+
+/// Builds the error of a Helix response with `status`, for tests of error
+/// handling which don't need a server
+pub fn twitch_error(status: i32) -> ApiError {
+    ApiError::from_twitch(ErrorResponse {
+        error: String::new(),
+        status,
+        message: String::new(),
+        cause: None,
+    })
+}
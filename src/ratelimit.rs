@@ -0,0 +1,136 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+//! Tracking of the Helix rate limit
+//!
+//! https://dev.twitch.tv/docs/api/guide#rate-limits
+
+use std::{
+    fmt,
+    time::Duration,
+};
+
+use chrono::{
+    TimeZone,
+    Utc,
+};
+use reqwest::header::HeaderMap;
+
+///////////////////////////////////////
+// RateLimitStatus
+///////////////////////////////////////
+/// The rate limit bucket as reported by the last Helix response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitStatus {
+    /// Points the bucket holds when full
+    pub limit: u32,
+    /// Points left in the bucket
+    pub remaining: u32,
+    /// Unix timestamp at which the bucket is full again
+    pub reset: i64,
+}
+
+impl RateLimitStatus {
+    /// Reads the `Ratelimit-*` headers, returns `None` if any is missing
+    pub fn from_headers(headers: &HeaderMap) -> Option<RateLimitStatus> {
+        fn parse<T: std::str::FromStr>(
+            headers: &HeaderMap,
+            name: &str,
+        ) -> Option<T>
+        {
+            headers.get(name)?.to_str().ok()?.parse().ok()
+        }
+
+        Some(RateLimitStatus {
+            limit: parse(headers, "ratelimit-limit")?,
+            remaining: parse(headers, "ratelimit-remaining")?,
+            reset: parse(headers, "ratelimit-reset")?,
+        })
+    }
+
+    /// How long until the bucket is full again, zero if `reset` already
+    /// passed
+    ///
+    /// `None` if `reset` isn't a valid timestamp.
+    pub fn reset_in(&self) -> Option<Duration> {
+        let reset = Utc
+            .timestamp_opt(self.reset, 0)
+            .single()
+            .filter(|_| self.reset > 0)?;
+        Some((reset - Utc::now()).to_std().unwrap_or_default())
+    }
+}
+
+///////////////////////////////////////
+// RateLimitCallback
+///////////////////////////////////////
+/// Called with the bucket reported by every Helix response carrying the
+/// rate limit headers, see `TwitchClient::on_rate_limit`
+pub type RateLimitCallback = Box<dyn Fn(&RateLimitStatus) + Send + Sync>;
+
+/// Wraps the callback so `TwitchClient` can keep deriving `Debug`
+pub(crate) struct RateLimitHook(pub(crate) RateLimitCallback);
+
+impl fmt::Debug for RateLimitHook {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result
+    {
+        f.write_str("RateLimitHook")
+    }
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::{
+        HeaderMap,
+        HeaderValue,
+    };
+
+    use std::time::Duration;
+
+    use chrono::Utc;
+
+    use super::RateLimitStatus;
+
+    #[test]
+    fn from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("Ratelimit-Limit", HeaderValue::from_static("800"));
+        headers.insert("Ratelimit-Remaining", HeaderValue::from_static("799"));
+        assert_eq!(RateLimitStatus::from_headers(&headers), None);
+
+        headers
+            .insert("Ratelimit-Reset", HeaderValue::from_static("1603307977"));
+        assert_eq!(
+            RateLimitStatus::from_headers(&headers),
+            Some(RateLimitStatus {
+                limit: 800,
+                remaining: 799,
+                reset: 1603307977,
+            })
+        );
+    }
+
+    #[test]
+    fn reset_in() {
+        let mut status = RateLimitStatus {
+            limit: 800,
+            remaining: 0,
+            reset: Utc::now().timestamp() + 10,
+        };
+        let wait = status.reset_in().unwrap();
+        assert!(wait > Duration::from_secs(8), "{:?}", wait);
+        assert!(wait <= Duration::from_secs(10), "{:?}", wait);
+
+        status.reset = Utc::now().timestamp() - 10;
+        assert_eq!(status.reset_in(), Some(Duration::from_secs(0)));
+
+        status.reset = 0;
+        assert_eq!(status.reset_in(), None);
+    }
+}
@@ -20,9 +20,15 @@ use std::{
     io,
 };
 
-use serde::Deserialize;
+use serde::{
+    de::DeserializeOwned,
+    Deserialize,
+};
+use serde_json::Value;
 use thiserror::Error;
 
+use crate::helix::scope::HelixScope;
+
 pub type TwitchResult<T> = Result<T, ApiError>;
 
 #[derive(Error, Debug)]
@@ -37,6 +43,28 @@ pub enum ApiError {
     TwitchError(ErrorResponse),
     #[error("Empty response")]
     EmptyResponse(EmptyResponse),
+    #[error("Expected a single result, got {0}")]
+    UnexpectedResultCount(usize),
+    #[error("Invalid input")]
+    InvalidInput(ValidationError),
+    #[error("Request rejected by Twitch")]
+    Rejected(Rejection),
+    #[error("Response body exceeds {0} bytes")]
+    ResponseTooLarge(usize),
+    /// The circuit breaker is open, try again in the given time
+    #[error("Circuit breaker open, retry in {0:?}")]
+    CircuitOpen(std::time::Duration),
+    /// Twitch answered `403 Forbidden`
+    ///
+    /// `missing_scope` is the scope the token lacks, if Twitch named one
+    /// this crate knows. `expected` are the scopes the endpoint declares,
+    /// any of which grants access to it.
+    #[error("Forbidden")]
+    Forbidden {
+        missing_scope: Option<HelixScope>,
+        expected: Vec<HelixScope>,
+        response: ErrorResponse,
+    },
 }
 
 impl From<reqwest::Error> for ApiError {
@@ -63,10 +91,51 @@ impl From<ErrorResponse> for ApiError {
     }
 }
 
+impl From<ValidationError> for ApiError {
+    fn from(err: ValidationError) -> ApiError {
+        ApiError::InvalidInput(err)
+    }
+}
+
 impl ApiError {
+    /// Wraps an error Twitch sent, `403` becomes `ApiError::Forbidden`
+    pub fn from_twitch(err: ErrorResponse) -> ApiError {
+        if err.status == 403 {
+            ApiError::Forbidden {
+                missing_scope: HelixScope::from_message(&err.message),
+                expected: Vec::new(),
+                response: err,
+            }
+        }
+        else {
+            ApiError::TwitchError(err)
+        }
+    }
+
+    /// Attaches the scopes of the endpoint a `403` came from
+    pub(crate) fn with_expected_scopes(
+        mut self,
+        scopes: &[HelixScope],
+    ) -> ApiError
+    {
+        if let ApiError::Forbidden {
+            ref mut expected, ..
+        } = self
+        {
+            *expected = scopes.to_vec();
+        }
+        self
+    }
+
     pub fn empty_response() -> ApiError {
         ApiError::EmptyResponse(EmptyResponse {})
     }
+
+    pub fn invalid_input(message: &str) -> ApiError {
+        ApiError::InvalidInput(ValidationError {
+            message: message.to_owned(),
+        })
+    }
 }
 
 ///////////////////////////////////////
@@ -78,7 +147,7 @@ pub struct ErrorResponse {
     pub status: i32,
     pub message: String,
     #[serde(skip_deserializing)]
-    pub cause: Option<Box<dyn std::error::Error>>,
+    pub cause: Option<Box<dyn std::error::Error + Send + Sync>>,
 }
 
 impl fmt::Display for ErrorResponse {
@@ -111,6 +180,122 @@ impl fmt::Display for EmptyResponse {
     }
 }
 
+///////////////////////////////////////
+// ValidationError
+///////////////////////////////////////
+/// Input rejected before sending it to Twitch
+#[derive(Debug)]
+pub struct ValidationError {
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result
+    {
+        write!(f, "ValidationError: {}", self.message)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+///////////////////////////////////////
+// Rejection
+///////////////////////////////////////
+/// Well-known reasons for Twitch refusing a request
+///
+/// Endpoints map the errors they document as distinct cases to an
+/// `ApiError::Rejected`, everything else stays an `ApiError::TwitchError`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Rejection {
+    /// The broadcaster is already raiding another channel
+    AlreadyRaiding,
+    /// The raid target doesn't exist or can't be raided, e.g. because it's
+    /// offline
+    RaidTargetUnavailable,
+    /// Only Turbo and Prime users can pick a hex color for their name
+    HexColorRequiresTurbo,
+    /// The user already has a warning they didn't acknowledge yet
+    AlreadyWarned,
+    /// Moderators and the broadcaster can't be warned
+    CannotWarnModerator,
+    /// The broadcaster has no VIP slots left
+    VipSlotsFull,
+    /// The user already is a VIP
+    AlreadyVip,
+    /// The user already is a moderator
+    AlreadyModerator,
+    /// VIPs have to lose their VIP status before they can be made a
+    /// moderator
+    UserIsVip,
+    /// Only partners and affiliates can run commercials
+    CommercialNotEligible,
+    /// A commercial is running or the last one ended too recently
+    CommercialRunning,
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result
+    {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+///////////////////////////////////////
+// SkippedItem
+///////////////////////////////////////
+/// An element of a response's `data` which failed to parse and was left
+/// out, see `TwitchClient::with_lenient_items`
+#[derive(Debug)]
+pub struct SkippedItem {
+    /// Position of the element in `data`
+    pub index: usize,
+    pub item: Value,
+    pub error: serde_json::Error,
+}
+
+/// Parses `body` as `T`, leaving out the elements of its `data` array `T`
+/// can't be parsed with
+///
+/// Only if parsing the whole body fails every element is tried on its own,
+/// so well-formed responses are parsed once.
+pub(crate) fn parse_lenient<T: DeserializeOwned>(
+    body: &[u8]
+) -> TwitchResult<(T, Vec<SkippedItem>)>
+{
+    let strict_err = match serde_json::from_slice(body) {
+        Ok(t) => return Ok((t, Vec::new())),
+        Err(e) => e,
+    };
+    let mut value: Value = serde_json::from_slice(body)?;
+    let data = match value.get_mut("data").map(Value::take) {
+        Some(Value::Array(data)) => data,
+        _ => return Err(strict_err.into()),
+    };
+
+    let mut kept = Vec::with_capacity(data.len());
+    let mut skipped = Vec::new();
+    for (index, item) in data.into_iter().enumerate() {
+        value["data"] = Value::Array(vec![item]);
+        match serde_json::from_value::<T>(value.clone()) {
+            Ok(_) => kept.extend(value["data"].as_array_mut().unwrap().pop()),
+            Err(error) => skipped.push(SkippedItem {
+                index,
+                item: value["data"][0].take(),
+                error,
+            }),
+        }
+    }
+    value["data"] = Value::Array(kept);
+    Ok((serde_json::from_value(value)?, skipped))
+}
+
 macro_rules! next_result {
     ($obj:ident, $url:expr, $serde:ty, $lst:ident) => {{
         let mut values_exist = false;
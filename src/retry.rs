@@ -0,0 +1,394 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+//! Retrying Helix requests which failed for transient reasons
+
+use std::{
+    io,
+    time::{
+        Duration,
+        SystemTime,
+        UNIX_EPOCH,
+    },
+};
+
+use reqwest::StatusCode;
+
+use crate::{
+    ratelimit::RateLimitStatus,
+    response::ApiError,
+};
+
+///////////////////////////////////////
+// RetryPolicy
+///////////////////////////////////////
+/// How often and how patiently `TwitchClient` retries failed requests
+///
+/// Errors for which `ApiError::is_retryable` holds are retried, which can
+/// be adjusted per status code with `retry_on` and `never_retry_on`.
+///
+/// Requests which change something, like sending a chat message, are
+/// retried as well. Retrying them after a `5xx` may repeat the change if
+/// Twitch applied it before failing, so only add such codes with care.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt, `0` disables retrying
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for every further retry
+    pub backoff: Duration,
+    /// Status codes which are retried on top of the retryable ones
+    pub retry_on: Vec<StatusCode>,
+    /// Status codes which are never retried, even if retryable
+    pub never_retry_on: Vec<StatusCode>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 3,
+            backoff: Duration::from_millis(500),
+            retry_on: Vec::new(),
+            never_retry_on: Vec::new(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The delay before retry number `retry`, starting at `0`
+    pub fn delay(
+        &self,
+        retry: u32,
+    ) -> Duration
+    {
+        self.backoff * 2u32.saturating_pow(retry)
+    }
+
+    /// The delay before retrying after `err`
+    ///
+    /// A `429 Too Many Requests` waits until `rate_limit` says the bucket is
+    /// full again, plus up to 100 milliseconds so clients sharing a bucket
+    /// don't all retry at once. Everything else, and a `429` without a
+    /// reset in the future, waits `delay(retry)`.
+    pub fn delay_for(
+        &self,
+        err: &ApiError,
+        retry: u32,
+        rate_limit: Option<RateLimitStatus>,
+    ) -> Duration
+    {
+        let reset_in = rate_limit
+            .and_then(|status| status.reset_in())
+            .filter(|wait| *wait > Duration::from_secs(0));
+        match (err.status(), reset_in) {
+            (Some(StatusCode::TOO_MANY_REQUESTS), Some(wait)) => {
+                wait + jitter()
+            }
+            _ => self.delay(retry),
+        }
+    }
+
+    /// Also retries `statuses`, e.g. a `409` Twitch is known to send for a
+    /// transient conflict
+    pub fn retry_on(
+        mut self,
+        statuses: &[StatusCode],
+    ) -> RetryPolicy
+    {
+        self.retry_on.extend_from_slice(statuses);
+        self
+    }
+
+    /// Never retries `statuses`, e.g. a `500` Twitch sends for a request it
+    /// will never accept
+    pub fn never_retry_on(
+        mut self,
+        statuses: &[StatusCode],
+    ) -> RetryPolicy
+    {
+        self.never_retry_on.extend_from_slice(statuses);
+        self
+    }
+
+    /// Whether `err` is retried under this policy, `never_retry_on` takes
+    /// precedence over `retry_on`
+    pub fn should_retry(
+        &self,
+        err: &ApiError,
+    ) -> bool
+    {
+        match err.status() {
+            Some(status) if self.never_retry_on.contains(&status) => false,
+            Some(status) if self.retry_on.contains(&status) => true,
+            _ => err.is_retryable(),
+        }
+    }
+}
+
+impl ApiError {
+    /// Whether sending the same request again might succeed
+    ///
+    /// That's the case for network failures like timeouts or reset
+    /// connections, server errors (`5xx`) and `429 Too Many Requests`.
+    /// Client errors like `400`, `401`, `403` or `404` and everything caught
+    /// before sending the request will fail again.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ApiError::ReqwestErr(e) => match e.status() {
+                Some(status) => is_retryable_status(status.as_u16()),
+                None => {
+                    e.is_timeout()
+                        || e.is_connect()
+                        || e.is_request()
+                        || e.is_body()
+                }
+            },
+            ApiError::IoError(e) => matches!(
+                e.kind(),
+                io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::BrokenPipe
+                    | io::ErrorKind::TimedOut
+                    | io::ErrorKind::Interrupted
+                    | io::ErrorKind::UnexpectedEof
+            ),
+            ApiError::TwitchError(e) => is_retryable_status(e.status as u16),
+            _ => false,
+        }
+    }
+}
+
+impl ApiError {
+    /// The HTTP status the error was answered with, if it came from Twitch
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            ApiError::ReqwestErr(e) => e.status(),
+            ApiError::TwitchError(e) => {
+                StatusCode::from_u16(e.status as u16).ok()
+            }
+            ApiError::Forbidden { .. } => Some(StatusCode::FORBIDDEN),
+            _ => None,
+        }
+    }
+}
+
+/// Up to 100 milliseconds, good enough to spread out retries without a
+/// random number generator
+fn jitter() -> Duration {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or_default();
+    Duration::from_millis(u64::from(nanos % 100))
+}
+
+fn is_retryable_status(status: u16) -> bool {
+    status == 429 || (500..600).contains(&status)
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        net::TcpListener,
+        time::Duration,
+    };
+
+    use chrono::Utc;
+    use reqwest::StatusCode;
+
+    use super::RetryPolicy;
+    use crate::{
+        helix::Endpoint,
+        mock::{
+            self,
+            twitch_error,
+        },
+        new,
+        ratelimit::RateLimitStatus,
+        response::{
+            ApiError,
+            Rejection,
+        },
+    };
+
+    #[test]
+    fn status_codes() {
+        for status in &[429, 500, 502, 503, 504] {
+            assert!(twitch_error(*status).is_retryable(), "{}", status);
+        }
+        for status in &[400, 401, 403, 404, 409, 422] {
+            assert!(!twitch_error(*status).is_retryable(), "{}", status);
+        }
+    }
+
+    #[test]
+    fn io_errors() {
+        let reset = io::Error::from(io::ErrorKind::ConnectionReset);
+        assert!(ApiError::from(reset).is_retryable());
+        let timeout = io::Error::from(io::ErrorKind::TimedOut);
+        assert!(ApiError::from(timeout).is_retryable());
+        let denied = io::Error::from(io::ErrorKind::PermissionDenied);
+        assert!(!ApiError::from(denied).is_retryable());
+    }
+
+    #[test]
+    fn connection_refused() {
+        let port = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        let err = reqwest::blocking::get(&format!("http://127.0.0.1:{}", port))
+            .unwrap_err();
+        assert!(ApiError::from(err).is_retryable());
+    }
+
+    #[test]
+    fn client_side_errors() {
+        assert!(!ApiError::invalid_input("").is_retryable());
+        assert!(!ApiError::empty_response().is_retryable());
+        assert!(!ApiError::Rejected(Rejection::AlreadyRaiding).is_retryable());
+        let parse = serde_json::from_str::<u32>("").unwrap_err();
+        assert!(!ApiError::from(parse).is_retryable());
+    }
+
+    #[test]
+    fn delay() {
+        let policy = RetryPolicy {
+            max_retries: 3,
+            backoff: Duration::from_millis(100),
+            ..RetryPolicy::default()
+        };
+        assert_eq!(policy.delay(0), Duration::from_millis(100));
+        assert_eq!(policy.delay(2), Duration::from_millis(400));
+    }
+
+    #[test]
+    fn retries_server_errors_only() {
+        const ENDPOINT: Endpoint = Endpoint::new("/streams");
+        let server = mock::serve(vec![
+            mock::response(503, &[], ""),
+            mock::response(200, &[], r#"{"data": []}"#),
+            mock::response(400, &[], ""),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+        let c = c.with_retry(RetryPolicy {
+            max_retries: 3,
+            backoff: Duration::from_millis(0),
+            ..RetryPolicy::default()
+        });
+
+        let r: serde_json::Value = c.helix_get(&ENDPOINT, &()).unwrap();
+        assert_eq!(r["data"], serde_json::json!([]));
+        match c.helix_get::<serde_json::Value, _>(&ENDPOINT, &()) {
+            Err(ApiError::TwitchError(e)) => assert_eq!(e.status, 400),
+            r => panic!("{:?}", r),
+        }
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[test]
+    fn custom_status_codes() {
+        let policy = RetryPolicy::default()
+            .retry_on(&[StatusCode::CONFLICT])
+            .never_retry_on(&[StatusCode::NOT_IMPLEMENTED]);
+
+        assert!(policy.should_retry(&twitch_error(409)));
+        assert!(!policy.should_retry(&twitch_error(501)));
+        assert!(policy.should_retry(&twitch_error(503)));
+        assert!(!policy.should_retry(&twitch_error(400)));
+        assert!(!policy.should_retry(&ApiError::invalid_input("")));
+
+        let both = RetryPolicy::default()
+            .retry_on(&[StatusCode::FORBIDDEN])
+            .never_retry_on(&[StatusCode::FORBIDDEN]);
+        assert!(!both.should_retry(&twitch_error(403)));
+    }
+
+    #[test]
+    fn retries_custom_status_codes() {
+        const ENDPOINT: Endpoint = Endpoint::new("/streams");
+        let server = mock::serve(vec![
+            mock::response(409, &[], ""),
+            mock::response(200, &[], r#"{"data": []}"#),
+            mock::response(503, &[], ""),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+        let c = c.with_retry(RetryPolicy {
+            backoff: Duration::from_millis(0),
+            ..RetryPolicy::default()
+                .retry_on(&[StatusCode::CONFLICT])
+                .never_retry_on(&[StatusCode::SERVICE_UNAVAILABLE])
+        });
+
+        c.helix_get::<serde_json::Value, _>(&ENDPOINT, &()).unwrap();
+        match c.helix_get::<serde_json::Value, _>(&ENDPOINT, &()) {
+            Err(ApiError::TwitchError(e)) => assert_eq!(e.status, 503),
+            r => panic!("{:?}", r),
+        }
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[test]
+    fn delay_for_rate_limit() {
+        let policy = RetryPolicy::default();
+        let status = |reset| RateLimitStatus {
+            limit: 800,
+            remaining: 0,
+            reset,
+        };
+        let soon = status(Utc::now().timestamp() + 5);
+
+        let wait = policy.delay_for(&twitch_error(429), 0, Some(soon));
+        assert!(wait > Duration::from_secs(3), "{:?}", wait);
+        assert!(wait <= Duration::from_millis(5100), "{:?}", wait);
+
+        // Server errors and stale or missing resets back off exponentially
+        assert_eq!(
+            policy.delay_for(&twitch_error(503), 1, Some(soon)),
+            Duration::from_millis(1000)
+        );
+        let past = status(Utc::now().timestamp() - 5);
+        assert_eq!(
+            policy.delay_for(&twitch_error(429), 0, Some(past)),
+            policy.backoff
+        );
+        assert_eq!(
+            policy.delay_for(&twitch_error(429), 0, None),
+            policy.backoff
+        );
+    }
+
+    #[test]
+    fn retries_429_after_reset() {
+        const ENDPOINT: Endpoint = Endpoint::new("/streams");
+        let reset = (Utc::now().timestamp() + 1).to_string();
+        let server = mock::serve(vec![
+            mock::response(
+                429,
+                &[
+                    ("Ratelimit-Limit", "800"),
+                    ("Ratelimit-Remaining", "0"),
+                    ("Ratelimit-Reset", &reset),
+                ],
+                "",
+            ),
+            mock::response(200, &[], r#"{"data": []}"#),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.helix_url = server.url.clone();
+        let c = c.with_retry(RetryPolicy {
+            backoff: Duration::from_secs(30),
+            ..RetryPolicy::default()
+        });
+
+        c.helix_get::<serde_json::Value, _>(&ENDPOINT, &()).unwrap();
+        // Retried after the reset, long before the backoff would have ended
+        assert!(Utc::now().timestamp() >= reset.parse::<i64>().unwrap());
+        assert_eq!(server.requests().len(), 2);
+    }
+}
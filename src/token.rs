@@ -0,0 +1,655 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+//! OAuth tokens and persisting them across restarts
+//!
+//! https://dev.twitch.tv/docs/authentication
+
+use std::{
+    fmt,
+    fs,
+    io,
+    path::PathBuf,
+};
+
+use chrono::prelude::*;
+use reqwest::header::AUTHORIZATION;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    helix::{
+        scope::HelixScope,
+        Endpoint,
+    },
+    response::{
+        ApiError,
+        TwitchResult,
+    },
+    TwitchClient,
+};
+
+///////////////////////////////////////
+// Token
+///////////////////////////////////////
+/// An access token together with everything Twitch sent along with it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub access_token: String,
+    /// `None` for app access tokens, which can't be refreshed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// `None` if Twitch didn't say when the token expires
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    pub token_type: String,
+}
+
+impl Token {
+    pub fn has_scope(
+        &self,
+        scope: HelixScope,
+    ) -> bool
+    {
+        self.scopes.iter().any(|s| s == scope.as_str())
+    }
+
+    /// Whether `helix::users::get_self` returns the email of the user
+    pub fn has_email_scope(&self) -> bool {
+        self.has_scope(HelixScope::UserReadEmail)
+    }
+
+    /// Whether the token is expired or expires within `margin`
+    pub fn expires_within(
+        &self,
+        margin: chrono::Duration,
+    ) -> bool
+    {
+        match self.expires_at {
+            Some(at) => at - margin <= Utc::now(),
+            None => false,
+        }
+    }
+}
+
+/// The body of a successful response of `id.twitch.tv/oauth2/token`
+#[derive(Deserialize, Debug)]
+pub(crate) struct TokenResponse {
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_in: Option<i64>,
+    #[serde(default)]
+    scope: Option<Vec<String>>,
+    token_type: String,
+}
+
+impl From<TokenResponse> for Token {
+    fn from(r: TokenResponse) -> Token {
+        Token {
+            access_token: r.access_token,
+            refresh_token: r.refresh_token,
+            scopes: r.scope.unwrap_or_default(),
+            expires_at: r
+                .expires_in
+                .map(|secs| Utc::now() + chrono::Duration::seconds(secs)),
+            token_type: r.token_type,
+        }
+    }
+}
+
+///////////////////////////////////////
+// TokenStore
+///////////////////////////////////////
+/// Storage the client persists its token to whenever it changes
+///
+/// Implement this to keep tokens in a database or the system keyring,
+/// `FileTokenStore` writes them to a TOML file.
+pub trait TokenStore: fmt::Debug + Send + Sync {
+    /// Loads the stored token, `None` if nothing was stored yet
+    fn load(&self) -> TwitchResult<Option<Token>>;
+
+    fn save(
+        &self,
+        token: &Token,
+    ) -> TwitchResult<()>;
+}
+
+/// Stores the token as a TOML file, like `Credentials::write_to_file`
+///
+/// The file holds the access and refresh token in plain text, so make sure
+/// it is only readable by the user running the app.
+#[derive(Debug, Clone)]
+pub struct FileTokenStore {
+    pub path: PathBuf,
+}
+
+impl FileTokenStore {
+    pub fn new<P: Into<PathBuf>>(path: P) -> FileTokenStore {
+        FileTokenStore { path: path.into() }
+    }
+}
+
+impl TokenStore for FileTokenStore {
+    fn load(&self) -> TwitchResult<Option<Token>> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+                return Ok(None)
+            }
+            Err(e) => return Err(e.into()),
+        };
+        toml::from_str(&content)
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e).into())
+    }
+
+    fn save(
+        &self,
+        token: &Token,
+    ) -> TwitchResult<()>
+    {
+        let content = toml::to_string(token)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(&self.path, content)?;
+        Ok(())
+    }
+}
+
+///////////////////////////////////////
+// TokenInfo
+///////////////////////////////////////
+/// What Twitch knows about a token, see `TwitchClient::validate_token`
+#[derive(Deserialize, Debug, Clone)]
+pub struct TokenInfo {
+    pub client_id: String,
+    /// `None` for app access tokens
+    pub login: Option<String>,
+    /// `None` for app access tokens
+    pub user_id: Option<String>,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// Seconds until the token expires
+    pub expires_in: i64,
+}
+
+impl TokenInfo {
+    /// The scopes of the token this crate knows, unknown ones are skipped
+    pub fn granted_scopes(&self) -> Vec<HelixScope> {
+        self.scopes.iter().filter_map(|s| s.parse().ok()).collect()
+    }
+
+    pub fn has_scope(
+        &self,
+        scope: HelixScope,
+    ) -> bool
+    {
+        self.scopes.iter().any(|s| s == scope.as_str())
+    }
+
+    /// Whether `helix::users::get_self` returns the email of the user
+    pub fn has_email_scope(&self) -> bool {
+        self.has_scope(HelixScope::UserReadEmail)
+    }
+
+    /// Whether this is a user or an app access token
+    pub fn token_type(&self) -> TokenType {
+        match (&self.user_id, &self.login) {
+            (Some(user_id), Some(login)) => TokenType::User {
+                user_id: user_id.clone(),
+                login: login.clone(),
+            },
+            _ => TokenType::App,
+        }
+    }
+}
+
+/// Who a token acts as
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenType {
+    /// A user access token, acting as the user who authorized the app
+    User { user_id: String, login: String },
+    /// An app access token, e.g. from the client credentials flow
+    App,
+}
+
+impl TwitchClient {
+    /// Asks Twitch whether the current token is still valid
+    ///
+    /// Twitch requires apps to validate their tokens on startup and then
+    /// hourly. An invalid token fails with a `401` `ApiError::TwitchError`.
+    pub fn validate_token(&self) -> TwitchResult<TokenInfo> {
+        let r = self
+            .client
+            .get(&format!("{}/oauth2/validate", self.id_url))
+            .header(AUTHORIZATION, format!("OAuth {}", self.cred.token))
+            .send()?;
+        self.helix_response(r)
+    }
+
+    /// Asks Twitch whether the current token is a user or an app access
+    /// token
+    ///
+    /// Lets code needing a user token fail fast with a clear message
+    /// instead of a `401` from the endpoint. An invalid token fails like
+    /// `validate_token`.
+    pub fn token_type(&self) -> TwitchResult<TokenType> {
+        Ok(self.validate_token()?.token_type())
+    }
+
+    /// Whether the current token has all of `scopes`, e.g. to only show
+    /// actions the user authorized
+    ///
+    /// The scopes of a token are validated once and remembered until the
+    /// token changes. Fails like `validate_token` if the token can't be
+    /// validated, use `missing_scopes` to find out which scopes are
+    /// missing. Note that the scopes of an `Endpoint` are alternatives,
+    /// check those with `can_call_endpoint`.
+    pub fn can_call(
+        &self,
+        scopes: &[HelixScope],
+    ) -> TwitchResult<bool>
+    {
+        Ok(self.missing_scopes(scopes)?.is_empty())
+    }
+
+    /// Whether the current token has one of the scopes granting access to
+    /// `endpoint`, see `can_call`
+    pub fn can_call_endpoint(
+        &self,
+        endpoint: &Endpoint,
+    ) -> TwitchResult<bool>
+    {
+        if endpoint.scopes().is_empty() {
+            return Ok(true);
+        }
+        let granted = self.validated_scopes()?;
+        Ok(endpoint.scopes().iter().any(|s| granted.contains(s)))
+    }
+
+    /// The scopes of `scopes` the current token lacks, see `can_call`
+    pub fn missing_scopes(
+        &self,
+        scopes: &[HelixScope],
+    ) -> TwitchResult<Vec<HelixScope>>
+    {
+        Ok(crate::auth::scope_diff(scopes, &self.validated_scopes()?))
+    }
+
+    fn validated_scopes(&self) -> TwitchResult<Vec<HelixScope>> {
+        let mut cached = self.granted_scopes.lock().unwrap();
+        match *cached {
+            Some((ref token, ref scopes)) if *token == self.cred.token => {
+                Ok(scopes.clone())
+            }
+            _ => {
+                let scopes = self.validate_token()?.granted_scopes();
+                *cached = Some((self.cred.token.clone(), scopes.clone()));
+                Ok(scopes)
+            }
+        }
+    }
+
+    /// Sets the store the client persists its token to
+    ///
+    /// If the store already holds a token, the client starts using it.
+    pub fn set_token_store(
+        &mut self,
+        store: Box<dyn TokenStore>,
+    ) -> TwitchResult<()>
+    {
+        if let Some(token) = store.load()? {
+            self.cred.token = token.access_token.clone();
+            self.token = Some(token);
+        }
+        self.token_store = Some(store);
+        Ok(())
+    }
+
+    /// Starts using `token` and saves it to the token store, if there is one
+    pub fn set_token(
+        &mut self,
+        token: Token,
+    ) -> TwitchResult<()>
+    {
+        if let Some(ref store) = self.token_store {
+            store.save(&token)?;
+        }
+        self.cred.token = token.access_token.clone();
+        self.token = Some(token);
+        Ok(())
+    }
+
+    /// Sets the client secret needed by `ensure_app_token`
+    pub fn set_client_secret(
+        &mut self,
+        client_secret: &str,
+    )
+    {
+        self.client_secret = Some(client_secret.to_owned());
+    }
+
+    /// Gets an app access token if there is no token or it expires within
+    /// a minute
+    ///
+    /// Apps which only use endpoints that don't need a user can call this
+    /// before every request, a new token is only requested when needed. A
+    /// token set with `set_oauth_token` is assumed to be valid. Needs the
+    /// client secret, see `set_client_secret`.
+    ///
+    /// To share a client between threads, put it behind a `Mutex` or
+    /// `RwLock`: this borrows the client mutably, so only one caller at a
+    /// time can request a token.
+    pub fn ensure_app_token(&mut self) -> TwitchResult<()> {
+        let valid = match self.token {
+            Some(ref token) => {
+                !token.expires_within(chrono::Duration::minutes(1))
+            }
+            None => !self.cred.token.is_empty(),
+        };
+        if valid {
+            return Ok(());
+        }
+
+        let client_secret = self.client_secret.clone().ok_or_else(|| {
+            ApiError::invalid_input(
+                "an app access token needs the client secret",
+            )
+        })?;
+        let url = format!("{}/oauth2/token", self.id_url);
+        let r: TokenResponse = self.post_form(&url, &[
+            ("client_id", &self.cred.client_id),
+            ("client_secret", &client_secret),
+            ("grant_type", "client_credentials"),
+        ])?;
+        self.set_token(Token::from(r))
+    }
+
+    /// The token set with `set_token` or loaded from the token store
+    pub fn token(&self) -> Option<&Token> {
+        self.token.as_ref()
+    }
+
+    /// Exchanges the refresh token of the current token for a new token
+    ///
+    /// The new token is used right away and saved to the token store.
+    /// Fails with `ApiError::InvalidInput` if there is no refresh token.
+    pub fn refresh_token(
+        &mut self,
+        client_secret: &str,
+    ) -> TwitchResult<&Token>
+    {
+        let refresh_token = self
+            .token
+            .as_ref()
+            .and_then(|t| t.refresh_token.clone())
+            .ok_or_else(|| {
+                ApiError::invalid_input("there is no refresh token to refresh")
+            })?;
+
+        let url = format!("{}/oauth2/token", self.id_url);
+        let r: TokenResponse = self.post_form(&url, &[
+            ("client_id", &self.cred.client_id),
+            ("client_secret", client_secret),
+            ("grant_type", "refresh_token"),
+            ("refresh_token", &refresh_token),
+        ])?;
+        self.set_token(Token::from(r))?;
+        Ok(self.token.as_ref().unwrap())
+    }
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        Mutex,
+    };
+
+    use chrono::prelude::*;
+
+    use super::{
+        FileTokenStore,
+        Token,
+        TokenStore,
+        TokenType,
+    };
+    use crate::{
+        helix::{
+            moderation,
+            scope::HelixScope,
+            streams,
+        },
+        mock,
+        new,
+        response::{
+            ApiError,
+            TwitchResult,
+        },
+    };
+
+    fn token() -> Token {
+        Token {
+            access_token: "1ssjqsqfy6bads1ws7m03gras79zfr".to_owned(),
+            refresh_token: Some(
+                "eyJfMzUtNDU0OC4MWYwLTQ5MDY5ODY4NGNlMSJ9".to_owned(),
+            ),
+            scopes: vec!["channel:read:subscriptions".to_owned()],
+            expires_at: Some(Utc.timestamp_opt(1_600_000_000, 0).unwrap()),
+            token_type: "bearer".to_owned(),
+        }
+    }
+
+    #[derive(Debug, Default, Clone)]
+    struct MemoryStore(Arc<Mutex<Vec<Token>>>);
+
+    impl TokenStore for MemoryStore {
+        fn load(&self) -> TwitchResult<Option<Token>> {
+            Ok(self.0.lock().unwrap().last().cloned())
+        }
+
+        fn save(
+            &self,
+            token: &Token,
+        ) -> TwitchResult<()>
+        {
+            self.0.lock().unwrap().push(token.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn ensure_app_token() {
+        let server = mock::serve(vec![mock::response(
+            200,
+            &[],
+            r#"{
+              "access_token": "jostpf5q0uzmxmkba9iyug38kjtgh",
+              "expires_in": 5011271,
+              "token_type": "bearer"
+            }"#,
+        )]);
+        let mut c = new(String::from("<clientid>"));
+        c.id_url = server.url.clone();
+
+        match c.ensure_app_token() {
+            Err(ApiError::InvalidInput(_)) => (),
+            r => panic!("{:?}", r),
+        }
+
+        c.set_client_secret("<secret>");
+        c.ensure_app_token().unwrap();
+        // The cached token is still valid, so no second request is sent
+        c.ensure_app_token().unwrap();
+        let token = c.token().unwrap();
+        assert_eq!(token.access_token, "jostpf5q0uzmxmkba9iyug38kjtgh");
+        assert!(token.refresh_token.is_none());
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].ends_with(
+            "client_id=%3Cclientid%3E&client_secret=%3Csecret%3E&grant_type=client_credentials"
+        ));
+    }
+
+    #[test]
+    fn ensure_app_token_keeps_set_token() {
+        let mut c = new(String::from("<clientid>"));
+        c.set_oauth_token("1ssjqsqfy6bads1ws7m03gras79zfr");
+        c.ensure_app_token().unwrap();
+        assert!(c.token().is_none());
+    }
+
+    #[test]
+    fn has_email_scope() {
+        let mut token = token();
+        assert!(!token.has_email_scope());
+        token.scopes.push("user:read:email".to_owned());
+        assert!(token.has_email_scope());
+    }
+
+    #[test]
+    fn file_store() {
+        let path = std::env::temp_dir()
+            .join(format!("libtwitch-rs-token-{}.toml", std::process::id()));
+        let store = FileTokenStore::new(&path);
+        assert_eq!(store.load().unwrap(), None);
+
+        store.save(&token()).unwrap();
+        assert_eq!(store.load().unwrap(), Some(token()));
+
+        let app_token = Token {
+            refresh_token: None,
+            expires_at: None,
+            ..token()
+        };
+        store.save(&app_token).unwrap();
+        assert_eq!(store.load().unwrap(), Some(app_token));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn refresh_persists() {
+        let server = mock::serve(vec![mock::response(
+            200,
+            &[],
+            r#"{
+              "access_token": "1ssjqsqfy6bads1ws7m03gras79zfr",
+              "refresh_token": "eyJfMzUtNDU0OC4MWYwLTQ5MDY5ODY4NGNlMSJ9",
+              "expires_in": 14124,
+              "scope": ["channel:read:subscriptions"],
+              "token_type": "bearer"
+            }"#,
+        )]);
+        let mut c = new(String::from("<clientid>"));
+        c.id_url = server.url.clone();
+
+        let store = MemoryStore::default();
+        store
+            .save(&Token {
+                access_token: "expired".to_owned(),
+                ..token()
+            })
+            .unwrap();
+        c.set_token_store(Box::new(store.clone())).unwrap();
+        assert_eq!(c.token().unwrap().access_token, "expired");
+
+        let refreshed = c.refresh_token("<secret>").unwrap().clone();
+        assert_eq!(refreshed.access_token, "1ssjqsqfy6bads1ws7m03gras79zfr");
+        assert!(!refreshed.expires_within(chrono::Duration::hours(3)));
+        assert!(refreshed.expires_within(chrono::Duration::hours(4)));
+        assert_eq!(c.cred.token, refreshed.access_token);
+        assert_eq!(store.load().unwrap(), Some(refreshed));
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with("POST /oauth2/token "));
+        assert!(requests[0].ends_with(
+            "client_id=%3Cclientid%3E&client_secret=%3Csecret%3E&grant_type=refresh_token&refresh_token=eyJfMzUtNDU0OC4MWYwLTQ5MDY5ODY4NGNlMSJ9"
+        ));
+    }
+
+    #[test]
+    fn token_type() {
+        let server = mock::serve(vec![
+            mock::response(
+                200,
+                &[],
+                r#"{
+                  "client_id": "wbmytr93xzw8zbg0p1izqyzzc5mbiz",
+                  "login": "twitchdev",
+                  "scopes": ["channel:read:subscriptions"],
+                  "user_id": "141981764",
+                  "expires_in": 5520838
+                }"#,
+            ),
+            mock::response(
+                200,
+                &[],
+                r#"{
+                  "client_id": "wbmytr93xzw8zbg0p1izqyzzc5mbiz",
+                  "scopes": [],
+                  "expires_in": 5011271
+                }"#,
+            ),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.id_url = server.url.clone();
+
+        assert_eq!(c.token_type().unwrap(), TokenType::User {
+            user_id: "141981764".to_owned(),
+            login: "twitchdev".to_owned(),
+        });
+        assert_eq!(c.token_type().unwrap(), TokenType::App);
+    }
+
+    #[test]
+    fn can_call() {
+        let server = mock::serve(vec![
+            mock::response(
+                200,
+                &[],
+                r#"{
+                  "client_id": "wbmytr93xzw8zbg0p1izqyzzc5mbiz",
+                  "login": "twitchdev",
+                  "scopes": ["moderator:read:blocked_terms", "user:read:email"],
+                  "user_id": "141981764",
+                  "expires_in": 5520838
+                }"#,
+            ),
+            mock::response(
+                401,
+                &[],
+                r#"{"status": 401, "message": "invalid access token"}"#,
+            ),
+        ]);
+        let mut c = new(String::from("<clientid>"));
+        c.id_url = server.url.clone();
+        c.set_oauth_token("<token>");
+
+        assert!(c.can_call(&[HelixScope::UserReadEmail]).unwrap());
+        assert!(!c
+            .can_call(&[HelixScope::UserReadEmail, HelixScope::BitsRead])
+            .unwrap());
+        assert_eq!(c.missing_scopes(&[HelixScope::BitsRead]).unwrap(), vec![
+            HelixScope::BitsRead
+        ]);
+        assert!(c.can_call_endpoint(&moderation::BLOCKED_TERMS).unwrap());
+        assert!(c.can_call_endpoint(&streams::GET).unwrap());
+        assert!(!c.can_call_endpoint(&moderation::WARN_USER).unwrap());
+
+        // A new token is validated again
+        c.set_oauth_token("<other>");
+        match c.can_call(&[HelixScope::UserReadEmail]) {
+            Err(ApiError::TwitchError(e)) => assert_eq!(e.status, 401),
+            r => panic!("{:?}", r),
+        }
+        assert_eq!(server.requests().len(), 2);
+    }
+}
@@ -0,0 +1,67 @@
+// Copyright 2020 the libtwitch-rs authors. See copying.md for legal info.
+
+//! Helpers for preparing user input before sending it to Twitch
+
+use crate::response::ValidationError;
+
+/// Turns a login as users type it, e.g. `@User_Name `, into the form Twitch
+/// accepts
+///
+/// Strips a leading `@` and surrounding whitespace and lowercases the rest,
+/// which then has to be 4 to 25 ASCII letters, digits or underscores.
+pub fn normalize_login(input: &str) -> Result<String, ValidationError> {
+    let login = input.trim();
+    let login = login
+        .strip_prefix('@')
+        .unwrap_or(login)
+        .to_ascii_lowercase();
+
+    let invalid = |message: &str| ValidationError {
+        message: format!("{:?} isn't a valid login: {}", input, message),
+    };
+    if !(4..=25).contains(&login.len()) {
+        return Err(invalid("logins are 4 to 25 characters long"));
+    }
+    if !login
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b == b'_')
+    {
+        return Err(invalid(
+            "logins only contain letters, digits and underscores",
+        ));
+    }
+    Ok(login)
+}
+
+///////////////////////////////////////
+// TESTS
+///////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::normalize_login;
+
+    #[test]
+    fn normalize() {
+        assert_eq!(normalize_login("@User_Name").unwrap(), "user_name");
+        assert_eq!(normalize_login("  TwitchDev\n").unwrap(), "twitchdev");
+        assert_eq!(normalize_login(&"a".repeat(25)).unwrap(), "a".repeat(25));
+    }
+
+    #[test]
+    fn reject() {
+        for input in &[
+            "",
+            "@",
+            "   ",
+            "abc",
+            &"a".repeat(26),
+            "user name",
+            "user-name",
+            "@@username",
+            "usérname",
+        ] {
+            assert!(normalize_login(input).is_err(), "{:?}", input);
+        }
+    }
+}
@@ -27,3 +27,11 @@ fn credential_import() {
     assert_eq!(cred.client_id, "13211542".to_string());
     assert_eq!(cred.token, "OAuth:1839213891u389u1389183139".to_string());
 }
+
+#[test]
+fn credential_import_fallible() {
+    let cred =
+        Credentials::from_file("tests/example_credentials.toml").unwrap();
+    assert_eq!(cred.client_id, "13211542".to_string());
+    assert!(Credentials::from_file("tests/missing_credentials.toml").is_err());
+}